@@ -3,20 +3,75 @@
 # Example: loop-track copilot "how to parse JSON in rust"
 
 LOOP_TRACKER_PORT=19532
+OUTPUT_TAIL_LINES=20
+HEARTBEAT_INTERVAL=15
 COMMAND="$*"
 TITLE="CLI: $COMMAND"
 
+# Escape a string for embedding in a JSON string literal
+json_escape() {
+  printf '%s' "$1" | awk '
+    BEGIN { ORS = "" }
+    {
+      gsub(/\033\[[0-9;?]*[A-Za-z]/, "")
+      gsub(/\\/, "\\\\\\\\")
+      gsub(/"/, "\\\"")
+      gsub(/\t/, "\\t")
+      gsub(/\r/, "")
+      gsub(/[\001-\037]/, "")
+      if (NR > 1) print "\\n"
+      print
+    }'
+}
+
+now_iso() {
+  date -u +"%Y-%m-%dT%H:%M:%SZ"
+}
+
 update_session_status() {
   local session_id="$1"
   local status="$2"
+  local exit_code="$3"
+  local output_tail="$4"
 
   if [ -n "$session_id" ] && [ "$session_id" != "error" ]; then
+    local body="{\"status\": \"$status\""
+    if [ -n "$exit_code" ]; then
+      body="$body, \"exit_code\": $exit_code, \"ended_at\": \"$(now_iso)\""
+    fi
+    if [ -n "$output_tail" ]; then
+      body="$body, \"output_tail\": \"$(json_escape "$output_tail")\""
+    fi
+    body="$body}"
+
     curl -s -X PATCH "http://localhost:$LOOP_TRACKER_PORT/api/sessions/$session_id" \
       -H "Content-Type: application/json" \
-      -d "{\"status\": \"$status\"}" > /dev/null
+      -d "$body" > /dev/null
   fi
 }
 
+# Periodically tell the app this wrapper is still alive, so the session can be
+# marked "lost" if the terminal is killed before the final status is sent.
+start_heartbeat() {
+  local session_id="$1"
+  local wrapper_pid=$$
+
+  if [ -z "$session_id" ] || [ "$session_id" = "error" ]; then
+    return
+  fi
+
+  (
+    while kill -0 "$wrapper_pid" 2>/dev/null; do
+      curl -s -X POST "http://localhost:$LOOP_TRACKER_PORT/api/sessions/$session_id/heartbeat" \
+        -H "Content-Type: application/json" \
+        -d "{\"pid\": $wrapper_pid}" > /dev/null
+      sleep "$HEARTBEAT_INTERVAL"
+    done
+  ) &
+  HEARTBEAT_PID=$!
+  trap 'kill "$HEARTBEAT_PID" 2>/dev/null' EXIT
+}
+
 is_interactive_copilot_command() {
   case "$1" in
     copilot*|"gh copilot"*) return 0 ;;
@@ -41,11 +96,27 @@ log_indicates_copilot_waiting() {
   [ "$has_idle_prompt" -eq 1 ] && [ "$has_activity" -eq 1 ]
 }
 
+# Run $COMMAND on the terminal while recording its output to a file. The
+# arguments after the file are the shell to run it with, `/bin/bash -c` by
+# default. util-linux and BSD/macOS `script` take their arguments
+# differently; only util-linux needs -e to exit with the command's status.
+run_recorded() {
+  local log_file="$1"
+  shift
+  [ $# -eq 0 ] && set -- /bin/bash -c
+
+  if script -q -e -c true /dev/null >/dev/null 2>&1; then
+    script -q -e -c "$(printf '%q ' "$@" "$COMMAND")" "$log_file"
+  else
+    script -q "$log_file" "$@" "$COMMAND"
+  fi
+}
+
 # Register session (include cwd for copilot session matching)
 CWD="$(pwd)"
 RESPONSE=$(curl -s -X POST "http://localhost:$LOOP_TRACKER_PORT/api/sessions" \
   -H "Content-Type: application/json" \
-  -d "{\"command\": \"$COMMAND\", \"title\": \"$TITLE\", \"cwd\": \"$CWD\"}")
+  -d "{\"command\": \"$(json_escape "$COMMAND")\", \"title\": \"$(json_escape "$TITLE")\", \"cwd\": \"$(json_escape "$CWD")\", \"pid\": $$, \"started_at\": \"$(now_iso)\"}")
 
 SESSION_ID=$(echo "$RESPONSE" | grep -o '"id":"[^"]*"' | cut -d'"' -f4)
 
@@ -54,6 +125,8 @@ if [ -z "$SESSION_ID" ] || [ "$SESSION_ID" = "error" ]; then
   echo "Make sure the app is running"
 fi
 
+start_heartbeat "$SESSION_ID"
+
 # For interactive Copilot sessions, preserve TTY while capturing output
 # via `script`. Status detection is handled by the app's polling system
 # through events.jsonl, so no log monitoring is needed here.
@@ -64,15 +137,25 @@ if is_interactive_copilot_command "$COMMAND"; then
 
   if command -v script >/dev/null 2>&1; then
     OUTPUT_LOG=$(mktemp)
-    USER_SHELL="${SHELL:-/bin/zsh}"
-    script -q "$OUTPUT_LOG" "$USER_SHELL" -ic "$COMMAND"
+    run_recorded "$OUTPUT_LOG" "${SHELL:-/bin/zsh}" -ic
     EXIT_CODE=$?
     rm -f "$OUTPUT_LOG"
   else
     eval "$COMMAND"
     EXIT_CODE=$?
   fi
+elif command -v script >/dev/null 2>&1; then
+  # Record the output with `script` so the tail can be attached to the
+  # session while the command keeps the terminal: colors, progress bars and
+  # prompts work, and the exit code is the command's own.
+  OUTPUT_LOG=$(mktemp)
+  run_recorded "$OUTPUT_LOG"
+  EXIT_CODE=$?
+  # util-linux `script` logs start and end lines around the output even with -q
+  OUTPUT_TAIL=$(grep -av '^Script \(started\|done\) on ' "$OUTPUT_LOG" | tail -n "$OUTPUT_TAIL_LINES")
+  rm -f "$OUTPUT_LOG"
 else
+  # Without `script` the output isn't captured, so no tail is attached
   eval "$COMMAND"
   EXIT_CODE=$?
 fi
@@ -84,9 +167,9 @@ if is_interactive_copilot_command "$COMMAND"; then
   # Don't override — polling detects in_progress/input_needed/completed from events.jsonl
   :
 elif [ $EXIT_CODE -eq 0 ]; then
-  update_session_status "$SESSION_ID" "completed"
+  update_session_status "$SESSION_ID" "completed" "$EXIT_CODE" "$OUTPUT_TAIL"
 else
-  update_session_status "$SESSION_ID" "failed"
+  update_session_status "$SESSION_ID" "failed" "$EXIT_CODE" "$OUTPUT_TAIL"
 fi
 
 exit $EXIT_CODE
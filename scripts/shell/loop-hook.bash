@@ -0,0 +1,92 @@
+# In The Loop shell integration for bash
+# Reports every command to the app; commands running longer than the
+# threshold configured in Settings become tracked CLI sessions.
+#
+# Usage: add to ~/.bashrc
+#   source /path/to/loop-hook.bash
+
+_LOOP_TRACKER_URL="http://localhost:${LOOP_TRACKER_PORT:-19532}/api/shell/commands"
+_LOOP_CMD_ID=""
+_LOOP_AT_PROMPT=0
+_LOOP_LAST_HISTCMD=""
+
+_loop_json_escape() {
+  printf '%s' "$1" | awk '
+    BEGIN { ORS = "" }
+    {
+      gsub(/\\/, "\\\\\\\\")
+      gsub(/"/, "\\\"")
+      gsub(/\t/, "\\t")
+      gsub(/[\001-\037]/, "")
+      if (NR > 1) print "\\n"
+      print
+    }'
+}
+
+_loop_report() {
+  _LOOP_CMD_ID="$$-$RANDOM$RANDOM"
+  local body="{\"id\": \"$_LOOP_CMD_ID\", \"command\": \"$(_loop_json_escape "$1")\", \"cwd\": \"$(_loop_json_escape "$PWD")\", \"pid\": $$}"
+  (curl -s -m 2 -X POST "$_LOOP_TRACKER_URL" \
+    -H "Content-Type: application/json" \
+    -d "$body" > /dev/null 2>&1 &)
+}
+
+# Runs from the DEBUG trap before every simple command; only the first one
+# after a prompt is reported so pipelines and PROMPT_COMMAND are skipped.
+# An empty line runs PROMPT_COMMAND without adding to the history, which
+# would report the previous command again, so HISTCMD has to have changed.
+_loop_preexec() {
+  [ -n "$COMP_LINE" ] && return
+  [ "$_LOOP_AT_PROMPT" = 1 ] || return
+  _LOOP_AT_PROMPT=0
+  [ "$HISTCMD" = "$_LOOP_LAST_HISTCMD" ] && return
+  _LOOP_LAST_HISTCMD=$HISTCMD
+
+  local command
+  command=$(HISTTIMEFORMAT= builtin history 1 | sed 's/^ *[0-9]* *//')
+  _loop_report "$command"
+}
+
+_loop_precmd() {
+  local exit_code=$?
+  if [ -n "$_LOOP_CMD_ID" ]; then
+    (curl -s -m 2 -X POST "$_LOOP_TRACKER_URL/$_LOOP_CMD_ID/finish" \
+      -H "Content-Type: application/json" \
+      -d "{\"exit_code\": $exit_code}" > /dev/null 2>&1 &)
+    _LOOP_CMD_ID=""
+  fi
+}
+
+_loop_arm() {
+  _LOOP_AT_PROMPT=1
+}
+
+# Install the DEBUG trap at the first prompt, once the rest of ~/.bashrc has
+# run, keeping a trap set there (e.g. by starship or atuin) and running it
+# first so it still sees $? and $_. `trap -p` shows nothing inside functions
+# and sourced files, so the current trap is read from PROMPT_COMMAND itself.
+_LOOP_INSTALL='_LOOP_PREV_DEBUG_TRAP=$(trap -p DEBUG); _loop_install; '
+_loop_install() {
+  PROMPT_COMMAND="${PROMPT_COMMAND/"$_LOOP_INSTALL"/}"
+
+  local previous=""
+  if [ -n "$_LOOP_PREV_DEBUG_TRAP" ]; then
+    eval "set -- $_LOOP_PREV_DEBUG_TRAP"
+    previous="$3"
+  fi
+  unset _LOOP_PREV_DEBUG_TRAP
+
+  case "$previous" in
+    "") trap '_loop_preexec' DEBUG ;;
+    *_loop_preexec*) ;;
+    *) trap "$previous"$'\n''_loop_preexec' DEBUG ;;
+  esac
+}
+
+if [ -n "${bash_preexec_imported:-}${__bp_imported:-}" ]; then
+  # bash-preexec owns the DEBUG trap and PROMPT_COMMAND; hook into it instead
+  preexec_functions+=(_loop_report)
+  precmd_functions=(_loop_precmd "${precmd_functions[@]}")
+else
+  PROMPT_COMMAND="_loop_precmd; ${_LOOP_INSTALL}${PROMPT_COMMAND:+$PROMPT_COMMAND; }_loop_arm"
+fi
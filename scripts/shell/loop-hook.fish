@@ -0,0 +1,43 @@
+# In The Loop shell integration for fish
+# Reports every command to the app; commands running longer than the
+# threshold configured in Settings become tracked CLI sessions.
+#
+# Usage: add to ~/.config/fish/config.fish
+#   source /path/to/loop-hook.fish
+
+set -q LOOP_TRACKER_PORT; or set -g LOOP_TRACKER_PORT 19532
+set -g _loop_tracker_url "http://localhost:$LOOP_TRACKER_PORT/api/shell/commands"
+set -g _loop_cmd_id ""
+
+function _loop_json_escape
+    printf '%s' $argv[1] | awk '
+      BEGIN { ORS = "" }
+      {
+        gsub(/\\\\/, "\\\\\\\\\\\\\\\\")
+        gsub(/"/, "\\\\\\"")
+        gsub(/\\t/, "\\\\t")
+        gsub(/[\\001-\\037]/, "")
+        if (NR > 1) print "\\\\n"
+        print
+      }'
+end
+
+function _loop_preexec --on-event fish_preexec
+    set -g _loop_cmd_id "$fish_pid-"(random)(random)
+    set -l command (_loop_json_escape "$argv[1]")
+    set -l cwd (_loop_json_escape "$PWD")
+    curl -s -m 2 -X POST "$_loop_tracker_url" \
+        -H "Content-Type: application/json" \
+        -d "{\"id\": \"$_loop_cmd_id\", \"command\": \"$command\", \"cwd\": \"$cwd\", \"pid\": $fish_pid}" > /dev/null 2>&1 &
+    disown 2>/dev/null
+end
+
+function _loop_postexec --on-event fish_postexec
+    set -l exit_code $status
+    test -z "$_loop_cmd_id"; and return
+    curl -s -m 2 -X POST "$_loop_tracker_url/$_loop_cmd_id/finish" \
+        -H "Content-Type: application/json" \
+        -d "{\"exit_code\": $exit_code}" > /dev/null 2>&1 &
+    disown 2>/dev/null
+    set -g _loop_cmd_id ""
+end
@@ -0,0 +1,341 @@
+use in_the_loop_lib::db::{Database, Item, Todo};
+use in_the_loop_lib::services::url_parser;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const USAGE: &str = "Usage: loop <command> [args]
+
+Commands:
+  ls [--archived]                          List tracked items
+  add <url> [--title <title>]              Track a Slack thread, GitHub PR or Action run
+  archive <id>                             Archive an item
+  todo add <title> [--date YYYY-MM-DD] [--parent <id>]
+                                           Add a todo
+  wait <id> --until <status> [--timeout <secs>] [--interval <secs>]
+                                           Block until the item reaches <status>
+
+Item ids may be abbreviated to any unique prefix.
+
+Exit codes:
+  0    success; for `wait`, the item reached the requested status
+  1    `wait` only: the item settled in a different final status
+  2    usage error or unknown item
+  3    the database is missing or can't be read or written
+  124  `wait` timed out";
+
+/// Every status an item can have.
+const STATUSES: &[&str] = &[
+    "waiting",
+    "in_progress",
+    "input_needed",
+    "updated",
+    "approved",
+    "merged",
+    "completed",
+    "closed",
+    "failed",
+    "lost",
+    "archived",
+];
+
+/// Statuses after which an item is not expected to change anymore.
+const FINAL_STATUSES: &[&str] = &["completed", "merged", "failed", "closed", "lost", "archived"];
+
+const EXIT_MISMATCH: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_ENVIRONMENT: i32 = 3;
+const EXIT_TIMEOUT: i32 = 124;
+
+fn get_db_path() -> PathBuf {
+    dirs::data_dir()
+        .map(|d| d.join("com.intheloop.app"))
+        .unwrap_or_default()
+        .join("in-the-loop.db")
+}
+
+fn open_db() -> Database {
+    let db_path = get_db_path();
+    if !db_path.exists() {
+        eprintln!(
+            "Database not found at {}. Is In The Loop installed and has been run at least once?",
+            db_path.display()
+        );
+        process::exit(EXIT_ENVIRONMENT);
+    }
+    Database::new(db_path).unwrap_or_else(|e| fail_environment(&format!("Failed to open database: {}", e)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(EXIT_USAGE);
+}
+
+/// Exit for errors of the database rather than the arguments, so scripts
+/// can tell them from `wait`'s mismatch and from usage errors.
+fn fail_environment(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(EXIT_ENVIRONMENT);
+}
+
+/// Return the value following `flag` in `args`, if present.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+/// Positional arguments, skipping flags and their values.
+fn positional(args: &[String]) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg.starts_with("--") {
+            if arg != "--archived" {
+                iter.next();
+            }
+            continue;
+        }
+        out.push(arg.as_str());
+    }
+    out
+}
+
+/// Find the item whose id equals or starts with `prefix`.
+fn resolve_item(db: &Database, prefix: &str) -> Item {
+    let mut matches: Vec<Item> = [false, true]
+        .iter()
+        .flat_map(|archived| {
+            db.get_items(*archived)
+                .unwrap_or_else(|e| fail_environment(&format!("Failed to list items: {}", e)))
+        })
+        .filter(|i| i.id.starts_with(prefix))
+        .collect();
+
+    if let Some(exact) = matches.iter().position(|i| i.id == prefix) {
+        return matches.swap_remove(exact);
+    }
+    match matches.len() {
+        0 => fail(&format!("No item matches id '{}'", prefix)),
+        1 => matches.remove(0),
+        n => fail(&format!("Id '{}' is ambiguous ({} items match)", prefix, n)),
+    }
+}
+
+fn cmd_ls(db: &Database, args: &[String]) {
+    let archived = args.iter().any(|a| a == "--archived");
+    let items = db
+        .get_items(archived)
+        .unwrap_or_else(|e| fail_environment(&format!("Failed to list items: {}", e)));
+
+    if items.is_empty() {
+        println!("No items found.");
+        return;
+    }
+
+    for item in &items {
+        println!(
+            "{:<8}  {:<12}  {:<16}  {}",
+            &item.id[..8.min(item.id.len())],
+            item.status,
+            item.item_type,
+            item.title
+        );
+    }
+}
+
+fn cmd_add(db: &Database, args: &[String]) {
+    let url = match positional(args).first() {
+        Some(u) => u.to_string(),
+        None => fail("Usage: loop add <url> [--title <title>]"),
+    };
+
+    let parsed = url_parser::parse_url(&url)
+        .unwrap_or_else(|e| fail(&format!("Error parsing URL: {}", e)));
+
+    let item = Item {
+        id: Uuid::new_v4().to_string(),
+        item_type: parsed.item_type,
+        title: flag_value(args, "--title")
+            .map(|t| t.to_string())
+            .unwrap_or(parsed.suggested_title),
+        url: Some(url),
+        status: "waiting".to_string(),
+        previous_status: None,
+        metadata: serde_json::to_string(&parsed.metadata).unwrap_or_else(|_| "{}".to_string()),
+        last_checked_at: None,
+        last_updated_at: None,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        archived: false,
+        archived_at: None,
+        polling_interval_override: None,
+        checked: false,
+    };
+
+    db.add_item(&item)
+        .unwrap_or_else(|e| fail_environment(&format!("Error adding item: {}", e)));
+    println!("{}", item.id);
+}
+
+fn cmd_archive(db: &Database, args: &[String]) {
+    let prefix = match positional(args).first() {
+        Some(id) => id.to_string(),
+        None => fail("Usage: loop archive <id>"),
+    };
+    let item = resolve_item(db, &prefix);
+    db.archive_item(&item.id)
+        .unwrap_or_else(|e| fail_environment(&format!("Error archiving item: {}", e)));
+    println!("Archived {}", item.title);
+}
+
+fn cmd_todo(db: &Database, args: &[String]) {
+    let rest = positional(args);
+    let title = match rest.as_slice() {
+        ["add", title, ..] => title.to_string(),
+        _ => fail("Usage: loop todo add <title> [--date YYYY-MM-DD] [--parent <id>]"),
+    };
+
+    let todo = Todo {
+        id: Uuid::new_v4().to_string(),
+        title,
+        status: "open".to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        completed_at: None,
+        planned_date: flag_value(args, "--date").map(|s| s.to_string()),
+        parent_id: flag_value(args, "--parent").map(|s| s.to_string()),
+    };
+
+    db.add_todo(&todo)
+        .unwrap_or_else(|e| fail_environment(&format!("Error adding todo: {}", e)));
+    println!("{}", todo.id);
+}
+
+/// Decide whether waiting is over for an item in `status`.
+/// Returns `Some(exit_code)` once done, `None` to keep waiting.
+/// "completed" also accepts "merged", and "failed" also accepts "lost".
+fn wait_outcome(status: &str, until: &str) -> Option<i32> {
+    let reached = status == until
+        || (until == "completed" && status == "merged")
+        || (until == "failed" && status == "lost");
+
+    if reached {
+        Some(0)
+    } else if FINAL_STATUSES.contains(&status) {
+        Some(EXIT_MISMATCH)
+    } else {
+        None
+    }
+}
+
+fn cmd_wait(db: &Database, args: &[String]) {
+    let prefix = match positional(args).first() {
+        Some(id) => id.to_string(),
+        None => fail("Usage: loop wait <id> --until <status>"),
+    };
+    let until = flag_value(args, "--until")
+        .unwrap_or_else(|| fail("Usage: loop wait <id> --until <status>"))
+        .to_string();
+    if !STATUSES.contains(&until.as_str()) {
+        fail(&format!("Unknown status: {} (expected one of: {})", until, STATUSES.join(", ")));
+    }
+    let timeout = flag_value(args, "--timeout")
+        .map(|t| t.parse::<u64>().unwrap_or_else(|_| fail("--timeout must be a number of seconds")))
+        .map(Duration::from_secs);
+    let interval = flag_value(args, "--interval")
+        .map(|t| t.parse::<u64>().unwrap_or_else(|_| fail("--interval must be a number of seconds")))
+        .unwrap_or(5);
+
+    let item_id = resolve_item(db, &prefix).id;
+    let started = Instant::now();
+
+    loop {
+        let item = match db.get_item(&item_id) {
+            Ok(Some(item)) => item,
+            Ok(None) => fail("Item was removed while waiting"),
+            Err(e) => fail_environment(&format!("Failed to read item: {}", e)),
+        };
+
+        if let Some(code) = wait_outcome(&item.status, &until) {
+            println!("{}: {}", item.title, item.status);
+            process::exit(code);
+        }
+
+        if let Some(limit) = timeout {
+            if started.elapsed() >= limit {
+                eprintln!("Timed out waiting for {} (status: {})", item.title, item.status);
+                process::exit(EXIT_TIMEOUT);
+            }
+        }
+
+        std::thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, rest) = match args.split_first() {
+        Some((c, r)) => (c.as_str(), r),
+        None => {
+            println!("{}", USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+
+    if matches!(command, "help" | "-h" | "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let db = open_db();
+    match command {
+        "ls" => cmd_ls(&db, rest),
+        "add" => cmd_add(&db, rest),
+        "archive" => cmd_archive(&db, rest),
+        "todo" => cmd_todo(&db, rest),
+        "wait" => cmd_wait(&db, rest),
+        _ => fail(&format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_succeeds_on_requested_status() {
+        assert_eq!(wait_outcome("completed", "completed"), Some(0));
+        assert_eq!(wait_outcome("merged", "completed"), Some(0));
+        assert_eq!(wait_outcome("failed", "failed"), Some(0));
+        assert_eq!(wait_outcome("approved", "approved"), Some(0));
+    }
+
+    #[test]
+    fn wait_fails_on_other_final_status() {
+        assert_eq!(wait_outcome("failed", "completed"), Some(EXIT_MISMATCH));
+        assert_eq!(wait_outcome("completed", "failed"), Some(EXIT_MISMATCH));
+        assert_eq!(wait_outcome("lost", "completed"), Some(EXIT_MISMATCH));
+    }
+
+    #[test]
+    fn final_statuses_are_statuses() {
+        assert!(FINAL_STATUSES.iter().all(|s| STATUSES.contains(s)));
+    }
+
+    #[test]
+    fn wait_continues_while_active() {
+        assert_eq!(wait_outcome("in_progress", "completed"), None);
+        assert_eq!(wait_outcome("waiting", "failed"), None);
+    }
+
+    #[test]
+    fn positional_skips_flag_values() {
+        let args: Vec<String> = ["abc", "--until", "completed", "--archived", "x"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(positional(&args), vec!["abc", "x"]);
+        assert_eq!(flag_value(&args, "--until"), Some("completed"));
+    }
+}
@@ -1,10 +1,24 @@
-use crate::db::{Credentials, Database, Item, Settings, Todo, TodoWithBindings};
+use crate::db::{
+    Credentials, DailySpend, Database, HookRun, Item, ItemEvent, ItemLink, ItemReminder, OpenCodeServer, Settings, Todo, TodoWithBindings,
+    Webhook, WebhookDelivery, DEFAULT_OPENCODE_SERVER,
+};
+use crate::services::opencode::ServerEndpoint;
+use crate::services::hooks;
+use crate::services::item_links;
+use crate::services::notifications::{self, Category};
+use crate::services::opencode_control;
+use crate::services::rules::{Rule, RuleMatch};
+use crate::services::transcript::{self, TranscriptPage};
 use crate::services::url_parser;
+use crate::reminder_scheduler;
+use crate::rule_engine;
 use crate::shortcut;
 use crate::tray;
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, State};
 use uuid::Uuid;
 
 pub struct AppState {
@@ -89,6 +103,411 @@ pub async fn archive_stale_items(before: String, app: AppHandle, state: State<'_
     Ok(count)
 }
 
+#[tauri::command]
+pub async fn get_session_transcript(
+    item_id: String,
+    before: Option<usize>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<TranscriptPage, String> {
+    let item = state
+        .db
+        .get_item(&item_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item not found: {}", item_id))?;
+    transcript::load_transcript(&state.db, &item, before, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn answer_opencode_question(
+    item_id: String,
+    answers: Vec<Vec<String>>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let item = state
+        .db
+        .get_item(&item_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item not found: {}", item_id))?;
+    opencode_control::answer_question(&state.db, &item, &answers)
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("item-updated", &item_id);
+    tray::refresh_tray(&app, &state.db);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn send_opencode_prompt(
+    item_id: String,
+    text: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let item = state
+        .db
+        .get_item(&item_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item not found: {}", item_id))?;
+    opencode_control::send_prompt(&state.db, &item, &text)
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("item-updated", &item_id);
+    tray::refresh_tray(&app, &state.db);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn abort_opencode_session(
+    item_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let item = state
+        .db
+        .get_item(&item_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item not found: {}", item_id))?;
+    opencode_control::abort(&state.db, &item)
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("item-updated", &item_id);
+    tray::refresh_tray(&app, &state.db);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_opencode_session(
+    server: String,
+    directory: String,
+    prompt: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Item, String> {
+    let item = opencode_control::start_session(&state.db, &server, &directory, prompt.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("item-updated", &item.id);
+    tray::refresh_tray(&app, &state.db);
+    Ok(item)
+}
+
+/// Directories a new session can be started in, per server name.
+#[tauri::command]
+pub async fn get_opencode_directories(
+    state: State<'_, AppState>,
+) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let servers = opencode_control::endpoints(&state.db).map_err(|e| e.to_string())?;
+    Ok(servers
+        .into_iter()
+        .map(|server| {
+            let mut directories = server.directories();
+            directories.sort();
+            directories.dedup();
+            (server.name, directories)
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_opencode_servers(state: State<'_, AppState>) -> Result<Vec<OpenCodeServer>, String> {
+    state.db.get_opencode_servers().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_opencode_server(
+    name: String,
+    url: String,
+    password: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Server name is required".to_string());
+    }
+    ServerEndpoint::new(name, url.trim(), "").map_err(|e| format!("Invalid OpenCode URL: {}", e))?;
+    state
+        .db
+        .save_opencode_server(name, url.trim(), password.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_opencode_server(name: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.remove_opencode_server(&name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_item_timeline(item_id: String, state: State<'_, AppState>) -> Result<Vec<ItemEvent>, String> {
+    state.db.get_item_events(&item_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_item_links(state: State<'_, AppState>) -> Result<Vec<ItemLink>, String> {
+    state.db.get_item_links().map_err(|e| e.to_string())
+}
+
+/// Link `source_id` to `target_id`, read as "source <relation> target".
+#[tauri::command]
+pub async fn link_items(
+    source_id: String,
+    target_id: String,
+    relation: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    for id in [&source_id, &target_id] {
+        if state.db.get_item(id).map_err(|e| e.to_string())?.is_none() {
+            return Err(format!("Item {} not found", id));
+        }
+    }
+    let links = state.db.get_item_links().map_err(|e| e.to_string())?;
+    let relation = item_links::check_new_link(&links, &source_id, &target_id, &relation).map_err(|e| e.to_string())?;
+    state
+        .db
+        .add_item_link(&source_id, &target_id, relation.as_str())
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("item-updated", &target_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unlink_items(
+    source_id: String,
+    target_id: String,
+    relation: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .db
+        .remove_item_link(&source_id, &target_id, &relation)
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("item-updated", &target_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_rules(state: State<'_, AppState>) -> Result<Vec<Rule>, String> {
+    state.db.get_rules().map_err(|e| e.to_string())
+}
+
+/// Add a rule, or update it when it already has an id.
+#[tauri::command]
+pub async fn save_rule(mut rule: Rule, state: State<'_, AppState>) -> Result<Rule, String> {
+    if rule.name.trim().is_empty() {
+        return Err("A rule needs a name".to_string());
+    }
+    if rule.actions.is_empty() {
+        return Err("A rule needs at least one action".to_string());
+    }
+    if rule.id.is_empty() {
+        rule.id = Uuid::new_v4().to_string();
+    }
+    if rule.created_at.is_empty() {
+        rule.created_at = chrono::Utc::now().to_rfc3339();
+    }
+    state.db.save_rule(&rule).map_err(|e| e.to_string())?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_rule(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.delete_rule(&id).map_err(|e| e.to_string())
+}
+
+/// Which rules would have fired over the recorded status history: just
+/// `rule` when given, otherwise every enabled rule. Nothing is run.
+#[tauri::command]
+pub async fn dry_run_rules(rule: Option<Rule>, state: State<'_, AppState>) -> Result<Vec<RuleMatch>, String> {
+    let rules = match rule {
+        Some(rule) => vec![rule],
+        None => state
+            .db
+            .get_rules()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|r| r.enabled)
+            .collect(),
+    };
+    rule_engine::dry_run(&state.db, &rules).map_err(|e| e.to_string())
+}
+
+/// The hooks directory, the hooks in it and their recent runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct HooksInfo {
+    pub dir: String,
+    pub hooks: Vec<String>,
+    pub runs: Vec<HookRun>,
+}
+
+/// Creates the hooks directory if it doesn't exist yet, so it can be opened.
+#[tauri::command]
+pub async fn get_hooks(state: State<'_, AppState>) -> Result<HooksInfo, String> {
+    let dir = hooks::hooks_dir().ok_or("No data directory to keep hooks in")?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(HooksInfo {
+        dir: dir.to_string_lossy().to_string(),
+        hooks: hooks::list_hooks(&dir),
+        runs: state.db.get_hook_runs(50).map_err(|e| e.to_string())?,
+    })
+}
+
+#[tauri::command]
+pub async fn get_webhooks(state: State<'_, AppState>) -> Result<Vec<Webhook>, String> {
+    state.db.get_webhooks().map_err(|e| e.to_string())
+}
+
+/// Add a webhook, or update it when it already has an id. A missing secret
+/// keeps the stored one, an empty one stops signing.
+#[tauri::command]
+pub async fn save_webhook(
+    mut webhook: Webhook,
+    secret: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Webhook, String> {
+    let parsed = url::Url::parse(webhook.url.trim()).map_err(|_| format!("Invalid URL '{}'", webhook.url))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("Webhook URLs must use http or https".to_string());
+    }
+    webhook.url = parsed.to_string();
+    webhook.events = webhook
+        .events
+        .iter()
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect();
+    if webhook.id.is_empty() {
+        webhook.id = Uuid::new_v4().to_string();
+    }
+    if webhook.created_at.is_empty() {
+        webhook.created_at = chrono::Utc::now().to_rfc3339();
+    }
+    state
+        .db
+        .save_webhook(&webhook, secret.as_deref())
+        .map_err(|e| e.to_string())?;
+    Ok(webhook)
+}
+
+#[tauri::command]
+pub async fn delete_webhook(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.delete_webhook(&id).map_err(|e| e.to_string())
+}
+
+/// The delivery log, newest first.
+#[tauri::command]
+pub async fn get_webhook_deliveries(state: State<'_, AppState>) -> Result<Vec<WebhookDelivery>, String> {
+    state.db.get_webhook_deliveries(100).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn retry_webhook_delivery(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.retry_webhook_delivery(id).map_err(|e| e.to_string())
+}
+
+/// Whether one kind of notification is on for an item type.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationToggle {
+    pub category: String,
+    pub label: String,
+    pub enabled: bool,
+}
+
+/// The notification matrix row for an item type.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationRow {
+    pub item_type: String,
+    pub toggles: Vec<NotificationToggle>,
+}
+
+#[tauri::command]
+pub async fn get_notification_matrix(state: State<'_, AppState>) -> Result<Vec<NotificationRow>, String> {
+    notifications::item_types()
+        .into_iter()
+        .map(|item_type| {
+            let toggles = notifications::categories_for(item_type)
+                .iter()
+                .map(|category| {
+                    Ok(NotificationToggle {
+                        category: category.key().to_string(),
+                        label: category.label().to_string(),
+                        enabled: state.db.notification_pref(item_type, *category).map_err(|e| e.to_string())?,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(NotificationRow { item_type: item_type.to_string(), toggles })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn set_notification_pref(
+    item_type: String,
+    category: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let category = Category::parse(&category).ok_or_else(|| format!("Unknown notification category '{}'", category))?;
+    if !notifications::categories_for(&item_type).contains(&category) {
+        return Err(format!("'{}' notifications don't apply to {}", category.key(), item_type));
+    }
+    state
+        .db
+        .set_notification_pref(&item_type, category.key(), enabled)
+        .map_err(|e| e.to_string())
+}
+
+/// Mute or unmute every notification about an item.
+#[tauri::command]
+pub async fn set_item_muted(id: String, muted: bool, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.set_item_muted(&id, muted).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_muted_items(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    state.db.get_muted_item_ids().map_err(|e| e.to_string())
+}
+
+/// Hold off reminders about an item for `minutes`; 0 ends the snooze.
+#[tauri::command]
+pub async fn snooze_item(
+    id: String,
+    minutes: i64,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let until = (minutes > 0).then(|| (chrono::Utc::now() + chrono::Duration::minutes(minutes)).to_rfc3339());
+    state
+        .db
+        .snooze_item(&id, until.as_deref())
+        .map_err(|e| e.to_string())?;
+    reminder_scheduler::refresh_pulse(&state.db, &app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_item_reminders(state: State<'_, AppState>) -> Result<Vec<ItemReminder>, String> {
+    let reminders = state.db.get_item_reminders().map_err(|e| e.to_string())?;
+    Ok(reminders.into_values().collect())
+}
+
+/// Agent session usage per directory on `day` (YYYY-MM-DD, default today).
+#[tauri::command]
+pub async fn get_daily_spend(day: Option<String>, state: State<'_, AppState>) -> Result<Vec<DailySpend>, String> {
+    let day = match day {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid day '{}', expected YYYY-MM-DD", d))?
+            .format("%Y-%m-%d")
+            .to_string(),
+        None => crate::services::budget::today(),
+    };
+    state.db.get_daily_spend(&day).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn toggle_checked(
     id: String,
@@ -127,20 +546,32 @@ pub async fn save_credentials(
         }
     }
 
-    if let Some(opencode_url) = credentials.opencode_url {
-        if !opencode_url.is_empty() {
+    if let Some(secret) = credentials.github_webhook_secret {
+        if !secret.is_empty() {
             state
                 .db
-                .save_credential("opencode_url", &opencode_url)
+                .save_credential("github_webhook_secret", &secret)
                 .map_err(|e| e.to_string())?;
         }
     }
 
-    if let Some(opencode_password) = credentials.opencode_password {
-        state
-            .db
-            .save_credential("opencode_password", &opencode_password)
-            .map_err(|e| e.to_string())?;
+    // The single OpenCode URL/password now configure the default server
+    let new_url = credentials.opencode_url.filter(|u| !u.is_empty());
+    if new_url.is_some() || credentials.opencode_password.is_some() {
+        let url = match new_url {
+            Some(u) => Some(u),
+            None => state
+                .db
+                .get_opencode_server(DEFAULT_OPENCODE_SERVER)
+                .map_err(|e| e.to_string())?
+                .map(|s| s.url),
+        };
+        if let Some(url) = url {
+            state
+                .db
+                .save_opencode_server(DEFAULT_OPENCODE_SERVER, &url, credentials.opencode_password.as_deref())
+                .map_err(|e| e.to_string())?;
+        }
     }
 
     Ok(())
@@ -171,6 +602,46 @@ pub async fn save_settings(
         .save_setting("notify_input_needed", &settings.notify_input_needed.to_string())
         .map_err(|e| e.to_string())?;
 
+    state
+        .db
+        .save_setting("shell_track_threshold_secs", &settings.shell_track_threshold_secs.to_string())
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("shell_track_ignore", &settings.shell_track_ignore)
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("budget_session_tokens", &settings.budget_session_tokens.max(0).to_string())
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("budget_session_cost", &settings.budget_session_cost.max(0.0).to_string())
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("budget_daily_tokens", &settings.budget_daily_tokens.max(0).to_string())
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("budget_daily_cost", &settings.budget_daily_cost.max(0.0).to_string())
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("reminder_interval_minutes", &settings.reminder_interval_minutes.max(0).to_string())
+        .map_err(|e| e.to_string())?;
+
+    state
+        .db
+        .save_setting("reminder_loud_sound", &settings.reminder_loud_sound.to_string())
+        .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -0,0 +1,200 @@
+use crate::db::Database;
+use crate::polling::PollingManager;
+use crate::services::copilot_cli;
+use crate::tray;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Writes arriving within this window are handled as one batch.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// How often to check whether the session-state directory has appeared.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Which file of a Copilot session changed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SessionFile {
+    Events,
+    Workspace,
+}
+
+/// Watch `~/.copilot/session-state` and update Copilot sessions as soon as
+/// their files change, instead of waiting for the next poll. Polling keeps
+/// running as a fallback, e.g. to notice exited processes.
+pub fn start(db: Arc<Database>, app_handle: AppHandle) {
+    let base = match copilot_cli::session_state_dir() {
+        Some(p) => p,
+        None => return,
+    };
+
+    std::thread::spawn(move || loop {
+        if base.is_dir() {
+            if let Err(e) = watch(&db, &app_handle, &base) {
+                eprintln!("Copilot session watcher stopped: {}", e);
+            }
+        }
+        std::thread::sleep(RETRY_INTERVAL);
+    });
+}
+
+fn watch(db: &Arc<Database>, app_handle: &AppHandle, base: &Path) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(base, RecursiveMode::Recursive)?;
+
+    while let Ok(first) = rx.recv() {
+        let mut changed = HashSet::new();
+        let mut collect = |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.kind.is_create() || event.kind.is_modify() {
+                    changed.extend(event.paths.iter().filter_map(|p| changed_session_file(base, p)));
+                }
+            }
+        };
+        collect(first);
+        while let Ok(res) = rx.recv_timeout(DEBOUNCE) {
+            collect(res);
+        }
+
+        if changed.is_empty() {
+            continue;
+        }
+        if let Err(e) = handle_changes(db, app_handle, &changed) {
+            eprintln!("Error handling Copilot session changes: {}", e);
+        }
+        tray::refresh_tray(app_handle, db);
+    }
+
+    Ok(())
+}
+
+/// Map a changed path to the session and file it belongs to.
+/// Only `<base>/<session-id>/{events.jsonl,workspace.yaml}` are of interest.
+fn changed_session_file(base: &Path, path: &Path) -> Option<(String, SessionFile)> {
+    let relative = path.strip_prefix(base).ok()?;
+    let mut parts = relative.components();
+    let session_id = parts.next()?.as_os_str().to_str()?.to_string();
+    let file_name = parts.next()?.as_os_str().to_str()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let file = match file_name {
+        "events.jsonl" => SessionFile::Events,
+        "workspace.yaml" => SessionFile::Workspace,
+        _ => return None,
+    };
+    Some((session_id, file))
+}
+
+fn handle_changes(
+    db: &Arc<Database>,
+    app_handle: &AppHandle,
+    changed: &HashSet<(String, SessionFile)>,
+) -> anyhow::Result<()> {
+    let active_cwds = copilot_cli::get_active_copilot_cwds();
+    let known_ids = db.get_copilot_session_ids()?;
+    let items = db.get_items(false)?;
+
+    let session_ids: HashSet<&String> = changed.iter().map(|(id, _)| id).collect();
+    for session_id in session_ids {
+        let session = match copilot_cli::read_session(session_id) {
+            Some(s) => s,
+            None => continue,
+        };
+
+        // New session: create its item without a full directory scan
+        if !known_ids.contains(session_id) {
+            PollingManager::add_copilot_session(db, app_handle, session, &active_cwds)?;
+            continue;
+        }
+
+        let item = items.iter().find(|i| {
+            i.item_type == "copilot_agent"
+                && serde_json::from_str::<serde_json::Value>(&i.metadata)
+                    .map(|m| m["copilot_session_id"].as_str() == Some(session_id.as_str()))
+                    .unwrap_or(false)
+        });
+        let item = match item {
+            Some(i) => i,
+            // Tracked through a cli_session item or archived; left to polling
+            None => continue,
+        };
+
+        let process_running = copilot_cli::is_session_process_running(&session, &active_cwds);
+        let state = PollingManager::copilot_session_state(db, session_id);
+        PollingManager::apply_copilot_activity(db, item, app_handle, &session, process_running, &state)?;
+        PollingManager::check_budget(db, app_handle, item)?;
+        let _ = app_handle.emit("item-updated", &item.id);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_files_are_recognized() {
+        let base = Path::new("/home/me/.copilot/session-state");
+        assert_eq!(
+            changed_session_file(base, &base.join("abc").join("events.jsonl")),
+            Some(("abc".to_string(), SessionFile::Events))
+        );
+        assert_eq!(
+            changed_session_file(base, &base.join("abc").join("workspace.yaml")),
+            Some(("abc".to_string(), SessionFile::Workspace))
+        );
+    }
+
+    #[test]
+    fn unrelated_paths_are_ignored() {
+        let base = Path::new("/home/me/.copilot/session-state");
+        assert_eq!(changed_session_file(base, &base.join("abc")), None);
+        assert_eq!(changed_session_file(base, &base.join("abc").join("notes.md")), None);
+        assert_eq!(
+            changed_session_file(base, &base.join("abc").join("sub").join("events.jsonl")),
+            None
+        );
+        assert_eq!(changed_session_file(base, Path::new("/tmp/events.jsonl")), None);
+    }
+
+    #[test]
+    fn a_session_found_twice_is_added_once() {
+        let db = Arc::new(
+            Database::new(std::env::temp_dir().join(format!("loop-test-{}.db", uuid::Uuid::new_v4()))).unwrap(),
+        );
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    let item = crate::db::Item {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        item_type: "copilot_agent".to_string(),
+                        title: "Copilot Session abc".to_string(),
+                        url: None,
+                        status: "in_progress".to_string(),
+                        previous_status: None,
+                        metadata: serde_json::json!({ "copilot_session_id": "abc" }).to_string(),
+                        last_checked_at: None,
+                        last_updated_at: None,
+                        created_at: chrono::Utc::now().to_rfc3339(),
+                        archived: false,
+                        archived_at: None,
+                        polling_interval_override: None,
+                        checked: false,
+                    };
+                    db.add_copilot_session_item(&item).unwrap()
+                })
+            })
+            .collect();
+        let added: Vec<bool> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(added.iter().filter(|a| **a).count(), 1);
+        assert_eq!(db.get_copilot_session_ids().unwrap(), vec!["abc".to_string()]);
+    }
+}
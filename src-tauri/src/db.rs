@@ -1,8 +1,12 @@
+use crate::services::notifications::Category;
+use crate::services::rules::{self, Rule, StatusChange};
 use anyhow::Result;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
@@ -23,12 +27,26 @@ pub struct Item {
     pub checked: bool,
 }
 
+/// Reminder state of an item left waiting on the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemReminder {
+    pub item_id: String,
+    /// When the last reminder was shown, and how many were shown since the
+    /// item entered its status.
+    pub reminded_at: Option<String>,
+    pub count: i64,
+    /// No reminders until then.
+    pub snoozed_until: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credentials {
     pub slack_token: Option<String>,
     pub github_token: Option<String>,
     pub opencode_url: Option<String>,
     pub opencode_password: Option<String>,
+    /// Secret GitHub signs webhook deliveries with.
+    pub github_webhook_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +55,16 @@ pub struct Settings {
     pub notify_session_started: bool,
     pub notify_session_ended: bool,
     pub notify_input_needed: bool,
+    pub shell_track_threshold_secs: i64,
+    pub shell_track_ignore: String,
+    /// Budget thresholds for agent sessions; 0 disables a threshold.
+    pub budget_session_tokens: i64,
+    pub budget_session_cost: f64,
+    pub budget_daily_tokens: i64,
+    pub budget_daily_cost: f64,
+    /// Minutes between reminders about items waiting on the user; 0 disables them.
+    pub reminder_interval_minutes: i64,
+    pub reminder_loud_sound: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,8 +86,125 @@ pub struct TodoWithBindings {
     pub subtasks: Vec<TodoWithBindings>,
 }
 
+/// A named OpenCode server endpoint with its last known health.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenCodeServer {
+    pub name: String,
+    pub url: String,
+    /// Never sent to the frontend; saving without one keeps the stored value.
+    #[serde(skip_serializing, default)]
+    pub password: String,
+    pub healthy: Option<bool>,
+    pub last_checked_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Name of the server created from the single `opencode_url` credential
+/// used before multiple servers were supported.
+pub const DEFAULT_OPENCODE_SERVER: &str = "default";
+
+/// An entry in an item's timeline: something done to or observed on the item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemEvent {
+    pub id: i64,
+    pub item_id: String,
+    pub kind: String,
+    pub message: String,
+    pub created_at: String,
+}
+
+/// Agent session usage on one day, summed per directory or repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySpend {
+    pub directory: String,
+    pub sessions: i64,
+    pub tokens: i64,
+    pub cost: f64,
+}
+
+/// A typed link between two items, read as "source <relation> target".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemLink {
+    pub source_item_id: String,
+    pub target_item_id: String,
+    pub relation: String,
+    pub created_at: String,
+}
+
+/// One run of a user shell hook (see services::hooks).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookRun {
+    pub id: i64,
+    pub hook: String,
+    pub item_id: String,
+    pub old_status: String,
+    pub new_status: String,
+    pub started_at: String,
+    pub duration_ms: i64,
+    /// `None` when the hook didn't start, was killed or died from a signal.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stderr: String,
+}
+
+/// An outgoing webhook called on status changes (see services::webhooks).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    #[serde(default)]
+    pub id: String,
+    pub url: String,
+    /// Event filters; empty means every status change.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Never sent to the frontend; saving without one keeps the stored value.
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    #[serde(default)]
+    pub signed: bool,
+    pub enabled: bool,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+/// One event for a webhook: queued while `pending`, then `delivered` or
+/// `failed` once the retries are used up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: String,
+    pub item_id: String,
+    pub event: String,
+    pub payload: String,
+    pub state: String,
+    pub attempts: i64,
+    pub next_attempt_at: Option<String>,
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// How many finished webhook deliveries are kept.
+const WEBHOOK_DELIVERIES_KEPT: i64 = 500;
+
+/// How many hook runs are kept.
+const HOOK_RUNS_KEPT: i64 = 200;
+
+/// SQL condition matching items nested under the item bound to `?1`, so
+/// archiving or removing a parent takes its children along.
+const CHILD_OF_ITEM: &str =
+    "(json_valid(metadata) AND json_extract(metadata, '$.parent_item_id') = ?1)";
+
+/// SQL condition matching items nested under another item.
+const IS_CHILD_ITEM: &str =
+    "(json_valid(metadata) AND json_extract(metadata, '$.parent_item_id') IS NOT NULL)";
+
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// Where status changes are published: the rule engine, webhooks.
+    status_changes: Mutex<Vec<UnboundedSender<StatusChange>>>,
+    /// Id of the last status event in `item_events` that was published.
+    published_status_event: Mutex<i64>,
 }
 
 impl Database {
@@ -180,17 +325,304 @@ impl Database {
             [],
         )?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS item_links (
+                source_item_id TEXT NOT NULL,
+                target_item_id TEXT NOT NULL,
+                relation TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (source_item_id, target_item_id, relation),
+                FOREIGN KEY (source_item_id) REFERENCES items(id) ON DELETE CASCADE,
+                FOREIGN KEY (target_item_id) REFERENCES items(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_item_links_target ON item_links(target_item_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS agent_session_state (
+                item_type TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                byte_offset INTEGER NOT NULL,
+                state TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (item_type, session_id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS item_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                message TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_item_events_item ON item_events(item_id, id)",
+            [],
+        )?;
+
+        // Token and cost usage of agent sessions, attributed to the local day
+        // it was observed on. Kept after the item is removed so past daily
+        // summaries stay complete.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS agent_usage (
+                day TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                directory TEXT NOT NULL,
+                tokens INTEGER NOT NULL DEFAULT 0,
+                cost REAL NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, item_id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS opencode_servers (
+                name TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                password TEXT NOT NULL DEFAULT '',
+                healthy INTEGER,
+                last_checked_at TEXT,
+                last_error TEXT
+            )",
+            [],
+        )?;
+
+        // Rules run on status transitions; trigger, conditions and actions
+        // are stored as JSON (see services::rules).
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                trigger TEXT NOT NULL,
+                conditions TEXT NOT NULL,
+                actions TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS hook_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                hook TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                old_status TEXT NOT NULL,
+                new_status TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                exit_code INTEGER,
+                timed_out INTEGER NOT NULL DEFAULT 0,
+                stderr TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS webhooks (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                events TEXT NOT NULL DEFAULT '[]',
+                secret TEXT NOT NULL DEFAULT '',
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Outgoing webhook events; pending rows are the retry queue and
+        // survive restarts, finished ones are the delivery log.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                webhook_id TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                state TEXT NOT NULL DEFAULT 'pending',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT,
+                response_status INTEGER,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_pending ON webhook_deliveries(state, next_attempt_at)",
+            [],
+        )?;
+
+        // Per item type overrides of the notification defaults
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS notification_prefs (
+                item_type TEXT NOT NULL,
+                category TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                PRIMARY KEY (item_type, category)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS item_mutes (
+                item_id TEXT PRIMARY KEY,
+                muted_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS item_reminders (
+                item_id TEXT PRIMARY KEY,
+                reminded_at TEXT,
+                count INTEGER NOT NULL DEFAULT 0,
+                snoozed_until TEXT
+            )",
+            [],
+        )?;
+
+        // Migration: turn the single OpenCode credential into the "default" server
+        conn.execute(
+            "INSERT INTO opencode_servers (name, url, password)
+             SELECT ?1, value, COALESCE((SELECT value FROM credentials WHERE key = 'opencode_password'), '')
+             FROM credentials
+             WHERE key = 'opencode_url' AND value != ''
+               AND NOT EXISTS (SELECT 1 FROM opencode_servers)",
+            params![DEFAULT_OPENCODE_SERVER],
+        )?;
+        conn.execute(
+            "DELETE FROM credentials WHERE key IN ('opencode_url', 'opencode_password')",
+            [],
+        )?;
+
+        // Migration: tag OpenCode items from before multiple servers with the default server
+        conn.execute(
+            "UPDATE items SET metadata = json_set(metadata, '$.server', ?1)
+             WHERE type = 'opencode_session' AND json_valid(metadata)
+               AND json_extract(metadata, '$.server') IS NULL",
+            params![DEFAULT_OPENCODE_SERVER],
+        )?;
+
+        // Migration: Claude Code sessions are tracked like the other log-based
+        // agents, and every agent's parser state lives in agent_session_state.
+        // Parser state is re-derived from the logs.
+        conn.execute(
+            "UPDATE items SET metadata = json_remove(
+                 json_set(metadata,
+                     '$.agent_session_id', json_extract(metadata, '$.claude_session_id'),
+                     '$.log_path', json_extract(metadata, '$.transcript_path')),
+                 '$.claude_session_id', '$.transcript_path')
+             WHERE type = 'claude_session' AND json_valid(metadata)
+               AND json_extract(metadata, '$.claude_session_id') IS NOT NULL",
+            [],
+        )?;
+        conn.execute("DROP TABLE IF EXISTS claude_session_state", [])?;
+        conn.execute("DROP TABLE IF EXISTS copilot_session_state", [])?;
+
+        // History from before this start isn't published again
+        let published_status_event: i64 =
+            conn.query_row("SELECT COALESCE(MAX(id), 0) FROM item_events", [], |row| row.get(0))?;
+
         Ok(Database {
             conn: Arc::new(Mutex::new(conn)),
+            status_changes: Mutex::new(Vec::new()),
+            published_status_event: Mutex::new(published_status_event),
         })
     }
 
+    /// Receive every status change made from now on.
+    pub fn subscribe_status_changes(&self) -> UnboundedReceiver<StatusChange> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.status_changes.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Record a status change in the item's timeline and publish it.
+    fn record_status_change(&self, conn: &Connection, id: &str, from: &str, to: &str, now: &str) -> Result<()> {
+        conn.execute(
+            "INSERT INTO item_events (item_id, kind, message, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, rules::STATUS_EVENT, rules::status_event_message(from, to), now],
+        )?;
+        self.publish_status_events(conn)
+    }
+
+    /// Publish the status changes made by other processes, the MCP server
+    /// and the `loop` CLI, since the last change published.
+    pub fn publish_external_status_changes(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        self.publish_status_events(&conn)
+    }
+
+    /// Publish every status event recorded after the last one published, in
+    /// the order they were recorded, whichever process recorded them.
+    fn publish_status_events(&self, conn: &Connection) -> Result<()> {
+        let mut published = self.published_status_event.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, message, created_at FROM item_events
+             WHERE kind = ?1 AND id > ?2 ORDER BY id",
+        )?;
+        let events = stmt
+            .query_map(params![rules::STATUS_EVENT, *published], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut subscribers = self.status_changes.lock().unwrap();
+        for (id, item_id, message, at) in events {
+            *published = id;
+            if let Some(change) = rules::parse_status_event(&item_id, &message, &at) {
+                // Subscribers that went away are dropped.
+                subscribers.retain(|tx| tx.send(change.clone()).is_ok());
+            }
+        }
+        Ok(())
+    }
+
     pub fn add_item(&self, item: &Item) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO items (id, type, title, url, status, previous_status, metadata, 
-                               last_checked_at, last_updated_at, created_at, archived, polling_interval_override, checked, archived_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        Self::insert_item(&conn, item, "1")?;
+        Ok(())
+    }
+
+    /// Add a `copilot_agent` item unless an item already tracks its Copilot
+    /// session. The check and the insert are one statement, so the file
+    /// watcher and the poller can't both add the same session. Returns
+    /// whether the item was added.
+    pub fn add_copilot_session_item(&self, item: &Item) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let added = Self::insert_item(
+            &conn,
+            item,
+            "NOT EXISTS (
+                SELECT 1 FROM items
+                WHERE type IN ('copilot_agent', 'cli_session') AND json_valid(metadata)
+                  AND json_extract(metadata, '$.copilot_session_id') = json_extract(?7, '$.copilot_session_id')
+            )",
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Insert `item` if the SQL `condition` holds, returning the rows added.
+    fn insert_item(conn: &Connection, item: &Item, condition: &str) -> Result<usize> {
+        let added = conn.execute(
+            &format!(
+                "INSERT INTO items (id, type, title, url, status, previous_status, metadata,
+                                   last_checked_at, last_updated_at, created_at, archived, polling_interval_override, checked, archived_at)
+                 SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14 WHERE {}",
+                condition
+            ),
             params![
                 item.id,
                 item.item_type,
@@ -208,7 +640,7 @@ impl Database {
                 item.archived_at,
             ],
         )?;
-        Ok(())
+        Ok(added)
     }
 
     pub fn get_items(&self, archived: bool) -> Result<Vec<Item>> {
@@ -243,6 +675,72 @@ impl Database {
         Ok(items)
     }
 
+    pub fn get_item(&self, id: &str) -> Result<Option<Item>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, type, title, url, status, previous_status, metadata,
+                    last_checked_at, last_updated_at, created_at, archived, polling_interval_override, checked, archived_at
+             FROM items WHERE id = ?1"
+        )?;
+
+        let mut items = stmt
+            .query_map([id], |row| {
+                Ok(Item {
+                    id: row.get(0)?,
+                    item_type: row.get(1)?,
+                    title: row.get(2)?,
+                    url: row.get(3)?,
+                    status: row.get(4)?,
+                    previous_status: row.get(5)?,
+                    metadata: row.get(6)?,
+                    last_checked_at: row.get(7)?,
+                    last_updated_at: row.get(8)?,
+                    created_at: row.get(9)?,
+                    archived: row.get::<_, i32>(10)? != 0,
+                    polling_interval_override: row.get(11)?,
+                    checked: row.get::<_, i32>(12)? != 0,
+                    archived_at: row.get(13)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items.pop())
+    }
+
+    /// Items nested under `parent_id` (e.g. OpenCode sub-agent sessions), oldest first.
+    pub fn get_child_items(&self, parent_id: &str) -> Result<Vec<Item>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, type, title, url, status, previous_status, metadata,
+                    last_checked_at, last_updated_at, created_at, archived, polling_interval_override, checked, archived_at
+             FROM items WHERE json_valid(metadata) AND json_extract(metadata, '$.parent_item_id') = ?1
+             ORDER BY created_at"
+        )?;
+
+        let items = stmt
+            .query_map([parent_id], |row| {
+                Ok(Item {
+                    id: row.get(0)?,
+                    item_type: row.get(1)?,
+                    title: row.get(2)?,
+                    url: row.get(3)?,
+                    status: row.get(4)?,
+                    previous_status: row.get(5)?,
+                    metadata: row.get(6)?,
+                    last_checked_at: row.get(7)?,
+                    last_updated_at: row.get(8)?,
+                    created_at: row.get(9)?,
+                    archived: row.get::<_, i32>(10)? != 0,
+                    polling_interval_override: row.get(11)?,
+                    checked: row.get::<_, i32>(12)? != 0,
+                    archived_at: row.get(13)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
     pub fn update_item_status(&self, id: &str, status: &str, metadata: Option<&str>) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().to_rfc3339();
@@ -284,9 +782,44 @@ impl Database {
                 params![now, id],
             )?;
         }
+        if status_changed {
+            self.record_status_change(&conn, id, &current_status, status, &now)?;
+        }
         Ok(())
     }
 
+    /// Record a heartbeat from a CLI session's wrapper. Only `last_heartbeat_at`
+    /// and `pid` in the metadata change, so a final update racing with the
+    /// heartbeat is never undone, and only a session marked "lost" is put
+    /// back to "in_progress". Returns whether the session was revived.
+    pub fn record_heartbeat(&self, id: &str, pid: Option<u32>) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        let metadata = "CASE WHEN json_valid(metadata) AND json_type(metadata) = 'object' THEN metadata ELSE '{}' END";
+        match pid {
+            Some(pid) => conn.execute(
+                &format!(
+                    "UPDATE items SET metadata = json_set({}, '$.last_heartbeat_at', ?1, '$.pid', ?2) WHERE id = ?3",
+                    metadata
+                ),
+                params![now, pid, id],
+            )?,
+            None => conn.execute(
+                &format!("UPDATE items SET metadata = json_set({}, '$.last_heartbeat_at', ?1) WHERE id = ?2", metadata),
+                params![now, id],
+            )?,
+        };
+        let revived = conn.execute(
+            "UPDATE items SET status = 'in_progress', previous_status = 'lost', last_updated_at = ?1
+             WHERE id = ?2 AND status = 'lost'",
+            params![now, id],
+        )? > 0;
+        if revived {
+            self.record_status_change(&conn, id, "lost", "in_progress", &now)?;
+        }
+        Ok(revived)
+    }
+
     pub fn touch_item_check(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().to_rfc3339();
@@ -335,6 +868,7 @@ impl Database {
                  WHERE id = ?4",
                 params![current_status, now, new_metadata, id],
             )?;
+            self.record_status_change(&conn, id, &current_status, "failed", &now)?;
         } else {
             conn.execute(
                 "UPDATE items SET last_checked_at = ?1, metadata = ?2 WHERE id = ?3",
@@ -347,7 +881,17 @@ impl Database {
 
     pub fn remove_item(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM items WHERE id = ?1", params![id])?;
+        conn.execute(
+            &format!("DELETE FROM items WHERE id = ?1 OR {}", CHILD_OF_ITEM),
+            params![id],
+        )?;
+        conn.execute("DELETE FROM item_events WHERE item_id = ?1", params![id])?;
+        conn.execute(
+            "DELETE FROM item_links WHERE source_item_id = ?1 OR target_item_id = ?1",
+            params![id],
+        )?;
+        conn.execute("DELETE FROM item_mutes WHERE item_id = ?1", params![id])?;
+        conn.execute("DELETE FROM item_reminders WHERE item_id = ?1", params![id])?;
         Ok(())
     }
 
@@ -364,8 +908,8 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "UPDATE items SET archived = 1, archived_at = ?1, checked = 0 WHERE id = ?2",
-            params![now, id],
+            &format!("UPDATE items SET archived = 1, archived_at = ?2, checked = 0 WHERE id = ?1 OR {}", CHILD_OF_ITEM),
+            params![id, now],
         )?;
         Ok(())
     }
@@ -375,8 +919,8 @@ impl Database {
         let now = chrono::Utc::now().to_rfc3339();
         for id in ids {
             conn.execute(
-                "UPDATE items SET archived = 1, archived_at = ?1, checked = 0 WHERE id = ?2",
-                params![now, id],
+                &format!("UPDATE items SET archived = 1, archived_at = ?2, checked = 0 WHERE id = ?1 OR {}", CHILD_OF_ITEM),
+                params![id, now],
             )?;
         }
         Ok(())
@@ -385,7 +929,7 @@ impl Database {
     pub fn unarchive_item(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "UPDATE items SET archived = 0, archived_at = NULL WHERE id = ?1",
+            &format!("UPDATE items SET archived = 0, archived_at = NULL WHERE id = ?1 OR {}", CHILD_OF_ITEM),
             params![id],
         )?;
         Ok(())
@@ -398,11 +942,37 @@ impl Database {
             "DELETE FROM items WHERE archived = 1 AND archived_at IS NOT NULL AND archived_at < ?1",
             params![cutoff],
         )?;
+        // Drop parser state for sessions no longer tracked by any item. Copilot
+        // state is also read for CLI sessions running Copilot.
+        conn.execute(
+            "DELETE FROM agent_session_state WHERE NOT EXISTS (
+                SELECT 1 FROM items
+                WHERE json_valid(metadata) AND (
+                    (items.type = agent_session_state.item_type
+                     AND json_extract(metadata, '$.agent_session_id') = agent_session_state.session_id)
+                    OR (agent_session_state.item_type = 'copilot_agent'
+                     AND json_extract(metadata, '$.copilot_session_id') = agent_session_state.session_id))
+            )",
+            [],
+        )?;
+        conn.execute(
+            "DELETE FROM item_events WHERE item_id NOT IN (SELECT id FROM items)",
+            [],
+        )?;
+        conn.execute(
+            "DELETE FROM item_links
+             WHERE source_item_id NOT IN (SELECT id FROM items) OR target_item_id NOT IN (SELECT id FROM items)",
+            [],
+        )?;
+        conn.execute("DELETE FROM item_mutes WHERE item_id NOT IN (SELECT id FROM items)", [])?;
+        conn.execute("DELETE FROM item_reminders WHERE item_id NOT IN (SELECT id FROM items)", [])?;
+        let usage_cutoff = (chrono::Local::now() - chrono::Duration::days(90)).format("%Y-%m-%d").to_string();
+        conn.execute("DELETE FROM agent_usage WHERE day < ?1", params![usage_cutoff])?;
         Ok(count as u64)
     }
 
-    /// Auto-archive copilot_agent and cli_session items that have been closed
-    /// for longer than the specified number of minutes.
+    /// Auto-archive agent session items (Copilot, Claude Code, Codex, Gemini, CLI) that have
+    /// been closed for longer than the specified number of minutes.
     pub fn auto_archive_old_closed(&self, closed_minutes: i64) -> Result<u64> {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().to_rfc3339();
@@ -411,7 +981,7 @@ impl Database {
             "UPDATE items SET archived = 1, archived_at = ?1, checked = 0
              WHERE archived = 0
                AND status = 'closed'
-               AND type IN ('copilot_agent', 'cli_session')
+               AND type IN ('copilot_agent', 'claude_session', 'codex_session', 'gemini_session', 'cli_session')
                AND last_updated_at IS NOT NULL
                AND last_updated_at < ?2",
             params![now, cutoff],
@@ -443,24 +1013,26 @@ impl Database {
         Ok(count as u64)
     }
 
-    pub fn get_opencode_session_ids(&self) -> Result<Vec<String>> {
+    /// Item id of every tracked OpenCode session, keyed by (server, session id).
+    pub fn get_opencode_session_items(&self) -> Result<HashMap<(String, String), String>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt =
-            conn.prepare("SELECT metadata FROM items WHERE type = 'opencode_session'")?;
-        let ids = stmt
+            conn.prepare("SELECT id, metadata FROM items WHERE type = 'opencode_session'")?;
+        let items = stmt
             .query_map([], |row| {
-                let meta: String = row.get(0)?;
-                Ok(meta)
+                let id: String = row.get(0)?;
+                let meta: String = row.get(1)?;
+                Ok((id, meta))
             })?
-            .filter_map(|m| {
-                m.ok().and_then(|meta_str| {
-                    serde_json::from_str::<serde_json::Value>(&meta_str)
-                        .ok()
-                        .and_then(|v| v["session_id"].as_str().map(|s| s.to_string()))
+            .filter_map(|r| {
+                r.ok().and_then(|(id, meta_str)| {
+                    let v = serde_json::from_str::<serde_json::Value>(&meta_str).ok()?;
+                    let server = v["server"].as_str().unwrap_or(DEFAULT_OPENCODE_SERVER).to_string();
+                    Some(((server, v["session_id"].as_str()?.to_string()), id))
                 })
             })
             .collect();
-        Ok(ids)
+        Ok(items)
     }
 
     /// Remove any copilot_agent items that track the given copilot session id.
@@ -519,6 +1091,646 @@ impl Database {
         Ok(ids)
     }
 
+    /// Session ids already tracked by an item of a log-based agent type.
+    pub fn get_agent_session_ids(&self, item_type: &str) -> Result<HashSet<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT json_extract(metadata, '$.agent_session_id') FROM items
+             WHERE type = ?1 AND json_valid(metadata)
+               AND json_extract(metadata, '$.agent_session_id') IS NOT NULL",
+        )?;
+        let ids = stmt
+            .query_map(params![item_type], |row| row.get::<_, String>(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+
+    pub fn get_agent_session_state(&self, item_type: &str, session_id: &str) -> Result<Option<(u64, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT byte_offset, state FROM agent_session_state WHERE item_type = ?1 AND session_id = ?2",
+        )?;
+        let mut rows = stmt.query(params![item_type, session_id])?;
+
+        if let Some(row) = rows.next()? {
+            let offset: i64 = row.get(0)?;
+            Ok(Some((offset as u64, row.get(1)?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn save_agent_session_state(&self, item_type: &str, session_id: &str, offset: u64, state: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO agent_session_state (item_type, session_id, byte_offset, state, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![item_type, session_id, offset as i64, state, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// The `github_pr` item tracking `owner/repo#number`, if there is one.
+    pub fn find_github_pr_item(&self, owner: &str, repo: &str, number: u64) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id FROM items
+             WHERE type = 'github_pr' AND json_valid(metadata)
+               AND lower(json_extract(metadata, '$.owner')) = lower(?1)
+               AND lower(json_extract(metadata, '$.repo')) = lower(?2)
+               AND CAST(json_extract(metadata, '$.pr_number') AS TEXT) = ?3
+             ORDER BY archived ASC, created_at DESC LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![owner, repo, number.to_string()])?;
+        Ok(rows.next()?.map(|row| row.get(0)).transpose()?)
+    }
+
+    /// The `cli_session` item a shell hook command was promoted to, found
+    /// by the hook's command id so it can be finished after a restart.
+    pub fn find_shell_command_item(&self, command_id: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id FROM items
+             WHERE type = 'cli_session' AND json_valid(metadata)
+               AND json_extract(metadata, '$.shell_command_id') = ?1
+             ORDER BY created_at DESC LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![command_id])?;
+        Ok(rows.next()?.map(|row| row.get(0)).transpose()?)
+    }
+
+    /// Link two items. Returns false if the link already existed.
+    pub fn add_item_link(&self, source_item_id: &str, target_item_id: &str, relation: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO item_links (source_item_id, target_item_id, relation, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![source_item_id, target_item_id, relation, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Remove a link. Returns false if there was no such link.
+    pub fn remove_item_link(&self, source_item_id: &str, target_item_id: &str, relation: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn.execute(
+            "DELETE FROM item_links WHERE source_item_id = ?1 AND target_item_id = ?2 AND relation = ?3",
+            params![source_item_id, target_item_id, relation],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_item_links(&self) -> Result<Vec<ItemLink>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT source_item_id, target_item_id, relation, created_at FROM item_links ORDER BY created_at ASC",
+        )?;
+        let links = stmt
+            .query_map([], |row| {
+                Ok(ItemLink {
+                    source_item_id: row.get(0)?,
+                    target_item_id: row.get(1)?,
+                    relation: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(links)
+    }
+
+    /// Head branches of the PRs a session is already linked to.
+    pub fn get_linked_pr_branches(&self, session_item_id: &str) -> Result<HashSet<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT json_extract(i.metadata, '$.head_branch') FROM items i
+             INNER JOIN item_links l ON l.source_item_id = i.id AND l.relation = 'produced_by'
+             WHERE l.target_item_id = ?1 AND i.type = 'github_pr' AND json_valid(i.metadata)
+               AND json_extract(i.metadata, '$.head_branch') IS NOT NULL",
+        )?;
+        let branches = stmt
+            .query_map([session_item_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(branches)
+    }
+
+    /// Append an entry to an item's timeline.
+    pub fn add_item_event(&self, item_id: &str, kind: &str, message: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO item_events (item_id, kind, message, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![item_id, kind, message, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// An item's timeline, oldest first.
+    pub fn get_item_events(&self, item_id: &str) -> Result<Vec<ItemEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, kind, message, created_at FROM item_events
+             WHERE item_id = ?1 ORDER BY id",
+        )?;
+        let events = stmt
+            .query_map(params![item_id], |row| {
+                Ok(ItemEvent {
+                    id: row.get(0)?,
+                    item_id: row.get(1)?,
+                    kind: row.get(2)?,
+                    message: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(events)
+    }
+
+    /// Record a session's cumulative usage. The growth since the last
+    /// recording is added to `day`; usage seen for the first time is all
+    /// attributed to `day`.
+    pub fn record_agent_usage(
+        &self,
+        item_id: &str,
+        directory: &str,
+        day: &str,
+        total_tokens: u64,
+        total_cost: f64,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let (recorded_tokens, recorded_cost): (i64, f64) = conn.query_row(
+            "SELECT COALESCE(SUM(tokens), 0), COALESCE(SUM(cost), 0) FROM agent_usage WHERE item_id = ?1",
+            params![item_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let tokens = (total_tokens as i64 - recorded_tokens).max(0);
+        let cost = (total_cost - recorded_cost).max(0.0);
+        if tokens == 0 && cost < 1e-9 {
+            return Ok(());
+        }
+        conn.execute(
+            "INSERT INTO agent_usage (day, item_id, directory, tokens, cost) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(day, item_id) DO UPDATE SET
+                directory = excluded.directory,
+                tokens = tokens + excluded.tokens,
+                cost = cost + excluded.cost",
+            params![day, item_id, directory, tokens, cost],
+        )?;
+        Ok(())
+    }
+
+    /// Usage on `day` per directory, most expensive first.
+    pub fn get_daily_spend(&self, day: &str) -> Result<Vec<DailySpend>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT directory, COUNT(*), SUM(tokens), SUM(cost) FROM agent_usage
+             WHERE day = ?1 GROUP BY directory ORDER BY SUM(cost) DESC, SUM(tokens) DESC",
+        )?;
+        let spend = stmt
+            .query_map(params![day], |row| {
+                Ok(DailySpend {
+                    directory: row.get(0)?,
+                    sessions: row.get(1)?,
+                    tokens: row.get(2)?,
+                    cost: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(spend)
+    }
+
+    pub fn get_opencode_servers(&self) -> Result<Vec<OpenCodeServer>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, url, password, healthy, last_checked_at, last_error
+             FROM opencode_servers ORDER BY name",
+        )?;
+        let servers = stmt
+            .query_map([], |row| {
+                Ok(OpenCodeServer {
+                    name: row.get(0)?,
+                    url: row.get(1)?,
+                    password: row.get(2)?,
+                    healthy: row.get::<_, Option<i32>>(3)?.map(|h| h != 0),
+                    last_checked_at: row.get(4)?,
+                    last_error: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(servers)
+    }
+
+    pub fn get_opencode_server(&self, name: &str) -> Result<Option<OpenCodeServer>> {
+        Ok(self.get_opencode_servers()?.into_iter().find(|s| s.name == name))
+    }
+
+    /// Add or update a server. A `None` password keeps the stored one.
+    /// Health is reset, since it may refer to the old URL.
+    pub fn save_opencode_server(&self, name: &str, url: &str, password: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO opencode_servers (name, url, password) VALUES (?1, ?2, COALESCE(?3, ''))
+             ON CONFLICT(name) DO UPDATE SET
+                url = excluded.url,
+                password = COALESCE(?3, password),
+                healthy = NULL,
+                last_checked_at = NULL,
+                last_error = NULL",
+            params![name, url, password],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_opencode_server(&self, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM opencode_servers WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+
+    pub fn update_opencode_server_health(&self, name: &str, healthy: bool, error: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE opencode_servers SET healthy = ?1, last_checked_at = ?2, last_error = ?3 WHERE name = ?4",
+            params![healthy as i32, chrono::Utc::now().to_rfc3339(), error, name],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_rules(&self) -> Result<Vec<Rule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, enabled, trigger, conditions, actions, created_at
+             FROM rules ORDER BY created_at",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i32>(2)? != 0,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, name, enabled, trigger, conditions, actions, created_at)| {
+                Ok(Rule {
+                    id,
+                    name,
+                    enabled,
+                    trigger: serde_json::from_str(&trigger)?,
+                    conditions: serde_json::from_str(&conditions)?,
+                    actions: serde_json::from_str(&actions)?,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Add a rule, or replace the one with the same id.
+    pub fn save_rule(&self, rule: &Rule) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO rules (id, name, enabled, trigger, conditions, actions, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                enabled = excluded.enabled,
+                trigger = excluded.trigger,
+                conditions = excluded.conditions,
+                actions = excluded.actions",
+            params![
+                rule.id,
+                rule.name,
+                rule.enabled as i32,
+                serde_json::to_string(&rule.trigger)?,
+                serde_json::to_string(&rule.conditions)?,
+                serde_json::to_string(&rule.actions)?,
+                rule.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_rule(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM rules WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn get_webhooks(&self) -> Result<Vec<Webhook>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, url, events, secret, enabled, created_at FROM webhooks ORDER BY created_at",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i32>(4)? != 0,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, url, events, secret, enabled, created_at)| {
+                Ok(Webhook {
+                    id,
+                    url,
+                    events: serde_json::from_str(&events)?,
+                    signed: !secret.is_empty(),
+                    secret,
+                    enabled,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Add or update a webhook. A `None` secret keeps the stored one, an
+    /// empty one stops signing.
+    pub fn save_webhook(&self, webhook: &Webhook, secret: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhooks (id, url, events, secret, enabled, created_at)
+             VALUES (?1, ?2, ?3, COALESCE(?4, ''), ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                url = excluded.url,
+                events = excluded.events,
+                secret = COALESCE(?4, secret),
+                enabled = excluded.enabled",
+            params![
+                webhook.id,
+                webhook.url,
+                serde_json::to_string(&webhook.events)?,
+                secret,
+                webhook.enabled as i32,
+                webhook.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a webhook and its deliveries.
+    pub fn delete_webhook(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM webhook_deliveries WHERE webhook_id = ?1", params![id])?;
+        conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Queue an event for a webhook, due right away.
+    pub fn add_webhook_delivery(&self, webhook_id: &str, item_id: &str, event: &str, payload: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO webhook_deliveries (webhook_id, item_id, event, payload, next_attempt_at, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?5)",
+            params![webhook_id, item_id, event, payload, now],
+        )?;
+        Ok(())
+    }
+
+    /// Pending deliveries due by `now`, oldest first.
+    pub fn get_due_webhook_deliveries(&self, now: &str) -> Result<Vec<WebhookDelivery>> {
+        self.query_webhook_deliveries(
+            "WHERE state = 'pending' AND next_attempt_at <= ?1 ORDER BY id",
+            params![now],
+        )
+    }
+
+    /// The most recent deliveries, newest first.
+    pub fn get_webhook_deliveries(&self, limit: i64) -> Result<Vec<WebhookDelivery>> {
+        self.query_webhook_deliveries("ORDER BY id DESC LIMIT ?1", params![limit])
+    }
+
+    fn query_webhook_deliveries(&self, filter: &str, args: impl rusqlite::Params) -> Result<Vec<WebhookDelivery>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, webhook_id, item_id, event, payload, state, attempts, next_attempt_at,
+                    response_status, last_error, created_at, updated_at
+             FROM webhook_deliveries {}",
+            filter
+        ))?;
+        let deliveries = stmt
+            .query_map(args, |row| {
+                Ok(WebhookDelivery {
+                    id: row.get(0)?,
+                    webhook_id: row.get(1)?,
+                    item_id: row.get(2)?,
+                    event: row.get(3)?,
+                    payload: row.get(4)?,
+                    state: row.get(5)?,
+                    attempts: row.get(6)?,
+                    next_attempt_at: row.get(7)?,
+                    response_status: row.get(8)?,
+                    last_error: row.get(9)?,
+                    created_at: row.get(10)?,
+                    updated_at: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(deliveries)
+    }
+
+    /// Record an attempt at a delivery: `delivered` on success, otherwise
+    /// `pending` until `next_attempt_at`, or `failed` when there's none.
+    pub fn record_webhook_attempt(
+        &self,
+        id: i64,
+        response_status: Option<u16>,
+        error: Option<&str>,
+        next_attempt_at: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let state = match (error, next_attempt_at) {
+            (None, _) => "delivered",
+            (Some(_), Some(_)) => "pending",
+            (Some(_), None) => "failed",
+        };
+        conn.execute(
+            "UPDATE webhook_deliveries SET state = ?1, attempts = attempts + 1, next_attempt_at = ?2,
+                response_status = ?3, last_error = ?4, updated_at = ?5
+             WHERE id = ?6",
+            params![state, next_attempt_at, response_status, error, chrono::Utc::now().to_rfc3339(), id],
+        )?;
+        conn.execute(
+            "DELETE FROM webhook_deliveries WHERE state != 'pending' AND id NOT IN
+                (SELECT id FROM webhook_deliveries WHERE state != 'pending' ORDER BY id DESC LIMIT ?1)",
+            params![WEBHOOK_DELIVERIES_KEPT],
+        )?;
+        Ok(())
+    }
+
+    /// Queue a finished delivery again, due right away.
+    pub fn retry_webhook_delivery(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE webhook_deliveries SET state = 'pending', attempts = 0, next_attempt_at = ?1, updated_at = ?1
+             WHERE id = ?2",
+            params![now, id],
+        )?;
+        Ok(())
+    }
+
+    /// Record a hook run, dropping the oldest beyond the last `HOOK_RUNS_KEPT`.
+    pub fn add_hook_run(&self, run: &HookRun) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO hook_runs (hook, item_id, old_status, new_status, started_at, duration_ms, exit_code, timed_out, stderr)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                run.hook,
+                run.item_id,
+                run.old_status,
+                run.new_status,
+                run.started_at,
+                run.duration_ms,
+                run.exit_code,
+                run.timed_out as i32,
+                run.stderr,
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM hook_runs WHERE id <= (SELECT MAX(id) FROM hook_runs) - ?1",
+            params![HOOK_RUNS_KEPT],
+        )?;
+        Ok(())
+    }
+
+    /// The most recent hook runs, newest first.
+    pub fn get_hook_runs(&self, limit: i64) -> Result<Vec<HookRun>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, hook, item_id, old_status, new_status, started_at, duration_ms, exit_code, timed_out, stderr
+             FROM hook_runs ORDER BY id DESC LIMIT ?1",
+        )?;
+        let runs = stmt
+            .query_map(params![limit], |row| {
+                Ok(HookRun {
+                    id: row.get(0)?,
+                    hook: row.get(1)?,
+                    item_id: row.get(2)?,
+                    old_status: row.get(3)?,
+                    new_status: row.get(4)?,
+                    started_at: row.get(5)?,
+                    duration_ms: row.get(6)?,
+                    exit_code: row.get(7)?,
+                    timed_out: row.get::<_, i32>(8)? != 0,
+                    stderr: row.get(9)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(runs)
+    }
+
+    pub fn set_notification_pref(&self, item_type: &str, category: &str, enabled: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO notification_prefs (item_type, category, enabled) VALUES (?1, ?2, ?3)",
+            params![item_type, category, enabled as i32],
+        )?;
+        Ok(())
+    }
+
+    /// Whether a notification of `category` for `item` should be shown:
+    /// never for a muted item, otherwise as set for the item's type.
+    pub fn notification_enabled(&self, item: &Item, category: Category) -> Result<bool> {
+        Ok(!self.is_item_muted(&item.id)? && self.notification_pref(&item.item_type, category)?)
+    }
+
+    /// Whether `category` is on for `item_type`, falling back to the old
+    /// global setting for the category and then to on.
+    pub fn notification_pref(&self, item_type: &str, category: Category) -> Result<bool> {
+        let enabled: Option<bool> = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT enabled FROM notification_prefs WHERE item_type = ?1 AND category = ?2",
+                params![item_type, category.key()],
+                |row| Ok(row.get::<_, i32>(0)? != 0),
+            )
+            .optional()?
+        };
+        if let Some(enabled) = enabled {
+            return Ok(enabled);
+        }
+        match category.legacy_setting() {
+            Some(key) => Ok(self.get_setting(key)?.map(|v| v != "false").unwrap_or(true)),
+            None => Ok(true),
+        }
+    }
+
+    pub fn set_item_muted(&self, item_id: &str, muted: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if muted {
+            conn.execute(
+                "INSERT OR IGNORE INTO item_mutes (item_id, muted_at) VALUES (?1, ?2)",
+                params![item_id, chrono::Utc::now().to_rfc3339()],
+            )?;
+        } else {
+            conn.execute("DELETE FROM item_mutes WHERE item_id = ?1", params![item_id])?;
+        }
+        Ok(())
+    }
+
+    pub fn is_item_muted(&self, item_id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let muted = conn
+            .query_row("SELECT 1 FROM item_mutes WHERE item_id = ?1", params![item_id], |_| Ok(()))
+            .optional()?;
+        Ok(muted.is_some())
+    }
+
+    pub fn get_muted_item_ids(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT item_id FROM item_mutes")?;
+        let ids = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    pub fn get_item_reminders(&self) -> Result<HashMap<String, ItemReminder>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT item_id, reminded_at, count, snoozed_until FROM item_reminders")?;
+        let reminders = stmt
+            .query_map([], |row| {
+                Ok(ItemReminder {
+                    item_id: row.get(0)?,
+                    reminded_at: row.get(1)?,
+                    count: row.get(2)?,
+                    snoozed_until: row.get(3)?,
+                })
+            })?
+            .map(|r| r.map(|r| (r.item_id.clone(), r)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(reminders)
+    }
+
+    pub fn record_reminder(&self, item_id: &str, reminded_at: &str, count: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO item_reminders (item_id, reminded_at, count) VALUES (?1, ?2, ?3)
+             ON CONFLICT(item_id) DO UPDATE SET reminded_at = ?2, count = ?3",
+            params![item_id, reminded_at, count],
+        )?;
+        Ok(())
+    }
+
+    /// Hold off reminders about an item until `until`; `None` ends a snooze.
+    pub fn snooze_item(&self, item_id: &str, until: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO item_reminders (item_id, snoozed_until) VALUES (?1, ?2)
+             ON CONFLICT(item_id) DO UPDATE SET snoozed_until = ?2",
+            params![item_id, until],
+        )?;
+        Ok(())
+    }
+
     pub fn save_credential(&self, key: &str, value: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
@@ -563,11 +1775,13 @@ impl Database {
 
     pub fn get_visible_items(&self) -> Result<Vec<Item>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
+        // Nested items (e.g. OpenCode sub-agents) are represented by their parent
+        let mut stmt = conn.prepare(&format!(
             "SELECT id, type, title, url, status, previous_status, metadata,
                     last_checked_at, last_updated_at, created_at, archived, polling_interval_override, checked, archived_at
-             FROM items WHERE archived = 0 AND checked = 0 ORDER BY created_at DESC"
-        )?;
+             FROM items WHERE archived = 0 AND checked = 0 AND NOT {} ORDER BY created_at DESC",
+            IS_CHILD_ITEM
+        ))?;
 
         let items = stmt
             .query_map([], |row| {
@@ -596,10 +1810,14 @@ impl Database {
     pub fn count_actionable_items(&self) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
         let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM items
-             WHERE archived = 0
-               AND checked = 0
-                AND status IN ('completed', 'failed', 'updated', 'approved', 'merged', 'waiting', 'input_needed')",
+            &format!(
+                "SELECT COUNT(*) FROM items
+                 WHERE archived = 0
+                   AND checked = 0
+                   AND NOT {}
+                   AND status IN ('completed', 'failed', 'updated', 'approved', 'merged', 'waiting', 'input_needed', 'lost')",
+                IS_CHILD_ITEM
+            ),
             [],
             |row| row.get(0),
         )?;
@@ -628,11 +1846,58 @@ impl Database {
             .map(|v| v != "false")
             .unwrap_or(true);
 
+        let shell_track_threshold_secs = self
+            .get_setting("shell_track_threshold_secs")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(crate::services::cli_session::DEFAULT_SHELL_TRACK_THRESHOLD_SECS);
+
+        let shell_track_ignore = self
+            .get_setting("shell_track_ignore")?
+            .unwrap_or_else(|| crate::services::cli_session::DEFAULT_SHELL_TRACK_IGNORE.to_string());
+
+        let budget_session_tokens = self
+            .get_setting("budget_session_tokens")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        let budget_session_cost = self
+            .get_setting("budget_session_cost")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+
+        let budget_daily_tokens = self
+            .get_setting("budget_daily_tokens")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        let budget_daily_cost = self
+            .get_setting("budget_daily_cost")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+
+        let reminder_interval_minutes = self
+            .get_setting("reminder_interval_minutes")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(crate::services::reminders::DEFAULT_INTERVAL_MINUTES);
+
+        let reminder_loud_sound = self
+            .get_setting("reminder_loud_sound")?
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
         Ok(Settings {
             polling_interval,
             notify_session_started,
             notify_session_ended,
             notify_input_needed,
+            shell_track_threshold_secs,
+            shell_track_ignore,
+            budget_session_tokens,
+            budget_session_cost,
+            budget_daily_tokens,
+            budget_daily_cost,
+            reminder_interval_minutes,
+            reminder_loud_sound,
         })
     }
 
@@ -818,3 +2083,106 @@ impl Database {
         Ok(ids)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(db: &Database, status: &str) -> Item {
+        let item = Item {
+            id: uuid::Uuid::new_v4().to_string(),
+            item_type: "cli_session".to_string(),
+            title: "cargo test".to_string(),
+            url: None,
+            status: status.to_string(),
+            previous_status: None,
+            metadata: serde_json::json!({ "pid": 41 }).to_string(),
+            last_checked_at: None,
+            last_updated_at: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        };
+        db.add_item(&item).unwrap();
+        item
+    }
+
+    fn temp_db_path() -> PathBuf {
+        std::env::temp_dir().join(format!("loop-test-{}.db", uuid::Uuid::new_v4()))
+    }
+
+    fn temp_db() -> Database {
+        Database::new(temp_db_path()).unwrap()
+    }
+
+    #[test]
+    fn status_changes_from_other_processes_are_published() {
+        let path = temp_db_path();
+        let app = Database::new(path.clone()).unwrap();
+        let item = session(&app, "waiting");
+        app.update_item_status(&item.id, "in_progress", None).unwrap();
+        let mut changes = app.subscribe_status_changes();
+
+        let cli = Database::new(path).unwrap();
+        cli.update_item_status(&item.id, "input_needed", None).unwrap();
+        app.update_item_status(&item.id, "completed", None).unwrap();
+        app.publish_external_status_changes().unwrap();
+
+        let received: Vec<(String, String)> = std::iter::from_fn(|| changes.try_recv().ok())
+            .map(|c| (c.from, c.to))
+            .collect();
+        assert_eq!(
+            received,
+            vec![
+                ("in_progress".to_string(), "input_needed".to_string()),
+                ("input_needed".to_string(), "completed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn heartbeat_after_finish_keeps_the_result() {
+        let db = temp_db();
+        let item = session(&db, "in_progress");
+        let finished = serde_json::json!({ "pid": 41, "exit_code": 0, "ended_at": "2026-01-01T00:00:00Z" });
+        db.update_item_status(&item.id, "completed", Some(&finished.to_string())).unwrap();
+
+        assert!(!db.record_heartbeat(&item.id, Some(42)).unwrap());
+
+        let item = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(item.status, "completed");
+        let metadata: serde_json::Value = serde_json::from_str(&item.metadata).unwrap();
+        assert_eq!(metadata["exit_code"], 0);
+        assert_eq!(metadata["ended_at"], "2026-01-01T00:00:00Z");
+        assert_eq!(metadata["pid"], 42);
+        assert!(metadata["last_heartbeat_at"].is_string());
+    }
+
+    #[test]
+    fn heartbeat_revives_lost_sessions() {
+        let db = temp_db();
+        let item = session(&db, "lost");
+
+        assert!(db.record_heartbeat(&item.id, None).unwrap());
+
+        let item = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(item.status, "in_progress");
+        assert_eq!(item.previous_status.as_deref(), Some("lost"));
+        let metadata: serde_json::Value = serde_json::from_str(&item.metadata).unwrap();
+        assert_eq!(metadata["pid"], 41);
+    }
+
+    #[test]
+    fn promoted_shell_commands_are_found_by_their_hook_id() {
+        let db = temp_db();
+        let item = session(&db, "in_progress");
+        let metadata = serde_json::json!({ "source": "shell_hook", "shell_command_id": "4242-1" });
+        db.update_item_status(&item.id, "in_progress", Some(&metadata.to_string())).unwrap();
+        session(&db, "in_progress");
+
+        assert_eq!(db.find_shell_command_item("4242-1").unwrap(), Some(item.id));
+        assert_eq!(db.find_shell_command_item("4242-2").unwrap(), None);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::db::{Database, Item};
+use crate::services::hooks;
+use std::sync::Arc;
+
+/// Run the user's shell hooks for every status change
+/// `Database::update_item_status` publishes, whichever path made it.
+pub fn start(db: Arc<Database>) {
+    let mut changes = db.subscribe_status_changes();
+    tauri::async_runtime::spawn(async move {
+        while let Some(change) = changes.recv().await {
+            match db.get_item(&change.item_id) {
+                Ok(Some(item)) => run_hooks(&db, &item, &change.from, &change.to),
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to load item {} for hooks: {}", change.item_id, e),
+            }
+        }
+    });
+}
+
+/// Run the hooks for `item` going from `old_status` to `new_status` in the
+/// background. `item` is passed to the hooks as it is after the change.
+pub fn run_hooks(db: &Arc<Database>, item: &Item, old_status: &str, new_status: &str) {
+    let hooks = match hooks::hooks_dir() {
+        Some(dir) => hooks::hooks_for(&dir, new_status),
+        None => return,
+    };
+    for hook in hooks {
+        let db = db.clone();
+        let item = item.clone();
+        let old_status = old_status.to_string();
+        let new_status = new_status.to_string();
+        tauri::async_runtime::spawn(async move {
+            let run = hooks::run_hook(&hook, &item, &old_status, &new_status, hooks::TIMEOUT).await;
+            if let Err(e) = db.add_hook_run(&run) {
+                eprintln!("Failed to record hook run: {}", e);
+            }
+        });
+    }
+}
@@ -1,11 +1,17 @@
 pub mod commands;
+pub mod copilot_watcher;
 pub mod db;
+pub mod hook_runner;
 pub mod local_server;
 pub mod mcp;
+pub mod opencode_stream;
 pub mod polling;
+pub mod reminder_scheduler;
+pub mod rule_engine;
 pub mod services;
 pub mod shortcut;
 pub mod tray;
+pub mod webhook_queue;
 
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 pub mod updater;
@@ -1,21 +1,48 @@
 use crate::db::{Database, Item};
+use crate::polling::PollingManager;
+use crate::services::notifications::Category;
+use crate::services::{cli_session, copilot_cli, github_pr, github_webhook, webhooks};
+use crate::tray;
 use axum::{
+    body::Bytes,
     extract::{Path, State as AxumState},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{patch, post},
     Json, Router,
 };
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 use uuid::Uuid;
 
+/// How many lines of combined command output are kept in item metadata.
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// How many of the trailing output lines are shown in a failure notification.
+const NOTIFICATION_ERROR_LINES: usize = 3;
+
+/// How long a shell command waits for its finish before it is forgotten.
+/// A tracked one can still be finished later through its item.
+const PENDING_SHELL_COMMAND_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long the id of a shell command that finished before its start
+/// arrived is kept, so the late start is dropped.
+const FINISHED_SHELL_COMMAND_TTL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Serialize, Deserialize)]
 struct CreateSessionRequest {
     command: String,
     title: String,
     #[serde(default)]
     cwd: Option<String>,
+    #[serde(default)]
+    pid: Option<u32>,
+    #[serde(default)]
+    started_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,18 +53,108 @@ struct CreateSessionResponse {
 #[derive(Debug, Serialize, Deserialize)]
 struct UpdateSessionRequest {
     status: String,
+    #[serde(default)]
+    exit_code: Option<i32>,
+    #[serde(default)]
+    ended_at: Option<String>,
+    /// Last lines of combined stdout/stderr, newline separated.
+    #[serde(default)]
+    output_tail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HeartbeatRequest {
+    #[serde(default)]
+    pid: Option<u32>,
+}
+
+/// Reported by the shell preexec hook. `id` is generated by the shell so the
+/// hooks never have to wait for a response.
+#[derive(Debug, Serialize, Deserialize)]
+struct ShellCommandStartRequest {
+    id: String,
+    command: String,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    pid: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ShellCommandFinishRequest {
+    #[serde(default)]
+    exit_code: Option<i32>,
+}
+
+/// A command reported by the shell hooks that has not finished yet.
+/// It only becomes a `cli_session` item once it outlives the threshold.
+struct PendingShellCommand {
+    command: String,
+    cwd: Option<String>,
+    pid: Option<u32>,
+    started_at: String,
+    item_id: Option<String>,
+    reported_at: Instant,
+}
+
+/// Shell commands between their start and finish. The hooks send both in
+/// the background, so a finish can arrive before its start.
+#[derive(Default)]
+struct ShellCommands {
+    pending: HashMap<String, PendingShellCommand>,
+    finished_early: HashMap<String, Instant>,
+}
+
+impl ShellCommands {
+    /// Record a started command; false if its finish already arrived.
+    fn start(&mut self, id: String, command: PendingShellCommand) -> bool {
+        self.evict(command.reported_at);
+        if self.finished_early.remove(&id).is_some() {
+            return false;
+        }
+        self.pending.insert(id, command);
+        true
+    }
+
+    /// Take the pending command finished by `id`, remembering ids whose
+    /// start hasn't arrived yet.
+    fn finish(&mut self, id: &str, now: Instant) -> Option<PendingShellCommand> {
+        self.evict(now);
+        let pending = self.pending.remove(id);
+        if pending.is_none() {
+            self.finished_early.insert(id.to_string(), now);
+        }
+        pending
+    }
+
+    fn evict(&mut self, now: Instant) {
+        self.pending
+            .retain(|_, command| now.duration_since(command.reported_at) < PENDING_SHELL_COMMAND_TTL);
+        self.finished_early
+            .retain(|_, finished_at| now.duration_since(*finished_at) < FINISHED_SHELL_COMMAND_TTL);
+    }
 }
 
 pub struct LocalServerState {
     pub db: Arc<Database>,
+    pub app_handle: AppHandle,
+    shell_commands: Mutex<ShellCommands>,
 }
 
-pub async fn start_local_server(db: Arc<Database>) -> anyhow::Result<()> {
-    let state = LocalServerState { db };
+pub async fn start_local_server(db: Arc<Database>, app_handle: AppHandle) -> anyhow::Result<()> {
+    let state = LocalServerState {
+        db,
+        app_handle,
+        shell_commands: Mutex::new(ShellCommands::default()),
+    };
 
     let app = Router::new()
         .route("/api/sessions", post(create_session))
         .route("/api/sessions/:id", patch(update_session))
+        .route("/api/sessions/:id/heartbeat", post(heartbeat_session))
+        .route("/api/shell/commands", post(start_shell_command))
+        .route("/api/shell/commands/:id/finish", post(finish_shell_command))
+        .route("/api/github/webhook", post(github_webhook))
         .with_state(Arc::new(state));
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:19532").await?;
@@ -59,11 +176,18 @@ async fn create_session(
     let id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
-    let mut metadata = std::collections::HashMap::new();
-    metadata.insert("command".to_string(), payload.command);
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("command".to_string(), serde_json::json!(payload.command));
     if let Some(ref cwd) = payload.cwd {
-        metadata.insert("cwd".to_string(), cwd.clone());
+        metadata.insert("cwd".to_string(), serde_json::json!(cwd));
     }
+    if let Some(pid) = payload.pid {
+        metadata.insert("pid".to_string(), serde_json::json!(pid));
+    }
+    metadata.insert(
+        "started_at".to_string(),
+        serde_json::json!(payload.started_at.unwrap_or_else(|| now.clone())),
+    );
 
     let item = Item {
         id: id.clone(),
@@ -83,10 +207,13 @@ async fn create_session(
     };
 
     match state.db.add_item(&item) {
-        Ok(_) => (
-            StatusCode::CREATED,
-            Json(CreateSessionResponse { id }),
-        ),
+        Ok(_) => {
+            let _ = state.app_handle.emit("item-updated", &id);
+            (
+                StatusCode::CREATED,
+                Json(CreateSessionResponse { id }),
+            )
+        }
         Err(e) => {
             eprintln!("Failed to create session: {}", e);
             (
@@ -104,7 +231,23 @@ async fn update_session(
     Path(id): Path<String>,
     Json(payload): Json<UpdateSessionRequest>,
 ) -> impl IntoResponse {
-    match state.db.update_item_status(&id, &payload.status, None) {
+    let item = match state.db.get_item(&id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            eprintln!("Failed to load session {}: {}", id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    match finish_session(
+        &state,
+        &item,
+        &payload.status,
+        payload.exit_code,
+        payload.ended_at,
+        payload.output_tail.as_deref(),
+    ) {
         Ok(_) => StatusCode::OK,
         Err(e) => {
             eprintln!("Failed to update session: {}", e);
@@ -112,3 +255,434 @@ async fn update_session(
         }
     }
 }
+
+/// Apply a status update to a CLI session, recording exit code, timing and
+/// output tail, and notify when the command finished.
+fn finish_session(
+    state: &LocalServerState,
+    item: &Item,
+    status: &str,
+    exit_code: Option<i32>,
+    ended_at: Option<String>,
+    output_tail: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut metadata = serde_json::from_str::<serde_json::Value>(&item.metadata)
+        .unwrap_or_else(|_| serde_json::json!({}));
+    if !metadata.is_object() {
+        metadata = serde_json::json!({});
+    }
+
+    let output_tail = output_tail.map(tail_lines);
+    let is_final = exit_code.is_some() || matches!(status, "completed" | "failed");
+
+    if let Some(map) = metadata.as_object_mut() {
+        if let Some(code) = exit_code {
+            map.insert("exit_code".to_string(), serde_json::json!(code));
+        }
+        if let Some(ref lines) = output_tail {
+            map.insert("output_tail".to_string(), serde_json::json!(lines));
+        }
+        if is_final {
+            let ended_at = ended_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+            let started_at = map
+                .get("started_at")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&item.created_at)
+                .to_string();
+            if let Some(secs) = duration_secs(&started_at, &ended_at) {
+                map.insert("duration_secs".to_string(), serde_json::json!(secs));
+            }
+            map.insert("ended_at".to_string(), serde_json::json!(ended_at));
+        }
+    }
+
+    state
+        .db
+        .update_item_status(&item.id, status, Some(&metadata.to_string()))?;
+
+    if status != item.status && matches!(status, "completed" | "failed") {
+        let duration = metadata["duration_secs"].as_i64();
+        let body = cli_completion_body(
+            exit_code,
+            duration,
+            output_tail.as_deref().unwrap_or(&[]),
+        );
+        PollingManager::notify(&state.db, &state.app_handle, item, Category::SessionEnded, &body);
+    }
+
+    let _ = state.app_handle.emit("item-updated", &item.id);
+    tray::refresh_tray(&state.app_handle, &state.db);
+
+    Ok(())
+}
+
+/// Record that the wrapper for a CLI session is still alive.
+/// A session previously marked "lost" (e.g. after the machine slept) is revived.
+async fn heartbeat_session(
+    AxumState(state): AxumState<Arc<LocalServerState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<HeartbeatRequest>,
+) -> impl IntoResponse {
+    let item = match state.db.get_item(&id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            eprintln!("Failed to load session {}: {}", id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    let revived = match state.db.record_heartbeat(&item.id, payload.pid) {
+        Ok(revived) => revived,
+        Err(e) => {
+            eprintln!("Failed to record heartbeat: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    if revived {
+        let _ = state.app_handle.emit("item-updated", &id);
+        tray::refresh_tray(&state.app_handle, &state.db);
+    }
+
+    StatusCode::OK
+}
+
+async fn start_shell_command(
+    AxumState(state): AxumState<Arc<LocalServerState>>,
+    Json(payload): Json<ShellCommandStartRequest>,
+) -> impl IntoResponse {
+    let settings = match state.db.get_all_settings() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to load settings: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    if cli_session::is_ignored_command(&payload.command, &settings.shell_track_ignore) {
+        return StatusCode::NO_CONTENT;
+    }
+
+    let started = state.shell_commands.lock().unwrap().start(
+        payload.id.clone(),
+        PendingShellCommand {
+            command: payload.command,
+            cwd: payload.cwd,
+            pid: payload.pid,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            item_id: None,
+            reported_at: Instant::now(),
+        },
+    );
+    if !started {
+        return StatusCode::NO_CONTENT;
+    }
+
+    let threshold = settings.shell_track_threshold_secs.max(0) as u64;
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(threshold)).await;
+        promote_shell_command(&state, &payload.id);
+    });
+
+    StatusCode::ACCEPTED
+}
+
+/// Turn a still-running shell command into a `cli_session` item.
+fn promote_shell_command(state: &LocalServerState, key: &str) {
+    let item = {
+        let mut commands = state.shell_commands.lock().unwrap();
+        let entry = match commands.pending.get_mut(key) {
+            Some(e) if e.item_id.is_none() => e,
+            _ => return,
+        };
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("command".to_string(), serde_json::json!(entry.command));
+        if let Some(ref cwd) = entry.cwd {
+            metadata.insert("cwd".to_string(), serde_json::json!(cwd));
+        }
+        if let Some(pid) = entry.pid {
+            metadata.insert("pid".to_string(), serde_json::json!(pid));
+        }
+        metadata.insert("started_at".to_string(), serde_json::json!(entry.started_at));
+        metadata.insert("source".to_string(), serde_json::json!("shell_hook"));
+        metadata.insert("shell_command_id".to_string(), serde_json::json!(key));
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let item = Item {
+            id: Uuid::new_v4().to_string(),
+            item_type: "cli_session".to_string(),
+            title: copilot_cli::truncate_title(&format!("CLI: {}", entry.command)),
+            url: None,
+            status: "in_progress".to_string(),
+            previous_status: None,
+            metadata: serde_json::to_string(&metadata).unwrap_or_else(|_| "{}".to_string()),
+            last_checked_at: Some(now.clone()),
+            last_updated_at: Some(now),
+            created_at: entry.started_at.clone(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        };
+        entry.item_id = Some(item.id.clone());
+        item
+    };
+
+    if let Err(e) = state.db.add_item(&item) {
+        eprintln!("Failed to track shell command: {}", e);
+        state.shell_commands.lock().unwrap().pending.remove(key);
+        return;
+    }
+    let _ = state.app_handle.emit("item-updated", &item.id);
+    tray::refresh_tray(&state.app_handle, &state.db);
+}
+
+async fn finish_shell_command(
+    AxumState(state): AxumState<Arc<LocalServerState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<ShellCommandFinishRequest>,
+) -> impl IntoResponse {
+    let pending = state.shell_commands.lock().unwrap().finish(&id, Instant::now());
+
+    // Commands that finished under the threshold were never tracked. Pending
+    // commands aren't kept across restarts, so a command promoted before one
+    // is found by its id in the item's metadata.
+    let item_id = match pending {
+        Some(pending) => pending.item_id,
+        None => match state.db.find_shell_command_item(&id) {
+            Ok(item_id) => item_id,
+            Err(e) => {
+                eprintln!("Failed to look up shell command {}: {}", id, e);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        },
+    };
+    let item_id = match item_id {
+        Some(item_id) => item_id,
+        None => return StatusCode::OK,
+    };
+
+    let item = match state.db.get_item(&item_id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return StatusCode::OK,
+        Err(e) => {
+            eprintln!("Failed to load shell command {}: {}", item_id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    let status = if payload.exit_code.unwrap_or(0) == 0 { "completed" } else { "failed" };
+    match finish_session(&state, &item, status, payload.exit_code, None, None) {
+        Ok(_) => StatusCode::OK,
+        Err(e) => {
+            eprintln!("Failed to finish shell command: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// GitHub webhook deliveries, e.g. relayed by smee. Signed deliveries
+/// update matching PR and Actions items right away.
+async fn github_webhook(
+    AxumState(state): AxumState<Arc<LocalServerState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, String) {
+    let secret = match state.db.get_credential("github_webhook_secret") {
+        Ok(Some(secret)) if !secret.is_empty() => secret,
+        _ => return (StatusCode::FORBIDDEN, "GitHub webhook secret not configured".to_string()),
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if !webhooks::verify(&secret, &body, header(github_webhook::SIGNATURE_HEADER)) {
+        return (StatusCode::UNAUTHORIZED, "Invalid signature".to_string());
+    }
+    let payload: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)),
+    };
+    let event = match github_webhook::parse_event(header(github_webhook::EVENT_HEADER), &payload) {
+        Some(event) => event,
+        None => return (StatusCode::OK, "Ignored".to_string()),
+    };
+
+    match apply_github_event(&state, &event) {
+        Ok(updated) => {
+            if updated > 0 {
+                tray::refresh_tray(&state.app_handle, &state.db);
+            }
+            (StatusCode::OK, format!("Updated {} items", updated))
+        }
+        Err(e) => {
+            eprintln!("Failed to apply GitHub webhook: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+    }
+}
+
+fn apply_github_event(state: &LocalServerState, event: &github_webhook::GithubEvent) -> anyhow::Result<usize> {
+    let mut updated = 0;
+    for item in state.db.get_items(false)? {
+        let update = match github_webhook::apply(event, &item) {
+            Some(update) => update,
+            None => continue,
+        };
+        if let Some(title) = &update.title {
+            state.db.update_item_title(&item.id, title)?;
+        }
+        state
+            .db
+            .update_item_status(&item.id, &update.status, Some(&update.metadata.to_string()))?;
+        PollingManager::notify_transition(&state.db, &state.app_handle, &item, &update.status);
+        let old_metadata = serde_json::from_str::<serde_json::Value>(&item.metadata).unwrap_or_default();
+        if item.item_type == "github_pr"
+            && github_pr::ci_newly_failed(old_metadata.get("ci_status"), update.metadata.get("ci_status"))
+        {
+            PollingManager::notify(&state.db, &state.app_handle, &item, Category::CiFailed, "CI failed");
+        }
+        let _ = state.app_handle.emit("item-updated", &item.id);
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// Strip ANSI escape sequences and keep the last `OUTPUT_TAIL_LINES` lines.
+fn tail_lines(output: &str) -> Vec<String> {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]|\x1b\][^\x07]*\x07").unwrap());
+    let cleaned = ansi.replace_all(output, "");
+    let lines: Vec<String> = cleaned
+        .lines()
+        .map(|l| l.trim_end_matches('\r').to_string())
+        .collect();
+    let skip = lines.len().saturating_sub(OUTPUT_TAIL_LINES);
+    lines.into_iter().skip(skip).collect()
+}
+
+fn duration_secs(started_at: &str, ended_at: &str) -> Option<i64> {
+    let start = chrono::DateTime::parse_from_rfc3339(started_at).ok()?;
+    let end = chrono::DateTime::parse_from_rfc3339(ended_at).ok()?;
+    Some(end.signed_duration_since(start).num_seconds().max(0))
+}
+
+fn format_duration(secs: i64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// Build the notification body for a finished CLI command.
+/// Failures include the exit code and the last few non-empty output lines.
+fn cli_completion_body(exit_code: Option<i32>, duration_secs: Option<i64>, output_tail: &[String]) -> String {
+    let took = duration_secs
+        .map(|s| format!(" after {}", format_duration(s)))
+        .unwrap_or_default();
+
+    match exit_code {
+        Some(0) | None => format!("Command finished{}", took),
+        Some(code) => {
+            let mut body = format!("Command failed with exit code {}{}", code, took);
+            let error_lines: Vec<&str> = output_tail
+                .iter()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .collect();
+            let skip = error_lines.len().saturating_sub(NOTIFICATION_ERROR_LINES);
+            for line in &error_lines[skip..] {
+                body.push('\n');
+                body.push_str(line);
+            }
+            body
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_lines_keeps_last_lines_without_ansi() {
+        let output: String = (1..=30)
+            .map(|i| format!("\x1b[31mline {}\x1b[0m\r\n", i))
+            .collect();
+        let tail = tail_lines(&output);
+        assert_eq!(tail.len(), OUTPUT_TAIL_LINES);
+        assert_eq!(tail.first().unwrap(), "line 11");
+        assert_eq!(tail.last().unwrap(), "line 30");
+    }
+
+    #[test]
+    fn duration_is_computed_from_rfc3339() {
+        assert_eq!(
+            duration_secs("2024-01-01T10:00:00Z", "2024-01-01T10:02:05Z"),
+            Some(125)
+        );
+        assert_eq!(duration_secs("garbage", "2024-01-01T10:02:05Z"), None);
+    }
+
+    #[test]
+    fn success_body_mentions_duration() {
+        assert_eq!(cli_completion_body(Some(0), Some(125), &[]), "Command finished after 2m 5s");
+    }
+
+    #[test]
+    fn failure_body_includes_exit_code_and_last_error_lines() {
+        let tail: Vec<String> = ["running tests", "", "test a ... FAILED", "error: 1 test failed", "npm ERR! Test failed."]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let body = cli_completion_body(Some(1), None, &tail);
+        assert_eq!(
+            body,
+            "Command failed with exit code 1\ntest a ... FAILED\nerror: 1 test failed\nnpm ERR! Test failed."
+        );
+    }
+
+    fn shell_command(reported_at: Instant) -> PendingShellCommand {
+        PendingShellCommand {
+            command: "cargo build".to_string(),
+            cwd: None,
+            pid: Some(41),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            item_id: None,
+            reported_at,
+        }
+    }
+
+    #[test]
+    fn a_start_arriving_after_its_finish_is_dropped() {
+        let now = Instant::now();
+        let mut commands = ShellCommands::default();
+
+        assert!(commands.finish("41-1", now).is_none());
+        assert!(!commands.start("41-1".to_string(), shell_command(now)));
+        assert!(commands.pending.is_empty());
+
+        assert!(commands.start("41-2".to_string(), shell_command(now)));
+        assert!(commands.finish("41-2", now).is_some());
+        assert!(commands.finished_early.is_empty());
+    }
+
+    #[test]
+    fn shell_commands_without_a_finish_are_forgotten() {
+        let now = Instant::now();
+        let mut commands = ShellCommands::default();
+        commands.start("41-1".to_string(), shell_command(now));
+        commands.finish("41-2", now);
+
+        commands.start("41-3".to_string(), shell_command(now + FINISHED_SHELL_COMMAND_TTL));
+        assert!(commands.finished_early.is_empty());
+        assert_eq!(commands.pending.len(), 2);
+
+        commands.start("41-4".to_string(), shell_command(now + PENDING_SHELL_COMMAND_TTL));
+        assert!(!commands.pending.contains_key("41-1"));
+        assert!(commands.pending.contains_key("41-4"));
+    }
+}
@@ -1,7 +1,9 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use in_the_loop_lib::{commands, db, local_server, polling, shortcut, tray};
+use in_the_loop_lib::{
+    commands, copilot_watcher, db, hook_runner, local_server, opencode_stream, polling, reminder_scheduler, rule_engine, shortcut, tray, webhook_queue,
+};
 use std::sync::Arc;
 use tauri::{Manager, WindowEvent};
 use tokio::sync::Mutex;
@@ -73,19 +75,53 @@ fn main() {
 
             // Start local server for CLI wrapper
             let db_clone = database.clone();
+            let server_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = local_server::start_local_server(db_clone).await {
+                if let Err(e) = local_server::start_local_server(db_clone, server_handle).await {
                     eprintln!("Failed to start local server: {}", e);
                 }
             });
 
             // Start polling manager
-            let polling_manager =
-                polling::PollingManager::new(database.clone(), app.handle().clone());
+            let opencode_live = Arc::new(opencode_stream::OpenCodeLive::new());
+            let polling_manager = polling::PollingManager::new(
+                database.clone(),
+                app.handle().clone(),
+                opencode_live.clone(),
+            );
             tauri::async_runtime::spawn(async move {
                 polling_manager.start().await;
             });
 
+            // Watch Copilot CLI session files for immediate status updates
+            copilot_watcher::start(database.clone(), app.handle().clone());
+
+            // Follow OpenCode's event stream; polling takes over while it's down
+            opencode_stream::start(database.clone(), app.handle().clone(), opencode_live);
+
+            // Publish status changes made by the MCP server and the loop CLI
+            let db_clone = database.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    if let Err(e) = db_clone.publish_external_status_changes() {
+                        eprintln!("Failed to read status changes from other processes: {}", e);
+                    }
+                }
+            });
+
+            // Run the user's shell hooks on status transitions
+            hook_runner::start(database.clone());
+
+            // Run user rules on status transitions
+            rule_engine::start(database.clone(), app.handle().clone());
+
+            // Send status changes to outgoing webhooks, retrying failed deliveries
+            webhook_queue::start(database.clone());
+
+            // Remind about items left waiting on the user
+            reminder_scheduler::start(database.clone(), app.handle().clone());
+
             // Setup system tray
             tray::setup_tray(app)?;
 
@@ -126,6 +162,36 @@ fn main() {
             commands::unarchive_item,
             commands::archive_closed_items,
             commands::archive_stale_items,
+            commands::get_session_transcript,
+            commands::answer_opencode_question,
+            commands::send_opencode_prompt,
+            commands::abort_opencode_session,
+            commands::start_opencode_session,
+            commands::get_opencode_directories,
+            commands::get_opencode_servers,
+            commands::save_opencode_server,
+            commands::remove_opencode_server,
+            commands::get_item_timeline,
+            commands::get_daily_spend,
+            commands::get_item_links,
+            commands::link_items,
+            commands::unlink_items,
+            commands::get_rules,
+            commands::save_rule,
+            commands::delete_rule,
+            commands::dry_run_rules,
+            commands::get_hooks,
+            commands::get_webhooks,
+            commands::save_webhook,
+            commands::delete_webhook,
+            commands::get_webhook_deliveries,
+            commands::retry_webhook_delivery,
+            commands::get_notification_matrix,
+            commands::set_notification_pref,
+            commands::set_item_muted,
+            commands::get_muted_items,
+            commands::snooze_item,
+            commands::get_item_reminders,
             commands::toggle_checked,
             commands::save_credentials,
             commands::save_settings,
@@ -1,4 +1,9 @@
-use crate::db::{Database, Item, TodoWithBindings};
+use crate::db::{DailySpend, Database, Item, ItemLink, TodoWithBindings};
+use crate::services::budget;
+use crate::services::item_links::{self, Relation};
+use crate::services::opencode::{self, PendingQuestion};
+use crate::services::opencode_control;
+use crate::services::transcript::{self, TranscriptPage, TranscriptStep};
 use crate::services::url_parser;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -176,6 +181,118 @@ impl McpHandler {
                             },
                             "required": ["todo_id", "item_id"]
                         }
+                    },
+                    {
+                        "name": "link_items",
+                        "description": "Link two tracked items, read as '<source> <relation> <target>'. Relations: 'blocks' (the target waits on the source; you are notified when everything blocking an item is done or a blocker fails), 'produced_by' (e.g. a PR produced by an agent session) and 'related_to'.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "source_id": {
+                                    "type": "string",
+                                    "description": "ID of the source item"
+                                },
+                                "target_id": {
+                                    "type": "string",
+                                    "description": "ID of the target item"
+                                },
+                                "relation": {
+                                    "type": "string",
+                                    "enum": ["blocks", "produced_by", "related_to"],
+                                    "description": "How the source relates to the target"
+                                }
+                            },
+                            "required": ["source_id", "target_id", "relation"]
+                        }
+                    },
+                    {
+                        "name": "unlink_items",
+                        "description": "Remove a link created with link_items.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "source_id": { "type": "string", "description": "ID of the source item" },
+                                "target_id": { "type": "string", "description": "ID of the target item" },
+                                "relation": {
+                                    "type": "string",
+                                    "enum": ["blocks", "produced_by", "related_to"],
+                                    "description": "Relation of the link to remove"
+                                }
+                            },
+                            "required": ["source_id", "target_id", "relation"]
+                        }
+                    },
+                    {
+                        "name": "get_item_links",
+                        "description": "List the links from and to a tracked item, and whether the items blocking it are done.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "item_id": {
+                                    "type": "string",
+                                    "description": "ID of the tracked item"
+                                }
+                            },
+                            "required": ["item_id"]
+                        }
+                    },
+                    {
+                        "name": "get_session_transcript",
+                        "description": "Get the conversation of a tracked agent session (Copilot CLI or OpenCode): user messages, assistant replies and tool calls. Returns the newest turns first page; use 'before' to page back.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "item_id": {
+                                    "type": "string",
+                                    "description": "ID of the tracked session item"
+                                },
+                                "before": {
+                                    "type": "integer",
+                                    "description": "Optional turn index to page back from, as returned by a previous call"
+                                },
+                                "limit": {
+                                    "type": "integer",
+                                    "description": "Optional number of turns to return. Default: 10"
+                                }
+                            },
+                            "required": ["item_id"]
+                        }
+                    },
+                    {
+                        "name": "answer_opencode_question",
+                        "description": "Answer the question an OpenCode session is waiting on (shown as 'Question' in list_items). Use 'answer' for a single question, or 'answers' with one list of chosen option labels (or free text) per question.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "item_id": {
+                                    "type": "string",
+                                    "description": "ID of the OpenCode session item"
+                                },
+                                "answer": {
+                                    "type": "string",
+                                    "description": "Option label or free-text answer when the session asked a single question"
+                                },
+                                "answers": {
+                                    "type": "array",
+                                    "items": { "type": "array", "items": { "type": "string" } },
+                                    "description": "One list of selected labels (or free text) per question, in order"
+                                }
+                            },
+                            "required": ["item_id"]
+                        }
+                    },
+                    {
+                        "name": "get_daily_spend",
+                        "description": "Summarize token usage and cost of agent sessions (OpenCode, Copilot CLI) on one day, per directory or repository.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "date": {
+                                    "type": "string",
+                                    "description": "Day as YYYY-MM-DD in local time. Default: today"
+                                }
+                            }
+                        }
                     }
                 ]
             }),
@@ -199,6 +316,12 @@ impl McpHandler {
             "add_item" => self.tool_add_item(id, &arguments),
             "add_todo" => self.tool_add_todo(id, &arguments),
             "bind_todo_to_item" => self.tool_bind_todo_to_item(id, &arguments),
+            "link_items" => self.tool_link_items(id, &arguments, true),
+            "unlink_items" => self.tool_link_items(id, &arguments, false),
+            "get_item_links" => self.tool_get_item_links(id, &arguments),
+            "get_session_transcript" => self.tool_get_session_transcript(id, &arguments),
+            "answer_opencode_question" => self.tool_answer_opencode_question(id, &arguments),
+            "get_daily_spend" => self.tool_get_daily_spend(id, &arguments),
             _ => JsonRpcResponse::error(id, -32602, format!("Unknown tool: {}", tool_name)),
         }
     }
@@ -211,7 +334,8 @@ impl McpHandler {
 
         match self.db.get_items(include_archived) {
             Ok(items) => {
-                let text = format_items(&items);
+                let links = self.db.get_item_links().unwrap_or_default();
+                let text = format_items(&items, &links);
                 JsonRpcResponse::success(
                     id,
                     json!({
@@ -256,6 +380,38 @@ impl McpHandler {
         }
     }
 
+    fn tool_get_daily_spend(&self, id: Option<Value>, arguments: &Value) -> JsonRpcResponse {
+        let day = match arguments.get("date").and_then(|v| v.as_str()) {
+            Some(d) if chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err() => {
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": "Error: 'date' must be YYYY-MM-DD" }],
+                        "isError": true
+                    }),
+                );
+            }
+            Some(d) => d.to_string(),
+            None => budget::today(),
+        };
+
+        match self.db.get_daily_spend(&day) {
+            Ok(spend) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": format_daily_spend(&day, &spend) }]
+                }),
+            ),
+            Err(e) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": format!("Error: {}", e) }],
+                    "isError": true
+                }),
+            ),
+        }
+    }
+
     fn tool_add_item(&self, id: Option<Value>, arguments: &Value) -> JsonRpcResponse {
         let url = match arguments.get("url").and_then(|v| v.as_str()) {
             Some(u) => u,
@@ -419,11 +575,218 @@ impl McpHandler {
             ),
         }
     }
+
+    fn tool_link_items(&self, id: Option<Value>, arguments: &Value, link: bool) -> JsonRpcResponse {
+        let arg = |key: &str| arguments.get(key).and_then(|v| v.as_str());
+        let (source_id, target_id, relation) = match (arg("source_id"), arg("target_id"), arg("relation")) {
+            (Some(s), Some(t), Some(r)) => (s, t, r),
+            _ => {
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": "Error: 'source_id', 'target_id' and 'relation' parameters are required" }],
+                        "isError": true
+                    }),
+                );
+            }
+        };
+
+        let result = if link {
+            self.link_items(source_id, target_id, relation)
+                .map(|_| format!("Linked: {} {} {}", source_id, relation, target_id))
+        } else {
+            self.db
+                .remove_item_link(source_id, target_id, relation)
+                .map(|removed| match removed {
+                    true => format!("Unlinked: {} {} {}", source_id, relation, target_id),
+                    false => "No such link.".to_string(),
+                })
+        };
+
+        match result {
+            Ok(text) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": text }]
+                }),
+            ),
+            Err(e) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": format!("Error: {}", e) }],
+                    "isError": true
+                }),
+            ),
+        }
+    }
+
+    fn link_items(&self, source_id: &str, target_id: &str, relation: &str) -> anyhow::Result<()> {
+        for item_id in [source_id, target_id] {
+            if self.db.get_item(item_id)?.is_none() {
+                return Err(anyhow::anyhow!("Item {} not found", item_id));
+            }
+        }
+        let links = self.db.get_item_links()?;
+        let relation = item_links::check_new_link(&links, source_id, target_id, relation)?;
+        self.db.add_item_link(source_id, target_id, relation.as_str())?;
+        Ok(())
+    }
+
+    fn tool_get_item_links(&self, id: Option<Value>, arguments: &Value) -> JsonRpcResponse {
+        let item_id = match arguments.get("item_id").and_then(|v| v.as_str()) {
+            Some(i) => i,
+            None => {
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": "Error: 'item_id' parameter is required" }],
+                        "isError": true
+                    }),
+                );
+            }
+        };
+
+        match self.item_links_text(item_id) {
+            Ok(text) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": text }]
+                }),
+            ),
+            Err(e) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": format!("Error: {}", e) }],
+                    "isError": true
+                }),
+            ),
+        }
+    }
+
+    fn item_links_text(&self, item_id: &str) -> anyhow::Result<String> {
+        let item = self
+            .db
+            .get_item(item_id)?
+            .ok_or_else(|| anyhow::anyhow!("Item {} not found", item_id))?;
+        // Linked items may be archived
+        let mut items = self.db.get_items(false)?;
+        items.extend(self.db.get_items(true)?);
+        let links = self.db.get_item_links()?;
+        Ok(format_item_links(&item, &items, &links))
+    }
+
+    fn tool_get_session_transcript(&self, id: Option<Value>, arguments: &Value) -> JsonRpcResponse {
+        let item_id = match arguments.get("item_id").and_then(|v| v.as_str()) {
+            Some(i) => i,
+            None => {
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": "Error: 'item_id' parameter is required" }],
+                        "isError": true
+                    }),
+                );
+            }
+        };
+        let before = arguments.get("before").and_then(|v| v.as_u64()).map(|v| v as usize);
+        let limit = arguments.get("limit").and_then(|v| v.as_u64()).map(|v| v as usize);
+
+        let result = self.db.get_item(item_id).and_then(|item| {
+            let item = item.ok_or_else(|| anyhow::anyhow!("Item not found: {}", item_id))?;
+            // OpenCode transcripts come from its HTTP API
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let page = runtime.block_on(transcript::load_transcript(&self.db, &item, before, limit))?;
+            Ok((item, page))
+        });
+
+        match result {
+            Ok((item, page)) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{
+                        "type": "text",
+                        "text": format_transcript(&item, &page)
+                    }]
+                }),
+            ),
+            Err(e) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": format!("Error: {}", e) }],
+                    "isError": true
+                }),
+            ),
+        }
+    }
+
+    fn tool_answer_opencode_question(&self, id: Option<Value>, arguments: &Value) -> JsonRpcResponse {
+        let item_id = match arguments.get("item_id").and_then(|v| v.as_str()) {
+            Some(i) => i,
+            None => {
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": "Error: 'item_id' parameter is required" }],
+                        "isError": true
+                    }),
+                );
+            }
+        };
+
+        let answers: Option<Vec<Vec<String>>> = match arguments.get("answer").and_then(|v| v.as_str()) {
+            Some(answer) => Some(vec![vec![answer.to_string()]]),
+            None => arguments
+                .get("answers")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+        };
+        let answers = match answers {
+            Some(a) => a,
+            None => {
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": "Error: 'answer' or 'answers' parameter is required" }],
+                        "isError": true
+                    }),
+                );
+            }
+        };
+
+        let result = self.db.get_item(item_id).and_then(|item| {
+            let item = item.ok_or_else(|| anyhow::anyhow!("Item not found: {}", item_id))?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(opencode_control::answer_question(&self.db, &item, &answers))?;
+            Ok(item)
+        });
+
+        match result {
+            Ok(item) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{
+                        "type": "text",
+                        "text": format!("Answered question for {}", item.title)
+                    }]
+                }),
+            ),
+            Err(e) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "content": [{ "type": "text", "text": format!("Error: {}", e) }],
+                    "isError": true
+                }),
+            ),
+        }
+    }
 }
 
 // ── Formatting helpers ──────────────────────────────────────────────
 
-fn format_items(items: &[Item]) -> String {
+fn format_items(items: &[Item], links: &[ItemLink]) -> String {
     if items.is_empty() {
         return "No items found.".to_string();
     }
@@ -440,7 +803,209 @@ fn format_items(items: &[Item]) -> String {
         if let Some(ref updated) = item.last_updated_at {
             out.push_str(&format!("  Updated: {}\n", updated));
         }
+        if let Some(usage) = format_usage(&item.metadata) {
+            out.push_str(&format!("  Usage: {}\n", usage));
+        }
+        if let Some(reason) = over_budget(&item.metadata) {
+            out.push_str(&format!("  Over budget: {}\n", reason));
+        }
+        if let Some(question) = pending_question(&item.metadata) {
+            if let Some(text) = opencode::describe_question(&question) {
+                out.push_str(&format!("  Question: {}\n", text));
+            }
+        }
+        for line in format_children(&item.metadata) {
+            out.push_str(&format!("  {}\n", line));
+        }
+        for line in format_links(item, items, links) {
+            out.push_str(&format!("  {}\n", line));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Links from and to an item, one per line, e.g. "Blocked by: [id] title (status)".
+fn format_links(item: &Item, items: &[Item], links: &[ItemLink]) -> Vec<String> {
+    let describe = |id: &str| match items.iter().find(|i| i.id == id) {
+        Some(linked) => {
+            let ci = serde_json::from_str::<Value>(&linked.metadata)
+                .ok()
+                .and_then(|m| m["ci_status"].as_str().map(|s| format!(", CI {}", s)))
+                .unwrap_or_default();
+            format!("[{}] {} ({}{})", id, linked.title, linked.status, ci)
+        }
+        None => format!("[{}]", id),
+    };
+
+    links
+        .iter()
+        .filter_map(|link| {
+            let relation = Relation::parse(&link.relation).ok()?;
+            if link.source_item_id == item.id {
+                Some(format!("{}: {}", relation.label(true), describe(&link.target_item_id)))
+            } else if link.target_item_id == item.id {
+                Some(format!("{}: {}", relation.label(false), describe(&link.source_item_id)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn format_item_links(item: &Item, items: &[Item], links: &[ItemLink]) -> String {
+    let lines = format_links(item, items, links);
+    if lines.is_empty() {
+        return format!("{} has no links.", item.title);
+    }
+
+    let mut out = format!("Links of {}:\n", item.title);
+    for line in lines {
+        out.push_str(&format!("- {}\n", line));
+    }
+    let blocker_statuses: Vec<&str> = links
+        .iter()
+        .filter(|l| l.relation == Relation::Blocks.as_str() && l.target_item_id == item.id)
+        .filter_map(|l| items.iter().find(|i| i.id == l.source_item_id))
+        .map(|i| i.status.as_str())
+        .collect();
+    if let Some(rollup) = item_links::Rollup::of(&blocker_statuses) {
+        out.push_str(&format!("\n{}\n", rollup.describe()));
+    }
+    out
+}
+
+fn pending_question(metadata: &str) -> Option<PendingQuestion> {
+    let meta: Value = serde_json::from_str(metadata).ok()?;
+    serde_json::from_value(meta.get("pending_question")?.clone()).ok()
+}
+
+/// Parent link of a sub-agent session, or the sub-agents of a parent session.
+fn format_children(metadata: &str) -> Vec<String> {
+    let meta: Value = match serde_json::from_str(metadata) {
+        Ok(m) => m,
+        Err(_) => return Vec::new(),
+    };
+    if let Some(parent) = meta["parent_item_id"].as_str() {
+        return vec![format!("Sub-agent of: {}", parent)];
+    }
+
+    let children = match meta["children"].as_array() {
+        Some(c) if !c.is_empty() => c,
+        _ => return Vec::new(),
+    };
+    let mut lines = vec![format!(
+        "Sub-agents: {} ({} busy, {} tokens, ${:.2})",
+        children.len(),
+        meta["children_busy"].as_u64().unwrap_or(0),
+        meta["children_total_tokens"].as_u64().unwrap_or(0),
+        meta["children_total_cost"].as_f64().unwrap_or(0.0)
+    )];
+    for child in children {
+        lines.push(format!(
+            "  - [{}] {} ({}, {} tokens)",
+            child["item_id"].as_str().unwrap_or(""),
+            child["title"].as_str().unwrap_or(""),
+            child["session_status"].as_str().unwrap_or("unknown"),
+            child["total_tokens"].as_u64().unwrap_or(0)
+        ));
+    }
+    lines
+}
+
+/// One-line usage summary for agent sessions (Copilot CLI, OpenCode).
+fn format_usage(metadata: &str) -> Option<String> {
+    let meta: Value = serde_json::from_str(metadata).ok()?;
+    let total_tokens = meta["total_tokens"].as_u64()?;
+
+    let mut parts = Vec::new();
+    if let Some(model) = meta["model"].as_str() {
+        parts.push(model.to_string());
+    }
+    parts.push(format!("{} tokens", total_tokens));
+    if let Some(cost) = meta["total_cost"].as_f64().filter(|c| *c > 0.0) {
+        parts.push(format!("${:.2}", cost));
+    }
+    if let Some(tools) = meta["tool_calls"].as_object() {
+        let count: u64 = tools.values().filter_map(|v| v.as_u64()).sum();
+        parts.push(format!("{} tool calls", count));
+    }
+    if let Some(files) = meta["files_edited"].as_array() {
+        parts.push(format!("{} files edited", files.len()));
+    }
+    if let Some(turns) = meta["turn_count"].as_u64() {
+        parts.push(format!("{} turns", turns));
+    }
+    Some(parts.join(", "))
+}
+
+fn over_budget(metadata: &str) -> Option<String> {
+    let meta: Value = serde_json::from_str(metadata).ok()?;
+    if meta["over_budget"].as_bool() != Some(true) {
+        return None;
+    }
+    Some(meta["budget_reason"].as_str().unwrap_or("yes").to_string())
+}
+
+fn format_daily_spend(day: &str, spend: &[DailySpend]) -> String {
+    if spend.is_empty() {
+        return format!("No agent usage recorded on {}.", day);
+    }
+
+    let tokens: i64 = spend.iter().map(|s| s.tokens).sum();
+    let cost: f64 = spend.iter().map(|s| s.cost).sum();
+    let mut out = format!("Agent usage on {}: {} tokens, ${:.2}\n\n", day, tokens, cost);
+    for s in spend {
+        out.push_str(&format!(
+            "- {}: {} tokens, ${:.2} ({} session{})\n",
+            s.directory,
+            s.tokens,
+            s.cost,
+            s.sessions,
+            if s.sessions == 1 { "" } else { "s" }
+        ));
+    }
+    out
+}
+
+fn format_transcript(item: &Item, page: &TranscriptPage) -> String {
+    if page.turns.is_empty() {
+        return format!("No transcript available for {}.", item.title);
+    }
+
+    let first = page.next_before.unwrap_or(0);
+    let mut out = format!(
+        "Transcript of {} (turns {}-{} of {}):\n",
+        item.title,
+        first + 1,
+        first + page.turns.len(),
+        page.total_turns
+    );
+    for (i, turn) in page.turns.iter().enumerate() {
+        out.push_str(&format!("\n## Turn {}", first + i + 1));
+        if let Some(ref ts) = turn.timestamp {
+            out.push_str(&format!(" ({})", ts));
+        }
         out.push('\n');
+        if let Some(ref message) = turn.user_message {
+            out.push_str(&format!("User: {}\n", message));
+        }
+        for step in &turn.steps {
+            match step {
+                TranscriptStep::Text { text, .. } => {
+                    out.push_str(&format!("Assistant: {}\n", text));
+                }
+                TranscriptStep::ToolCall { name, arguments, result, .. } => {
+                    out.push_str(&format!("Tool {}: {}\n", name, arguments.as_deref().unwrap_or("")));
+                    if let Some(result) = result {
+                        out.push_str(&format!("  Result: {}\n", result));
+                    }
+                }
+            }
+        }
+    }
+    if let Some(before) = page.next_before {
+        out.push_str(&format!("\nOlder turns available: call again with before={}\n", before));
     }
     out
 }
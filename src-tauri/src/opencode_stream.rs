@@ -0,0 +1,234 @@
+use crate::db::Database;
+use crate::polling::{OpenCodeContext, PollingManager};
+use crate::services::opencode::{self, OpenCodeSession, ServerEndpoint};
+use crate::services::opencode_control;
+use crate::services::opencode_events::{parse_event, LiveState, OpenCodeEvent, SseParser};
+use crate::tray;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::time;
+
+/// First reconnect delay; doubled after each failed attempt.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for the reconnect delay.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Reconnect when the stream has been silent for this long, in case the
+/// server went away without closing the connection.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How often to look for newly configured servers.
+const SERVER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// OpenCode state kept current by the event streams, per server name.
+/// A server without an entry is disconnected, in which case polling
+/// fetches its state itself.
+#[derive(Default)]
+pub struct OpenCodeLive {
+    states: Mutex<HashMap<String, LiveState>>,
+}
+
+impl OpenCodeLive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current state of `server`, or `None` when its stream is not connected.
+    pub fn snapshot(&self, server: &str) -> Option<LiveState> {
+        self.states.lock().unwrap().get(server).cloned()
+    }
+
+    fn set(&self, server: &str, state: Option<LiveState>) {
+        let mut states = self.states.lock().unwrap();
+        match state {
+            Some(s) => states.insert(server.to_string(), s),
+            None => states.remove(server),
+        };
+    }
+
+    fn apply(&self, server: &str, event: &OpenCodeEvent) -> Option<String> {
+        self.states.lock().unwrap().get_mut(server)?.apply(event)
+    }
+}
+
+/// Subscribe to the event stream of every configured OpenCode server and
+/// update `opencode_session` items as status and question events arrive.
+/// Each server reconnects with its own exponential backoff; polling covers
+/// the gaps.
+pub fn start(db: Arc<Database>, app_handle: AppHandle, live: Arc<OpenCodeLive>) {
+    tauri::async_runtime::spawn(async move {
+        let followed: Arc<Mutex<HashSet<String>>> = Arc::default();
+        loop {
+            let servers = db.get_opencode_servers().unwrap_or_default();
+            for server in servers {
+                if !followed.lock().unwrap().insert(server.name.clone()) {
+                    continue;
+                }
+                let (db, app_handle, live, followed) =
+                    (db.clone(), app_handle.clone(), live.clone(), followed.clone());
+                tauri::async_runtime::spawn(async move {
+                    follow(&db, &app_handle, &live, &server.name).await;
+                    followed.lock().unwrap().remove(&server.name);
+                });
+            }
+            time::sleep(SERVER_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Keep one server's stream connected until the server is removed.
+async fn follow(db: &Arc<Database>, app_handle: &AppHandle, live: &OpenCodeLive, name: &str) {
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let server = match opencode_control::server_endpoint(db, name) {
+            Ok(s) => s,
+            Err(_) => {
+                live.set(name, None);
+                return;
+            }
+        };
+
+        if let Err(e) = run(db, app_handle, live, &server, &mut backoff).await {
+            // Connection failures are expected while OpenCode isn't running
+            if live.snapshot(name).is_some() {
+                eprintln!("OpenCode event stream for '{}' disconnected: {}", name, e);
+            }
+        }
+        live.set(name, None);
+
+        time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+async fn run(
+    db: &Arc<Database>,
+    app_handle: &AppHandle,
+    live: &OpenCodeLive,
+    server: &ServerEndpoint,
+    backoff: &mut Duration,
+) -> anyhow::Result<()> {
+    let mut response = opencode::open_event_stream(&server.base_url, &server.password).await?;
+    db.update_opencode_server_health(&server.name, true, None)?;
+
+    // Seed from the status endpoints once; events keep it current from here
+    live.set(&server.name, Some(LiveState::fetch(server).await));
+    *backoff = MIN_BACKOFF;
+    if let Err(e) = PollingManager::discover_opencode_sessions(db, app_handle, server).await {
+        eprintln!("Error discovering OpenCode sessions on '{}': {}", server.name, e);
+    }
+
+    let mut parser = SseParser::new();
+    while let Some(chunk) = time::timeout(IDLE_TIMEOUT, response.chunk()).await?? {
+        for data in parser.push(&chunk) {
+            let event = match parse_event(&data) {
+                Some(e) => e,
+                None => continue,
+            };
+            if let Err(e) = handle_event(db, app_handle, live, server, &event).await {
+                eprintln!("Error handling OpenCode event: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_event(
+    db: &Arc<Database>,
+    app_handle: &AppHandle,
+    live: &OpenCodeLive,
+    server: &ServerEndpoint,
+    event: &OpenCodeEvent,
+) -> anyhow::Result<()> {
+    if let OpenCodeEvent::SessionUpdated { session, directory } = event {
+        return add_new_session(db, app_handle, live, server, session, directory.as_deref());
+    }
+
+    let session_id = match live.apply(&server.name, event) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let state = match live.snapshot(&server.name) {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+
+    let items = db.get_items(false)?;
+    let item = items.iter().find(|i| {
+        i.item_type == "opencode_session"
+            && serde_json::from_str::<serde_json::Value>(&i.metadata)
+                .map(|m| {
+                    m["session_id"].as_str() == Some(session_id.as_str())
+                        && opencode_control::server_name(&m) == server.name
+                })
+                .unwrap_or(false)
+    });
+    let item = match item {
+        Some(i) => i,
+        // Sessions created while disconnected are left to polling
+        None => return Ok(()),
+    };
+
+    let context = OpenCodeContext {
+        base_url: server.base_url.clone(),
+        password: server.password.clone(),
+        pending_questions: state.pending_questions(),
+        statuses: state.statuses,
+    };
+    PollingManager::poll_opencode_session(db, item, Some(&context), app_handle).await?;
+    PollingManager::check_budget(db, app_handle, item)?;
+    let _ = app_handle.emit("item-updated", &item.id);
+
+    // A sub-agent changing state can change its parent's rolled-up status
+    let parent_id = serde_json::from_str::<serde_json::Value>(&item.metadata)
+        .ok()
+        .and_then(|m| m["parent_item_id"].as_str().map(|s| s.to_string()));
+    if let Some(parent) = parent_id.and_then(|id| items.iter().find(|i| i.id == id)) {
+        PollingManager::poll_opencode_session(db, parent, Some(&context), app_handle).await?;
+        PollingManager::check_budget(db, app_handle, parent)?;
+        let _ = app_handle.emit("item-updated", &parent.id);
+    }
+
+    tray::refresh_tray(app_handle, db);
+    Ok(())
+}
+
+/// Track a session created after the stream connected. Updates to
+/// already-known sessions (titles, activity) are picked up by polling.
+fn add_new_session(
+    db: &Arc<Database>,
+    app_handle: &AppHandle,
+    live: &OpenCodeLive,
+    server: &ServerEndpoint,
+    session: &OpenCodeSession,
+    event_directory: Option<&str>,
+) -> anyhow::Result<()> {
+    let tracked = db.get_opencode_session_items()?;
+    if tracked.contains_key(&(server.name.clone(), session.id.clone())) {
+        return Ok(());
+    }
+
+    // Sub-agent sessions are nested under their parent, if that is tracked
+    let parent_item_id = match session.parent_id {
+        Some(ref parent_id) => match tracked.get(&(server.name.clone(), parent_id.clone())) {
+            Some(id) => Some(id.as_str()),
+            None => return Ok(()),
+        },
+        None => None,
+    };
+
+    let directory = match Some(session.directory.as_str()).filter(|d| !d.is_empty()).or(event_directory) {
+        Some(d) => d,
+        None => return Ok(()),
+    };
+    let statuses = live.snapshot(&server.name).map(|s| s.statuses).unwrap_or_default();
+    let item = opencode_control::session_item(session, server, directory, &statuses, parent_item_id)?;
+    db.add_item(&item)?;
+    let _ = app_handle.emit("item-updated", &item.id);
+    tray::refresh_tray(app_handle, db);
+    Ok(())
+}
@@ -1,45 +1,46 @@
 use crate::db::{Database, Item};
-use crate::services::{copilot_cli, github_actions, github_pr, opencode, slack, url_parser};
+use crate::opencode_stream::OpenCodeLive;
+use crate::services::opencode::ServerEndpoint;
+use crate::services::opencode_events::LiveState;
+use crate::services::notifications::Category;
+use crate::services::{
+    agent_logs, budget, cli_session, copilot_cli, github_actions, github_pr, item_links, notifications, opencode, opencode_control, pr_link, session_state, slack, url_parser,
+};
 use crate::tray;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
 use tauri_plugin_notification::NotificationExt;
 use tokio::time;
 
-enum NotificationCategory {
-    SessionStarted,
-    SessionEnded,
-    InputNeeded,
-}
-
-fn classify_notification(old_status: &str, new_status: &str) -> Option<(&'static str, NotificationCategory)> {
-    match (old_status, new_status) {
-        ("in_progress", "input_needed") => Some(("Waiting for your input", NotificationCategory::InputNeeded)),
-        ("in_progress", "completed") => Some(("Agent finished working", NotificationCategory::SessionEnded)),
-        ("in_progress", "closed") => Some(("Session closed", NotificationCategory::SessionEnded)),
-        (_, "archived") => Some(("Session has been archived", NotificationCategory::SessionEnded)),
-        ("input_needed" | "completed" | "closed", "in_progress") => Some(("Agent started working", NotificationCategory::SessionStarted)),
-        _ => None,
-    }
+/// OpenCode server state, taken from the event stream when connected and
+/// otherwise fetched once per polling cycle.
+pub(crate) struct OpenCodeContext {
+    pub(crate) base_url: String,
+    pub(crate) password: String,
+    pub(crate) statuses: HashMap<String, opencode::SessionStatus>,
+    pub(crate) pending_questions: Vec<opencode::PendingQuestion>,
 }
 
 pub struct PollingManager {
     db: Arc<Database>,
     app_handle: AppHandle,
+    opencode_live: Arc<OpenCodeLive>,
 }
 
 impl PollingManager {
-    pub fn new(db: Arc<Database>, app_handle: AppHandle) -> Self {
-        Self { db, app_handle }
+    pub fn new(db: Arc<Database>, app_handle: AppHandle, opencode_live: Arc<OpenCodeLive>) -> Self {
+        Self { db, app_handle, opencode_live }
     }
 
     pub async fn start(&self) {
         let db = self.db.clone();
         let app_handle = self.app_handle.clone();
+        let opencode_live = self.opencode_live.clone();
 
         tokio::spawn(async move {
+            let mut pr_lookups = pr_link::LookupTimes::default();
             loop {
                 // Get polling interval from settings
                 let interval = match db.get_setting("polling_interval") {
@@ -48,10 +49,18 @@ impl PollingManager {
                 };
 
                 // Poll all items
-                if let Err(e) = Self::poll_items(&db, &app_handle).await {
+                if let Err(e) = Self::poll_items(&db, &app_handle, &opencode_live).await {
                     eprintln!("Error polling items: {}", e);
                 }
 
+                if let Err(e) = Self::link_agent_prs(&db, &app_handle, &mut pr_lookups).await {
+                    eprintln!("Error linking agent sessions to PRs: {}", e);
+                }
+
+                if let Err(e) = Self::evaluate_link_rollups(&db, &app_handle) {
+                    eprintln!("Error evaluating item link rollups: {}", e);
+                }
+
                 // Cleanup archived items older than 7 days
                 match db.cleanup_old_archived() {
                     Ok(count) if count > 0 => {
@@ -77,10 +86,12 @@ impl PollingManager {
         });
     }
 
-    async fn poll_items(db: &Arc<Database>, app_handle: &AppHandle) -> anyhow::Result<()> {
-        if let Err(e) = Self::discover_opencode_sessions(db, app_handle).await {
-            eprintln!("Error discovering OpenCode sessions: {}", e);
-        }
+    async fn poll_items(
+        db: &Arc<Database>,
+        app_handle: &AppHandle,
+        opencode_live: &OpenCodeLive,
+    ) -> anyhow::Result<()> {
+        let opencode_contexts = Self::get_opencode_contexts(db, app_handle, opencode_live).await;
 
         let active_cwds = copilot_cli::get_active_copilot_cwds();
 
@@ -88,12 +99,21 @@ impl PollingManager {
             eprintln!("Error discovering Copilot CLI sessions: {}", e);
         }
 
-        let items = db.get_items(false)?;
+        let agent_cwds: HashMap<&str, HashSet<String>> = agent_logs::AGENTS
+            .iter()
+            .map(|agent| (agent.item_type, agent.active_cwds()))
+            .collect();
+
+        for agent in agent_logs::AGENTS {
+            if let Err(e) = Self::discover_agent_sessions(db, app_handle, agent, &agent_cwds[agent.item_type]) {
+                eprintln!("Error discovering {} sessions: {}", agent.label, e);
+            }
+        }
 
-        let opencode_statuses = Self::get_opencode_context(db).await;
+        let items = db.get_items(false)?;
 
         for item in items {
-            // Skip terminal items, but keep polling opencode_session and copilot_agent
+            // Skip terminal items, but keep polling agent sessions
             // (archived sessions need status tracking, idle sessions may become busy).
             // "failed" github_action/github_pr items are re-polled so they can recover
             // if the failure was due to a transient polling error.
@@ -102,6 +122,7 @@ impl PollingManager {
                 && item.item_type != "copilot_agent"
                 && item.item_type != "cli_session"
                 && item.item_type != "github_pr"
+                && agent_logs::rules_for(&item.item_type).is_none()
             {
                 continue;
             }
@@ -112,15 +133,21 @@ impl PollingManager {
             }
 
             let result = match item.item_type.as_str() {
-                "slack_thread" => Self::poll_slack_thread(db, &item).await,
-                "github_action" => Self::poll_github_action(db, &item).await,
-                "github_pr" => Self::poll_github_pr(db, &item).await,
+                "slack_thread" => Self::poll_slack_thread(db, &item, app_handle).await,
+                "github_action" => Self::poll_github_action(db, &item, app_handle).await,
+                "github_pr" => Self::poll_github_pr(db, &item, app_handle).await,
                 "opencode_session" => {
-                    Self::poll_opencode_session(db, &item, &opencode_statuses, app_handle).await
+                    let context = serde_json::from_str::<serde_json::Value>(&item.metadata)
+                        .ok()
+                        .and_then(|m| opencode_contexts.get(opencode_control::server_name(&m)));
+                    Self::poll_opencode_session(db, &item, context, app_handle).await
                 }
                 "copilot_agent" => Self::poll_copilot_session(db, &item, app_handle, &active_cwds),
                 "cli_session" => Self::poll_cli_session(db, &item, app_handle, &active_cwds),
-                _ => continue,
+                other => match agent_logs::rules_for(other) {
+                    Some(agent) => Self::poll_agent_session(db, &item, app_handle, agent, &agent_cwds[agent.item_type]),
+                    None => continue,
+                },
             };
 
             if let Err(e) = result {
@@ -130,6 +157,9 @@ impl PollingManager {
                 let _ = app_handle.emit("item-updated", &item.id);
                 eprintln!("Error polling item {}: {}", item.id, error_text);
             } else {
+                if let Err(e) = Self::check_budget(db, app_handle, &item) {
+                    eprintln!("Error checking budget for item {}: {}", item.id, e);
+                }
                 // Emit event to frontend
                 let _ = app_handle.emit("item-updated", &item.id);
             }
@@ -138,6 +168,105 @@ impl PollingManager {
         Ok(())
     }
 
+    /// Record an agent session's usage, flag it while it is over the session
+    /// budget and notify once when it or today's total crosses a budget.
+    /// Sub-agents count towards their parent's budget rather than their own.
+    pub(crate) fn check_budget(db: &Arc<Database>, app_handle: &AppHandle, item: &Item) -> anyhow::Result<()> {
+        if !budget::is_agent_session(&item.item_type) {
+            return Ok(());
+        }
+        // Polling has just rewritten the metadata
+        let item = match db.get_item(&item.id)? {
+            Some(i) => i,
+            None => return Ok(()),
+        };
+        let usage = match budget::session_usage(&item) {
+            Some(u) => u,
+            None => return Ok(()),
+        };
+        let today = budget::today();
+        db.record_agent_usage(&item.id, &usage.directory, &today, usage.tokens, usage.cost)?;
+
+        let budgets = budget::Budgets::from_settings(&db.get_all_settings()?);
+        let mut metadata: serde_json::Value = serde_json::from_str(&item.metadata)?;
+        if metadata["parent_item_id"].is_null() {
+            let reason = budgets.session_exceeded(&usage);
+            let changed = metadata
+                .as_object_mut()
+                .map(|map| budget::set_flag(map, reason.as_deref()))
+                .unwrap_or(false);
+            if changed {
+                db.update_item_status(&item.id, &item.status, Some(&metadata.to_string()))?;
+            }
+
+            // The timeline remembers whether the crossing was already announced
+            let announced = db
+                .get_item_events(&item.id)?
+                .iter()
+                .rev()
+                .find(|e| e.kind == "over_budget" || e.kind == "within_budget")
+                .map(|e| e.kind == "over_budget")
+                .unwrap_or(false);
+            match reason {
+                Some(reason) if !announced => {
+                    db.add_item_event(&item.id, "over_budget", &reason)?;
+                    Self::show_notification(app_handle, &item.title, &reason);
+                }
+                None if announced => {
+                    db.add_item_event(&item.id, "within_budget", "Back within budget")?;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(reason) = budgets.daily_exceeded(&db.get_daily_spend(&today)?) {
+            if db.get_setting("budget_daily_alerted_on")?.as_deref() != Some(today.as_str()) {
+                db.save_setting("budget_daily_alerted_on", &today)?;
+                Self::show_notification(app_handle, "Daily agent budget exceeded", &reason);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn show_notification(app_handle: &AppHandle, title: &str, body: &str) {
+        let _ = app_handle.notification().builder().title(title).body(body).show();
+    }
+
+    /// Show a system notification for a status transition, if one applies
+    /// and its category is enabled for the item.
+    pub(crate) fn notify_transition(db: &Arc<Database>, app_handle: &AppHandle, item: &Item, new_status: &str) {
+        Self::notify_transition_with_body(db, app_handle, item, new_status, None);
+    }
+
+    /// Like `notify_transition`, replacing the generic body with `body` when given.
+    fn notify_transition_with_body(
+        db: &Arc<Database>,
+        app_handle: &AppHandle,
+        item: &Item,
+        new_status: &str,
+        body: Option<&str>,
+    ) {
+        if new_status == item.status {
+            return;
+        }
+        if let Some((default_body, category)) =
+            notifications::classify(&item.item_type, item.status.as_str(), new_status)
+        {
+            Self::notify(db, app_handle, item, category, body.unwrap_or(default_body));
+        }
+    }
+
+    /// Show a notification about `item` unless its category is switched off
+    /// for the item's type or the item is muted.
+    pub(crate) fn notify(db: &Arc<Database>, app_handle: &AppHandle, item: &Item, category: Category, body: &str) {
+        match db.notification_enabled(item, category) {
+            Ok(true) => Self::show_notification(app_handle, &item.title, body),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to check notification settings for {}: {}", item.id, e),
+        }
+    }
+
     fn is_permanent_github_error(item_type: &str, error: &str) -> bool {
         // A 404 for a GitHub Action run means the run doesn't exist (deleted or wrong URL).
         // "failed" items are still re-polled, so they recover automatically if the 404
@@ -146,7 +275,11 @@ impl PollingManager {
         item_type == "github_action" && error.contains("404 Not Found")
     }
 
-    async fn poll_slack_thread(db: &Arc<Database>, item: &crate::db::Item) -> anyhow::Result<()> {
+    async fn poll_slack_thread(
+        db: &Arc<Database>,
+        item: &crate::db::Item,
+        app_handle: &AppHandle,
+    ) -> anyhow::Result<()> {
         let token = db.get_credential("slack_token")?
             .ok_or_else(|| anyhow::anyhow!("Slack token not configured"))?;
 
@@ -172,6 +305,10 @@ impl PollingManager {
             result_with_identifiers.insert("thread_ts".to_string(), serde_json::json!(thread_ts));
             let new_metadata = serde_json::to_string(&result_with_identifiers)?;
             db.update_item_status(&item.id, "updated", Some(&new_metadata))?;
+            Self::notify_transition(db, app_handle, item, "updated");
+            let replies = new_count - old_count;
+            let body = if replies == 1 { "New reply".to_string() } else { format!("{} new replies", replies) };
+            Self::notify(db, app_handle, item, Category::SlackReply, &body);
         } else {
             // Just update last_checked_at
             db.update_item_status(&item.id, &item.status, None)?;
@@ -183,6 +320,7 @@ impl PollingManager {
     async fn poll_github_action(
         db: &Arc<Database>,
         item: &crate::db::Item,
+        app_handle: &AppHandle,
     ) -> anyhow::Result<()> {
         let token = db
             .get_credential("github_token")?
@@ -195,22 +333,10 @@ impl PollingManager {
 
         let result = github_actions::check_github_action(&token, &owner, &repo, &run_id).await?;
 
-        // Determine new status based on GitHub Action status
-        let status = result["status"].as_str().unwrap_or("unknown");
-        let conclusion = result["conclusion"].as_str();
-
-        let new_status = match status {
-            "queued" | "waiting" => "waiting",
-            "in_progress" => "in_progress",
-            "completed" => {
-                match conclusion {
-                    Some("success") => "completed",
-                    Some("failure") | Some("cancelled") => "failed",
-                    _ => "completed",
-                }
-            }
-            _ => "waiting",
-        };
+        let new_status = github_actions::run_status(
+            result["status"].as_str().unwrap_or("unknown"),
+            result["conclusion"].as_str(),
+        );
 
         // Update if status changed
         let metadata_missing_ids = metadata["owner"].as_str().is_none()
@@ -224,6 +350,7 @@ impl PollingManager {
             result_with_identifiers.insert("run_id".to_string(), serde_json::json!(run_id));
             let new_metadata = serde_json::to_string(&result_with_identifiers)?;
             db.update_item_status(&item.id, new_status, Some(&new_metadata))?;
+            Self::notify_transition(db, app_handle, item, new_status);
         } else {
             db.update_item_status(&item.id, &item.status, None)?;
         }
@@ -231,7 +358,11 @@ impl PollingManager {
         Ok(())
     }
 
-    async fn poll_github_pr(db: &Arc<Database>, item: &crate::db::Item) -> anyhow::Result<()> {
+    async fn poll_github_pr(
+        db: &Arc<Database>,
+        item: &crate::db::Item,
+        app_handle: &AppHandle,
+    ) -> anyhow::Result<()> {
         let token = db
             .get_credential("github_token")?
             .unwrap_or_default();
@@ -262,25 +393,32 @@ impl PollingManager {
             || metadata["repo"].as_str().is_none()
             || metadata["pr_number"].as_str().is_none();
 
-        let new_status = if merged {
-            "merged"
-        } else if state == "closed" {
-            "completed"
-        } else if has_approval {
-            "approved"
-        } else if new_review_count > old_review_count || has_changes_requested {
-            "updated"
-        } else {
-            "in_progress"
-        };
+        let new_status = github_pr::pr_status(
+            state,
+            merged,
+            has_approval,
+            has_changes_requested,
+            new_review_count > old_review_count,
+        );
+
+        let ci_changed = result.get("ci_status") != old_metadata.get("ci_status");
+        let ci_failed = github_pr::ci_newly_failed(old_metadata.get("ci_status"), result.get("ci_status"));
 
-        if new_status != item.status || new_review_count > old_review_count || metadata_missing_ids {
+        if new_status != item.status || new_review_count > old_review_count || metadata_missing_ids || ci_changed {
             let mut result_with_identifiers = result;
             result_with_identifiers.insert("owner".to_string(), serde_json::json!(owner));
             result_with_identifiers.insert("repo".to_string(), serde_json::json!(repo));
             result_with_identifiers.insert("pr_number".to_string(), serde_json::json!(pr_number));
             let new_metadata = serde_json::to_string(&result_with_identifiers)?;
             db.update_item_status(&item.id, new_status, Some(&new_metadata))?;
+            if new_status != item.status {
+                Self::notify_transition(db, app_handle, item, new_status);
+            } else if new_review_count > old_review_count {
+                Self::notify(db, app_handle, item, Category::PrReview, "New review activity");
+            }
+            if ci_failed {
+                Self::notify(db, app_handle, item, Category::CiFailed, "CI failed");
+            }
         } else {
             db.update_item_status(&item.id, &item.status, None)?;
         }
@@ -288,6 +426,154 @@ impl PollingManager {
         Ok(())
     }
 
+    /// Link agent sessions to the open PR for their repository and branch,
+    /// adding a `github_pr` item for it when the PR isn't tracked yet.
+    async fn link_agent_prs(
+        db: &Arc<Database>,
+        app_handle: &AppHandle,
+        lookups: &mut pr_link::LookupTimes,
+    ) -> anyhow::Result<()> {
+        let token = match db.get_credential("github_token")?.filter(|t| !t.is_empty()) {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+        let sessions: Vec<Item> = db
+            .get_items(false)?
+            .into_iter()
+            .filter(|item| budget::is_agent_session(&item.item_type))
+            .collect();
+        lookups.retain(|id| sessions.iter().any(|s| s.id == id));
+
+        let now = Instant::now();
+        for session in &sessions {
+            let metadata: serde_json::Value = match serde_json::from_str(&session.metadata) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            // Sub-agents work on their parent's branch
+            if metadata["parent_item_id"].is_string() {
+                continue;
+            }
+            let ended = matches!(session.status.as_str(), "closed" | "lost");
+            if !lookups.due(&session.id, pr_link::recorded_branch_ref(&metadata).as_ref(), ended, now) {
+                continue;
+            }
+            let branch = pr_link::branch_ref(&metadata).await;
+            lookups.record(&session.id, branch.clone(), ended, now);
+            let branch = match branch {
+                Some(b) => b,
+                None => continue,
+            };
+            if db.get_linked_pr_branches(&session.id)?.contains(&branch.branch) {
+                continue;
+            }
+
+            let pr = match github_pr::find_open_pr(&token, &branch.owner, &branch.repo, &branch.branch).await {
+                Ok(Some(pr)) => pr,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Error looking up PR for {}: {}", branch.head(), e);
+                    continue;
+                }
+            };
+
+            let pr_item_id = match db.find_github_pr_item(&branch.owner, &branch.repo, pr.number)? {
+                Some(id) => id,
+                None => {
+                    let item = Item {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        item_type: "github_pr".to_string(),
+                        title: pr.title.clone(),
+                        url: Some(pr.html_url.clone()),
+                        status: "waiting".to_string(),
+                        previous_status: None,
+                        metadata: serde_json::json!({
+                            "owner": branch.owner,
+                            "repo": branch.repo,
+                            "pr_number": pr.number.to_string(),
+                            "head_branch": branch.branch,
+                        })
+                        .to_string(),
+                        last_checked_at: None,
+                        last_updated_at: None,
+                        created_at: chrono::Utc::now().to_rfc3339(),
+                        archived: false,
+                        archived_at: None,
+                        polling_interval_override: None,
+                        checked: false,
+                    };
+                    db.add_item(&item)?;
+                    item.id
+                }
+            };
+
+            if db.add_item_link(&pr_item_id, &session.id, item_links::Relation::ProducedBy.as_str())? {
+                db.add_item_event(&session.id, "pr_linked", &format!("Opened PR #{}: {}", pr.number, pr.title))?;
+                db.add_item_event(&pr_item_id, "pr_linked", &format!("Opened by {}", session.title))?;
+                let _ = app_handle.emit("item-updated", &session.id);
+                let _ = app_handle.emit("item-updated", &pr_item_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Roll up the status of the items blocking each item. The rollup is
+    /// recorded in the blocked item's timeline, with a notification when all
+    /// blockers are done or one of them fails.
+    fn evaluate_link_rollups(db: &Arc<Database>, app_handle: &AppHandle) -> anyhow::Result<()> {
+        let links = db.get_item_links()?;
+        if links.is_empty() {
+            return Ok(());
+        }
+        // Archived blockers (a merged PR) still count
+        let items: HashMap<String, Item> = db
+            .get_items(false)?
+            .into_iter()
+            .chain(db.get_items(true)?)
+            .map(|item| (item.id.clone(), item))
+            .collect();
+
+        let mut blockers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for link in links.iter().filter(|l| l.relation == item_links::Relation::Blocks.as_str()) {
+            if let Some(blocker) = items.get(&link.source_item_id) {
+                blockers
+                    .entry(link.target_item_id.as_str())
+                    .or_default()
+                    .push(blocker.status.as_str());
+            }
+        }
+
+        for (item_id, statuses) in blockers {
+            let item = match items.get(item_id) {
+                Some(i) if !i.archived => i,
+                _ => continue,
+            };
+            let rollup = match item_links::Rollup::of(&statuses) {
+                Some(r) => r,
+                None => continue,
+            };
+            let previous = db
+                .get_item_events(item_id)?
+                .into_iter()
+                .rev()
+                .find(|e| e.kind.starts_with("rollup_"));
+            if previous.as_ref().map(|e| e.kind.as_str()) == Some(rollup.event_kind()) {
+                continue;
+            }
+
+            let message = rollup.describe();
+            db.add_item_event(item_id, rollup.event_kind(), &message)?;
+            // The first evaluation only records where things stand
+            if previous.is_some() && rollup.notifies() {
+                Self::notify(db, app_handle, item, Category::Blockers, &message);
+            }
+            let _ = app_handle.emit("item-updated", item_id);
+        }
+
+        Ok(())
+    }
+
     fn resolve_metadata_field(
         item: &crate::db::Item,
         metadata: &serde_json::Value,
@@ -308,122 +594,92 @@ impl PollingManager {
         Err(anyhow::anyhow!("Missing {}", key))
     }
 
-    async fn get_opencode_context(
+    /// State of every reachable OpenCode server, keyed by server name. Servers
+    /// with a connected event stream already have it in memory and report new
+    /// sessions themselves; the others are health-checked, scanned for new
+    /// sessions and queried.
+    async fn get_opencode_contexts(
         db: &Arc<Database>,
-    ) -> Option<(String, String, HashMap<String, opencode::SessionStatus>, Vec<String>)> {
-        let raw_url = db.get_credential("opencode_url").ok().flatten()?;
-        if raw_url.is_empty() {
-            return None;
-        }
-        let config = opencode::parse_opencode_url(&raw_url).ok()?;
-        let password = db
-            .get_credential("opencode_password")
-            .ok()
-            .flatten()
-            .unwrap_or_default();
+        app_handle: &AppHandle,
+        opencode_live: &OpenCodeLive,
+    ) -> HashMap<String, OpenCodeContext> {
+        let mut contexts = HashMap::new();
+
+        for server in opencode_control::endpoints(db).unwrap_or_default() {
+            let state = match opencode_live.snapshot(&server.name) {
+                Some(state) => state,
+                None => {
+                    let healthy = opencode::check_opencode_health(&server.base_url, &server.password)
+                        .await
+                        .unwrap_or(false);
+                    let error = if healthy { None } else { Some("Server is not reachable") };
+                    let _ = db.update_opencode_server_health(&server.name, healthy, error);
+                    if !healthy {
+                        continue;
+                    }
 
-        let directories = opencode::enumerate_opencode_directories();
-        let mut all_statuses = HashMap::new();
-        for dir in &directories {
-            if let Ok(statuses) = opencode::get_session_statuses(&config.base_url, &password, Some(dir)).await {
-                all_statuses.extend(statuses);
-            }
-        }
+                    if let Err(e) = Self::discover_opencode_sessions(db, app_handle, &server).await {
+                        eprintln!("Error discovering OpenCode sessions on '{}': {}", server.name, e);
+                    }
+                    LiveState::fetch(&server).await
+                }
+            };
 
-        let pending_question_session_ids = opencode::get_pending_question_session_ids(&config.base_url, &password, &directories)
-            .await
-            .unwrap_or_default();
+            contexts.insert(
+                server.name.clone(),
+                OpenCodeContext {
+                    base_url: server.base_url.clone(),
+                    password: server.password.clone(),
+                    pending_questions: state.pending_questions(),
+                    statuses: state.statuses,
+                },
+            );
+        }
 
-        Some((config.base_url, password, all_statuses, pending_question_session_ids))
+        contexts
     }
 
-    async fn discover_opencode_sessions(
+    /// Add items for untracked sessions on `server`. Sub-agent sessions are
+    /// nested under their parent's item, and skipped if the parent isn't tracked.
+    pub(crate) async fn discover_opencode_sessions(
         db: &Arc<Database>,
         app_handle: &AppHandle,
+        server: &ServerEndpoint,
     ) -> anyhow::Result<()> {
-        let raw_url = match db.get_credential("opencode_url")? {
-            Some(u) if !u.is_empty() => u,
-            _ => return Ok(()),
-        };
-        let config = opencode::parse_opencode_url(&raw_url)?;
-        let password = db
-            .get_credential("opencode_password")?
-            .unwrap_or_default();
-
-        if !opencode::check_opencode_health(&config.base_url, &password).await? {
-            return Ok(());
-        }
-
-        let directories = opencode::enumerate_opencode_directories();
-        let existing_session_ids = db.get_opencode_session_ids()?;
+        let directories = server.directories();
+        let mut tracked = db.get_opencode_session_items()?;
 
         for dir in &directories {
-            let sessions = match opencode::list_sessions(&config.base_url, &password, Some(dir)).await {
+            let sessions = match opencode::list_sessions(&server.base_url, &server.password, Some(dir)).await {
                 Ok(s) => s,
                 Err(_) => continue,
             };
-            let statuses = opencode::get_session_statuses(&config.base_url, &password, Some(dir))
+            let statuses = opencode::get_session_statuses(&server.base_url, &server.password, Some(dir))
                 .await
                 .unwrap_or_default();
-            let web_url = opencode::build_web_url(&config.base_url, dir);
 
-            for session in &sessions {
-                if existing_session_ids.contains(&session.id) {
-                    continue;
-                }
+            // Oldest first, so parents are tracked before their sub-agents
+            let mut sessions = sessions;
+            sessions.sort_by(|a, b| a.time.created.total_cmp(&b.time.created));
 
-                if session.parent_id.is_some() {
+            for session in &sessions {
+                let key = (server.name.clone(), session.id.clone());
+                if tracked.contains_key(&key) {
                     continue;
                 }
 
-                let status_str = if session.time.archived.is_some() {
-                    "archived"
-                } else {
-                    match statuses.get(&session.id) {
-                        Some(opencode::SessionStatus::Busy) => "in_progress",
-                        Some(opencode::SessionStatus::Retry { .. }) => "in_progress",
-                        Some(opencode::SessionStatus::Idle) | None => "waiting",
-                    }
-                };
-
-                let title = if session.title.is_empty() {
-                    format!("OpenCode Session {}", &session.id[..8.min(session.id.len())])
-                } else {
-                    session.title.clone()
-                };
-
-                let metadata = serde_json::json!({
-                    "session_id": session.id,
-                    "opencode_url": web_url,
-                    "directory": dir,
-                    "session_status": match statuses.get(&session.id) {
-                        Some(opencode::SessionStatus::Idle) => "idle",
-                        Some(opencode::SessionStatus::Busy) => "busy",
-                        Some(opencode::SessionStatus::Retry { .. }) => "retry",
-                        None => "unknown",
+                let parent_item_id = match session.parent_id {
+                    Some(ref parent_id) => match tracked.get(&(server.name.clone(), parent_id.clone())) {
+                        Some(id) => Some(id.clone()),
+                        None => continue,
                     },
-                    "session_title": session.title,
-                    "last_activity": session.time.updated,
-                });
-
-                let item = Item {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    item_type: "opencode_session".to_string(),
-                    title,
-                    url: None,
-                    status: status_str.to_string(),
-                    previous_status: None,
-                    metadata: serde_json::to_string(&metadata)?,
-                    last_checked_at: None,
-                    last_updated_at: None,
-                    created_at: chrono::Utc::now().to_rfc3339(),
-                    archived: false,
-                    archived_at: None,
-                    polling_interval_override: None,
-                    checked: status_str == "archived",
+                    None => None,
                 };
 
+                let item =
+                    opencode_control::session_item(session, server, dir, &statuses, parent_item_id.as_deref())?;
                 db.add_item(&item)?;
+                tracked.insert(key, item.id.clone());
                 let _ = app_handle.emit("item-updated", &item.id);
             }
         }
@@ -431,14 +687,14 @@ impl PollingManager {
         Ok(())
     }
 
-    async fn poll_opencode_session(
+    pub(crate) async fn poll_opencode_session(
         db: &Arc<Database>,
         item: &crate::db::Item,
-        context: &Option<(String, String, HashMap<String, opencode::SessionStatus>, Vec<String>)>,
+        context: Option<&OpenCodeContext>,
         app_handle: &AppHandle,
     ) -> anyhow::Result<()> {
-        let (url, password, statuses, pending_question_session_ids) = match context {
-            Some(ctx) => (&ctx.0, &ctx.1, &ctx.2, &ctx.3),
+        let (url, password, statuses, pending_questions) = match context {
+            Some(ctx) => (&ctx.base_url, &ctx.password, &ctx.statuses, &ctx.pending_questions),
             None => {
                 db.update_item_status(&item.id, &item.status, None)?;
                 return Ok(());
@@ -453,7 +709,7 @@ impl PollingManager {
         let resolved_dir = stored_dir.or_else(|| opencode::find_session_directory(session_id));
 
         let result =
-            opencode::poll_opencode_session(url, password, session_id, statuses, pending_question_session_ids).await?;
+            opencode::poll_opencode_session(url, password, session_id, statuses, pending_questions).await?;
 
         let session_status = result
             .get("session_status")
@@ -472,10 +728,15 @@ impl PollingManager {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
+        // A parent stays busy while any of its sub-agent sessions is
+        let children = opencode_control::ChildRollup::from_children(&db.get_child_items(&item.id)?, statuses);
+
         let new_status = if is_archived {
             "archived"
         } else if has_pending_question {
             "input_needed"
+        } else if children.busy > 0 {
+            "in_progress"
         } else {
             match session_status {
                 "busy" | "retry" => "in_progress",
@@ -484,7 +745,12 @@ impl PollingManager {
             }
         };
 
-        let mut full_metadata = result;
+        // Merged into the stored metadata, which also holds keys set
+        // elsewhere: the server, parent links, budget flags, linked PRs
+        let mut full_metadata: HashMap<String, serde_json::Value> =
+            serde_json::from_value(metadata.clone()).unwrap_or_default();
+        full_metadata.extend(result);
+        children.insert_into(&mut full_metadata);
         if let Some(ref dir) = resolved_dir {
             let web_url = opencode::build_web_url(url, dir);
             full_metadata.insert("opencode_url".to_string(), serde_json::json!(web_url));
@@ -510,22 +776,14 @@ impl PollingManager {
         let new_metadata = serde_json::to_string(&full_metadata)?;
         db.update_item_status(&item.id, new_status, Some(&new_metadata))?;
 
-        if new_status != item.status {
-            if let Some((body, category)) = classify_notification(item.status.as_str(), new_status) {
-                let should_notify = match category {
-                    NotificationCategory::SessionStarted => db.get_setting("notify_session_started").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                    NotificationCategory::SessionEnded => db.get_setting("notify_session_ended").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                    NotificationCategory::InputNeeded => db.get_setting("notify_input_needed").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                };
-                if should_notify {
-                    let _ = app_handle
-                        .notification()
-                        .builder()
-                        .title(&item.title)
-                        .body(body)
-                        .show();
-                }
-            }
+        // Show what was asked, so it can be answered without opening OpenCode
+        let question_text = pending_questions
+            .iter()
+            .find(|q| q.session_id == session_id)
+            .and_then(opencode::describe_question);
+        // Sub-agents report through their parent
+        if metadata["parent_item_id"].is_null() {
+            Self::notify_transition_with_body(db, app_handle, item, new_status, question_text.as_deref());
         }
 
         if new_status == "archived" && item.status != "archived" {
@@ -554,60 +812,72 @@ impl PollingManager {
             if existing_ids.contains(&session.id) {
                 continue;
             }
+            Self::add_copilot_session(db, app_handle, session, active_cwds)?;
+        }
 
-            let process_running = copilot_cli::is_session_process_running(&session, active_cwds);
-            let activity = copilot_cli::detect_session_activity(&session.id, process_running);
+        Ok(())
+    }
 
-            let status = if !process_running {
-                "closed"
-            } else {
-                match activity {
-                    copilot_cli::SessionActivity::InProgress => "in_progress",
-                    copilot_cli::SessionActivity::InputNeeded => "input_needed",
-                    copilot_cli::SessionActivity::Idle => "waiting",
-                }
-            };
+    /// Create a `copilot_agent` item for a newly discovered Copilot CLI session.
+    pub(crate) fn add_copilot_session(
+        db: &Arc<Database>,
+        app_handle: &AppHandle,
+        session: copilot_cli::CopilotSession,
+        active_cwds: &HashSet<String>,
+    ) -> anyhow::Result<()> {
+        let process_running = copilot_cli::is_session_process_running(&session, active_cwds);
+        let activity = Self::copilot_session_state(db, &session.id).classify(process_running);
 
-            // Auto-name: summary > first user message > repository > generic
-            let title = session
-                .display_name()
-                .map(|s| copilot_cli::truncate_title(s))
-                .or_else(|| copilot_cli::first_user_message(&session.id))
-                .or_else(|| {
-                    session.repository.as_ref().map(|r| format!("Session in {}", r))
-                })
-                .unwrap_or_else(|| {
-                    format!("Copilot Session {}", &session.id[..8.min(session.id.len())])
-                });
-
-            let metadata = serde_json::json!({
-                "copilot_session_id": session.id,
-                "cwd": session.cwd,
-                "repository": session.repository,
-                "branch": session.branch,
-                "summary": session.summary,
+        let status = if !process_running {
+            "closed"
+        } else {
+            match activity {
+                copilot_cli::SessionActivity::InProgress => "in_progress",
+                copilot_cli::SessionActivity::InputNeeded => "input_needed",
+                copilot_cli::SessionActivity::Idle => "waiting",
+            }
+        };
+
+        // Auto-name: summary > first user message > repository > generic
+        let title = session
+            .display_name()
+            .map(copilot_cli::truncate_title)
+            .or_else(|| copilot_cli::first_user_message(&session.id))
+            .or_else(|| {
+                session.repository.as_ref().map(|r| format!("Session in {}", r))
+            })
+            .unwrap_or_else(|| {
+                format!("Copilot Session {}", &session.id[..8.min(session.id.len())])
             });
 
-            let item = Item {
-                id: uuid::Uuid::new_v4().to_string(),
-                item_type: "copilot_agent".to_string(),
-                title,
-                url: None,
-                status: status.to_string(),
-                previous_status: None,
-                metadata: serde_json::to_string(&metadata)?,
-                last_checked_at: None,
-                last_updated_at: session.updated_at.clone(),
-                created_at: session
-                    .created_at
-                    .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
-                archived: false,
-                archived_at: None,
-                polling_interval_override: None,
-                checked: false,
-            };
+        let metadata = serde_json::json!({
+            "copilot_session_id": session.id,
+            "cwd": session.cwd,
+            "repository": session.repository,
+            "branch": session.branch,
+            "summary": session.summary,
+        });
+
+        let item = Item {
+            id: uuid::Uuid::new_v4().to_string(),
+            item_type: "copilot_agent".to_string(),
+            title,
+            url: None,
+            status: status.to_string(),
+            previous_status: None,
+            metadata: serde_json::to_string(&metadata)?,
+            last_checked_at: None,
+            last_updated_at: session.updated_at.clone(),
+            created_at: session
+                .created_at
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        };
 
-            db.add_item(&item)?;
+        if db.add_copilot_session_item(&item)? {
             let _ = app_handle.emit("item-updated", &item.id);
         }
 
@@ -635,13 +905,43 @@ impl PollingManager {
 
         // Detect live status from events.jsonl
         let process_running = copilot_cli::is_session_process_running(&session, active_cwds);
-        let activity = copilot_cli::detect_session_activity(session_id, process_running);
+        let state = Self::copilot_session_state(db, session_id);
+
+        Self::apply_copilot_activity(db, item, app_handle, &session, process_running, &state)
+    }
+
+    /// Load a Copilot session's persisted parser state, fold in any events
+    /// appended to events.jsonl since, and persist the result.
+    pub(crate) fn copilot_session_state(db: &Arc<Database>, session_id: &str) -> copilot_cli::SessionState {
+        session_state::refresh(
+            db.get_agent_session_state("copilot_agent", session_id).ok().flatten(),
+            |state: &mut copilot_cli::SessionState| {
+                copilot_cli::events_file(session_id).map(|path| state.read_new(&path)).unwrap_or(false)
+            },
+            |offset, json| {
+                if let Err(e) = db.save_agent_session_state("copilot_agent", session_id, offset, json) {
+                    eprintln!("Error saving Copilot session state {}: {}", session_id, e);
+                }
+            },
+        )
+    }
+
+    /// Update a `copilot_agent` item from its session's parsed event state.
+    pub(crate) fn apply_copilot_activity(
+        db: &Arc<Database>,
+        item: &crate::db::Item,
+        app_handle: &AppHandle,
+        session: &copilot_cli::CopilotSession,
+        process_running: bool,
+        state: &copilot_cli::SessionState,
+    ) -> anyhow::Result<()> {
+        let session_id = session.id.as_str();
+        let activity = state.classify(process_running);
+        let last_activity = state.last_event_at.clone();
 
         // If the copilot process is no longer running, the session is closed.
         // A closed session stays closed — process_running may be true because
         // a different session at the same CWD is active, not this one.
-        let last_activity = copilot_cli::last_event_timestamp(session_id);
-
         let new_status = if !process_running || item.status == "closed" {
             "closed"
         } else {
@@ -674,40 +974,222 @@ impl PollingManager {
             }
         }
 
-        // Update metadata
-        let new_metadata = serde_json::json!({
-            "copilot_session_id": session.id,
-            "cwd": session.cwd,
-            "repository": session.repository,
-            "branch": session.branch,
-            "summary": session.summary,
-            "last_activity": last_activity,
-        });
+        // Merged into the stored metadata, which also holds keys set
+        // elsewhere: budget flags, linked PRs
+        let mut new_metadata = serde_json::from_str::<serde_json::Value>(&item.metadata)
+            .ok()
+            .filter(|m| m.is_object())
+            .unwrap_or_else(|| serde_json::json!({}));
+        if let Some(map) = new_metadata.as_object_mut() {
+            map.insert("copilot_session_id".to_string(), serde_json::json!(session.id));
+            map.insert("cwd".to_string(), serde_json::json!(session.cwd));
+            map.insert("repository".to_string(), serde_json::json!(session.repository));
+            map.insert("branch".to_string(), serde_json::json!(session.branch));
+            map.insert("summary".to_string(), serde_json::json!(session.summary));
+            map.insert("last_activity".to_string(), serde_json::json!(last_activity));
+            state.stats.insert_into(map);
+        }
         let new_metadata_str = serde_json::to_string(&new_metadata)?;
 
         db.update_item_status(&item.id, new_status, Some(&new_metadata_str))?;
 
         // Notifications on status transitions (following OpenCode pattern)
-        if new_status != item.status {
-            if let Some((body, category)) = classify_notification(item.status.as_str(), new_status) {
-                let should_notify = match category {
-                    NotificationCategory::SessionStarted => db.get_setting("notify_session_started").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                    NotificationCategory::SessionEnded => db.get_setting("notify_session_ended").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                    NotificationCategory::InputNeeded => db.get_setting("notify_input_needed").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                };
-                if should_notify {
-                    let _ = app_handle
-                        .notification()
-                        .builder()
-                        .title(&item.title)
-                        .body(body)
-                        .show();
+        Self::notify_transition(db, app_handle, item, new_status);
+
+        // Auto-uncheck when session becomes active
+        if (item.status == "input_needed" || item.status == "completed" || item.status == "closed" || item.status == "failed")
+            && (new_status == "waiting" || new_status == "in_progress")
+        {
+            db.toggle_checked(&item.id, false)?;
+        }
+
+        Ok(())
+    }
+
+    fn discover_agent_sessions(
+        db: &Arc<Database>,
+        app_handle: &AppHandle,
+        agent: &agent_logs::AgentRules,
+        active_cwds: &HashSet<String>,
+    ) -> anyhow::Result<()> {
+        let existing_ids = db.get_agent_session_ids(agent.item_type)?;
+        for session in agent.discover_sessions() {
+            if existing_ids.contains(&session.id) {
+                continue;
+            }
+            Self::add_agent_session(db, app_handle, agent, session, active_cwds)?;
+        }
+        Ok(())
+    }
+
+    /// Title for an agent session without a prompt yet.
+    fn agent_session_title(agent: &agent_logs::AgentRules, cwd: Option<&str>) -> String {
+        match cwd.and_then(|c| std::path::Path::new(c).file_name()).and_then(|n| n.to_str()) {
+            Some(name) => format!("{} Session in {}", agent.label, name),
+            None => format!("{} Session", agent.label),
+        }
+    }
+
+    /// Create an item for a newly discovered session of a log-based agent.
+    fn add_agent_session(
+        db: &Arc<Database>,
+        app_handle: &AppHandle,
+        agent: &agent_logs::AgentRules,
+        session: agent_logs::AgentSession,
+        active_cwds: &HashSet<String>,
+    ) -> anyhow::Result<()> {
+        let mut state = Self::agent_session_state(db, agent, &session.id, &session.log_path);
+        // Nothing but a summary or metadata has been logged yet
+        if state.started_at.is_none() {
+            return Ok(());
+        }
+        let process_running = state.is_process_running(active_cwds);
+
+        let status = if !process_running {
+            "closed"
+        } else {
+            match state.classify(agent, process_running) {
+                copilot_cli::SessionActivity::InProgress => "in_progress",
+                copilot_cli::SessionActivity::InputNeeded => "input_needed",
+                copilot_cli::SessionActivity::Idle => "waiting",
+            }
+        };
+
+        let title = state
+            .first_prompt
+            .clone()
+            .unwrap_or_else(|| Self::agent_session_title(agent, state.cwd.as_deref()));
+
+        let mut metadata = serde_json::json!({
+            "agent_session_id": session.id,
+            "log_path": session.log_path,
+            "cwd": state.cwd,
+            "branch": state.branch,
+            "repository": state.repository,
+            "last_activity": state.last_event_at,
+        });
+        if let Some(map) = metadata.as_object_mut() {
+            state.stats.insert_into(map);
+        }
+
+        let item = Item {
+            id: uuid::Uuid::new_v4().to_string(),
+            item_type: agent.item_type.to_string(),
+            title,
+            url: None,
+            status: status.to_string(),
+            previous_status: None,
+            metadata: serde_json::to_string(&metadata)?,
+            last_checked_at: None,
+            last_updated_at: session.updated_at.clone(),
+            created_at: state
+                .started_at
+                .clone()
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        };
+
+        db.add_item(&item)?;
+        let _ = app_handle.emit("item-updated", &item.id);
+
+        Ok(())
+    }
+
+    /// Load an agent session's persisted parser state, fold in what was
+    /// written to its log since, and persist the result.
+    fn agent_session_state(
+        db: &Arc<Database>,
+        agent: &agent_logs::AgentRules,
+        session_id: &str,
+        log_path: &std::path::Path,
+    ) -> agent_logs::SessionState {
+        session_state::refresh(
+            db.get_agent_session_state(agent.item_type, session_id).ok().flatten(),
+            |state: &mut agent_logs::SessionState| state.read_new(agent, log_path),
+            |offset, json| {
+                if let Err(e) = db.save_agent_session_state(agent.item_type, session_id, offset, json) {
+                    eprintln!("Error saving {} session state {}: {}", agent.label, session_id, e);
+                }
+            },
+        )
+    }
+
+    /// Update a log-based agent session item. A closed session reopens when
+    /// the agent resumes writing to its log, e.g. after `claude --resume`.
+    fn poll_agent_session(
+        db: &Arc<Database>,
+        item: &Item,
+        app_handle: &AppHandle,
+        agent: &agent_logs::AgentRules,
+        active_cwds: &HashSet<String>,
+    ) -> anyhow::Result<()> {
+        let metadata: serde_json::Value = serde_json::from_str(&item.metadata)?;
+        let session_id = metadata["agent_session_id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing agent_session_id"))?;
+        let log_path = metadata["log_path"]
+            .as_str()
+            .map(std::path::PathBuf::from)
+            .ok_or_else(|| anyhow::anyhow!("Missing log_path"))?;
+
+        if !log_path.exists() {
+            db.touch_item_check(&item.id)?;
+            return Ok(());
+        }
+
+        let mut state = Self::agent_session_state(db, agent, session_id, &log_path);
+        // A cwd found earlier from a project hash outlives the process
+        if state.cwd.is_none() {
+            state.cwd = metadata["cwd"].as_str().map(|s| s.to_string());
+        }
+        let process_running = state.is_process_running(active_cwds);
+
+        let new_status = if !process_running {
+            "closed"
+        } else {
+            match state.classify(agent, process_running) {
+                copilot_cli::SessionActivity::InProgress => "in_progress",
+                copilot_cli::SessionActivity::InputNeeded => "input_needed",
+                copilot_cli::SessionActivity::Idle if item.status == "waiting" || item.status == "closed" => {
+                    item.status.as_str()
                 }
+                copilot_cli::SessionActivity::Idle => {
+                    // Idle for a while next to a running process: the process
+                    // most likely belongs to another session in the same cwd
+                    let is_stale = state
+                        .last_event_at
+                        .as_deref()
+                        .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+                        .map(|ts| chrono::Utc::now().signed_duration_since(ts) > chrono::Duration::minutes(5))
+                        .unwrap_or(true);
+                    if is_stale { "closed" } else { "completed" }
+                }
+            }
+        };
+
+        if let Some(prompt) = &state.first_prompt {
+            if item.title.starts_with(&format!("{} Session", agent.label)) {
+                db.update_item_title(&item.id, prompt)?;
             }
         }
 
-        // Auto-uncheck when session becomes active
-        if (item.status == "input_needed" || item.status == "completed" || item.status == "closed" || item.status == "failed")
+        let mut new_metadata = metadata.clone();
+        if let Some(map) = new_metadata.as_object_mut() {
+            map.insert("cwd".to_string(), serde_json::json!(state.cwd));
+            map.insert("branch".to_string(), serde_json::json!(state.branch));
+            map.insert("repository".to_string(), serde_json::json!(state.repository));
+            map.insert("last_prompt".to_string(), serde_json::json!(state.last_prompt));
+            map.insert("last_activity".to_string(), serde_json::json!(state.last_event_at));
+            state.stats.insert_into(map);
+        }
+        db.update_item_status(&item.id, new_status, Some(&new_metadata.to_string()))?;
+
+        Self::notify_transition(db, app_handle, item, new_status);
+
+        if (item.status == "input_needed" || item.status == "completed" || item.status == "closed")
             && (new_status == "waiting" || new_status == "in_progress")
         {
             db.toggle_checked(&item.id, false)?;
@@ -716,6 +1198,43 @@ impl PollingManager {
         Ok(())
     }
 
+    /// Mark a running CLI session as "lost" when its wrapper process is gone
+    /// or has stopped sending heartbeats (e.g. the terminal was killed).
+    /// Returns whether it was marked lost.
+    fn check_cli_session_liveness(
+        db: &Arc<Database>,
+        item: &crate::db::Item,
+        metadata: &serde_json::Value,
+        app_handle: &AppHandle,
+    ) -> anyhow::Result<bool> {
+        // Copilot commands are also waiting or input_needed while they run
+        if !matches!(item.status.as_str(), "in_progress" | "waiting" | "input_needed") {
+            return Ok(false);
+        }
+
+        let reason = match cli_session::detect_lost_session(
+            metadata,
+            chrono::Utc::now(),
+            cli_session::is_process_alive,
+        ) {
+            Some(r) => r,
+            None => return Ok(false),
+        };
+
+        let mut new_meta = metadata.clone();
+        if let Some(map) = new_meta.as_object_mut() {
+            map.insert("lost_reason".to_string(), serde_json::json!(reason.as_str()));
+            map.insert(
+                "ended_at".to_string(),
+                serde_json::json!(chrono::Utc::now().to_rfc3339()),
+            );
+        }
+        db.update_item_status(&item.id, "lost", Some(&new_meta.to_string()))?;
+        Self::notify_transition(db, app_handle, item, "lost");
+
+        Ok(true)
+    }
+
     fn poll_cli_session(
         db: &Arc<Database>,
         item: &crate::db::Item,
@@ -725,8 +1244,14 @@ impl PollingManager {
         let metadata: serde_json::Value = serde_json::from_str(&item.metadata)?;
         let command = metadata["command"].as_str().unwrap_or("");
 
-        // Only enrich copilot sessions
-        if !command.contains("copilot") {
+        if Self::check_cli_session_liveness(db, item, &metadata, app_handle)? {
+            return Ok(());
+        }
+
+        // Plain commands report their own final status and a lost command
+        // comes back with its next heartbeat. Running Copilot commands are
+        // enriched from events.jsonl below.
+        if item.status == "lost" || !command.contains("copilot") {
             db.touch_item_check(&item.id)?;
             return Ok(());
         }
@@ -736,9 +1261,10 @@ impl PollingManager {
             if let Some(session) = copilot_cli::read_session(sid) {
                 // Detect live status
                 let process_running = copilot_cli::is_session_process_running(&session, active_cwds);
-                let activity = copilot_cli::detect_session_activity(sid, process_running);
+                let state = Self::copilot_session_state(db, sid);
+                let activity = state.classify(process_running);
 
-                let last_activity_ts = copilot_cli::last_event_timestamp(sid);
+                let last_activity_ts = state.last_event_at.clone();
 
                 let new_status = if !process_running || item.status == "closed" {
                     "closed"
@@ -776,27 +1302,12 @@ impl PollingManager {
                     if let Some(ts) = &last_activity_ts {
                         map.insert("last_activity".to_string(), serde_json::json!(ts));
                     }
+                    state.stats.insert_into(map);
                 }
                 db.update_item_status(&item.id, new_status, Some(&new_meta.to_string()))?;
 
                 // Notifications on status transitions
-                if new_status != item.status {
-                    if let Some((body, category)) = classify_notification(item.status.as_str(), new_status) {
-                        let should_notify = match category {
-                            NotificationCategory::SessionStarted => db.get_setting("notify_session_started").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                            NotificationCategory::SessionEnded => db.get_setting("notify_session_ended").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                            NotificationCategory::InputNeeded => db.get_setting("notify_input_needed").ok().flatten().map(|v| v != "false").unwrap_or(true),
-                        };
-                        if should_notify {
-                            let _ = app_handle
-                                .notification()
-                                .builder()
-                                .title(&item.title)
-                                .body(body)
-                                .show();
-                        }
-                    }
-                }
+                Self::notify_transition(db, app_handle, item, new_status);
 
                 return Ok(());
             }
@@ -827,7 +1338,8 @@ impl PollingManager {
 
             // Detect live status from events.jsonl on first match
             let process_running = copilot_cli::is_session_process_running(&session, active_cwds);
-            let activity = copilot_cli::detect_session_activity(&session.id, process_running);
+            let state = Self::copilot_session_state(db, &session.id);
+            let activity = state.classify(process_running);
 
             let new_status = if !process_running || item.status == "closed" {
                 "closed"
@@ -852,7 +1364,7 @@ impl PollingManager {
                     serde_json::json!(session.repository),
                 );
                 map.insert("branch".to_string(), serde_json::json!(session.branch));
-                if let Some(ts) = copilot_cli::last_event_timestamp(&session.id) {
+                if let Some(ts) = &state.last_event_at {
                     map.insert("last_activity".to_string(), serde_json::json!(ts));
                 }
             }
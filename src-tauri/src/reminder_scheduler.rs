@@ -0,0 +1,85 @@
+use crate::db::{Database, Item};
+use crate::services::{reminders, rules};
+use crate::tray;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// How often items are checked for reminders that came due.
+const TICK: Duration = Duration::from_secs(30);
+
+/// Remind about items that stay in a status waiting on the user, and pulse
+/// the tray badge while any of them was reminded about and not snoozed.
+pub fn start(db: Arc<Database>, app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK).await;
+            check(&db, &app_handle);
+        }
+    });
+}
+
+/// Show the reminders that are due and update the tray badge's pulse.
+pub fn check(db: &Arc<Database>, app_handle: &AppHandle) {
+    match remind(db, app_handle, true) {
+        Ok(overdue) => tray::set_badge_pulsing(app_handle, overdue),
+        Err(e) => eprintln!("Failed to check reminders: {}", e),
+    }
+}
+
+/// Update the tray badge's pulse without showing the reminders that are
+/// due, e.g. after an item was snoozed.
+pub fn refresh_pulse(db: &Arc<Database>, app_handle: &AppHandle) {
+    match remind(db, app_handle, false) {
+        Ok(overdue) => tray::set_badge_pulsing(app_handle, overdue),
+        Err(e) => eprintln!("Failed to check reminders: {}", e),
+    }
+}
+
+/// Show the reminders that are due unless `show_due` is false; returns
+/// whether any item is overdue.
+fn remind(db: &Arc<Database>, app_handle: &AppHandle, show_due: bool) -> Result<bool> {
+    let settings = db.get_all_settings()?;
+    let interval = chrono::Duration::minutes(settings.reminder_interval_minutes);
+    let states = db.get_item_reminders()?;
+    let now = chrono::Utc::now();
+    let mut overdue = false;
+
+    for item in db.get_items(false)? {
+        if !reminders::REMINDER_STATUSES.contains(&item.status.as_str()) {
+            continue;
+        }
+        let category = reminders::category(&item.item_type, &item.status);
+        if !db.notification_enabled(&item, category)? {
+            continue;
+        }
+        let history = rules::status_history(&item.id, &db.get_item_events(&item.id)?);
+        let entered_at = match reminders::status_entered_at(&item, &history) {
+            Some(at) => at,
+            None => continue,
+        };
+        let state = states.get(&item.id);
+        overdue |= reminders::is_overdue(entered_at, state, now);
+        if !show_due {
+            continue;
+        }
+
+        if let Some(reminder) = reminders::due(entered_at, state, interval, now) {
+            let body = reminders::body(&item.status, reminder.waiting);
+            show_reminder(app_handle, &item, &body, settings.reminder_loud_sound);
+            db.record_reminder(&item.id, &now.to_rfc3339(), reminder.count)?;
+            overdue = true;
+        }
+    }
+    Ok(overdue)
+}
+
+fn show_reminder(app_handle: &AppHandle, item: &Item, body: &str, loud: bool) {
+    let mut builder = app_handle.notification().builder().title(&item.title).body(body);
+    if loud {
+        builder = builder.sound(reminders::LOUD_SOUND);
+    }
+    let _ = builder.show();
+}
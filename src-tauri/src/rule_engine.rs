@@ -0,0 +1,157 @@
+use crate::db::{Database, Item, Todo};
+use crate::hook_runner;
+use crate::services::rules::{self, Action, MatchContext, Rule, RuleMatch, StatusChange};
+use crate::services::webhooks;
+use crate::tray;
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// Run the user's rules on every status change `Database::update_item_status`
+/// publishes.
+pub fn start(db: Arc<Database>, app_handle: AppHandle) {
+    let mut changes = db.subscribe_status_changes();
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        while let Some(change) = changes.recv().await {
+            if let Err(e) = handle_change(&db, &app_handle, &client, &change).await {
+                eprintln!("Failed to run rules for item {}: {}", change.item_id, e);
+            }
+        }
+    });
+}
+
+async fn handle_change(
+    db: &Arc<Database>,
+    app_handle: &AppHandle,
+    client: &reqwest::Client,
+    change: &StatusChange,
+) -> Result<()> {
+    let rules: Vec<Rule> = db.get_rules()?.into_iter().filter(|r| r.enabled).collect();
+    if rules.is_empty() {
+        return Ok(());
+    }
+    let item = match db.get_item(&change.item_id)? {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+    let context = match_context(db, &item, change)?;
+
+    for rule in rules.iter().filter(|r| r.matches(&item, change, &context)) {
+        for action in &rule.actions {
+            let described = action.describe(change, &item);
+            let message = match run_action(db, app_handle, client, action, &item, change).await {
+                Ok(()) => format!("Rule \"{}\": {}", rule.name, described),
+                Err(e) => format!("Rule \"{}\" failed to {}: {}", rule.name, described, e),
+            };
+            db.add_item_event(&item.id, "rule", &message)?;
+        }
+    }
+
+    let _ = app_handle.emit("item-updated", &item.id);
+    tray::refresh_tray(app_handle, db);
+    Ok(())
+}
+
+fn match_context(db: &Database, item: &Item, change: &StatusChange) -> Result<MatchContext> {
+    Ok(MatchContext {
+        bound_todos: db.get_todo_ids_for_item(&item.id)?.len(),
+        hour: rules::local_hour(&change.at),
+    })
+}
+
+async fn run_action(
+    db: &Arc<Database>,
+    app_handle: &AppHandle,
+    client: &reqwest::Client,
+    action: &Action,
+    item: &Item,
+    change: &StatusChange,
+) -> Result<()> {
+    match action {
+        Action::Archive => {
+            db.archive_item(&item.id)?;
+            // Archiving isn't a status change, so hooks for it are run here
+            if let Some(archived) = db.get_item(&item.id)? {
+                hook_runner::run_hooks(db, &archived, &item.status, "archived");
+            }
+            Ok(())
+        }
+        Action::CompleteBoundTodos => {
+            for todo_id in db.get_todo_ids_for_item(&item.id)? {
+                db.update_todo_status(&todo_id, "done")?;
+            }
+            Ok(())
+        }
+        Action::CreateTodo { title, bind } => {
+            let todo = Todo {
+                id: uuid::Uuid::new_v4().to_string(),
+                title: rules::render(title, item, change),
+                status: "open".to_string(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                completed_at: None,
+                planned_date: None,
+                parent_id: None,
+            };
+            db.add_todo(&todo)?;
+            if *bind {
+                db.bind_todo_to_item(&todo.id, &item.id)?;
+            }
+            Ok(())
+        }
+        Action::Notify { title, body } => {
+            let title = title.as_deref().map(|t| rules::render(t, item, change)).unwrap_or_else(|| item.title.clone());
+            app_handle
+                .notification()
+                .builder()
+                .title(title)
+                .body(rules::render(body, item, change))
+                .show()
+                .map_err(|e| anyhow!("{}", e))
+        }
+        Action::Webhook { url } => {
+            let payload = serde_json::json!({
+                "item": item,
+                "from": change.from,
+                "to": change.to,
+                "at": change.at,
+            });
+            let response = client
+                .post(url)
+                .timeout(webhooks::REQUEST_TIMEOUT)
+                .json(&payload)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("HTTP {}", response.status()));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Which of `rules` would have fired over the recorded status history of
+/// every item, archived ones included. Conditions are checked against the
+/// item as it is now.
+pub fn dry_run(db: &Database, rules: &[Rule]) -> Result<Vec<RuleMatch>> {
+    let mut matches = Vec::new();
+    let mut items = db.get_items(false)?;
+    items.extend(db.get_items(true)?);
+
+    for item in &items {
+        let history = rules::status_history(&item.id, &db.get_item_events(&item.id)?);
+        for change in &history {
+            let context = match_context(db, item, change)?;
+            matches.extend(
+                rules
+                    .iter()
+                    .filter(|r| r.matches(item, change, &context))
+                    .map(|r| RuleMatch::new(r, item, change)),
+            );
+        }
+    }
+
+    matches.sort_by(|a, b| b.at.cmp(&a.at));
+    Ok(matches)
+}
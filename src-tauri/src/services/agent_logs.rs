@@ -0,0 +1,1055 @@
+//! Session tracking for coding agents that write JSON session logs under
+//! their home directory (Claude Code, Codex CLI, Gemini CLI). Each agent is
+//! described by an `AgentRules` value: where its logs live, how events are
+//! recognized and what each event means for the session's status. Adding an
+//! agent means adding a rule set to `AGENTS`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::claude_code;
+use super::copilot_cli::{truncate_title, PendingTool, SessionActivity, SessionStats};
+use super::process_table;
+use super::session_state::IncrementalState;
+
+/// Only logs written to within this window are discovered.
+const DISCOVERY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How deep below the sessions directory log files are looked for.
+const MAX_DISCOVERY_DEPTH: usize = 4;
+
+/// A turn that waits this long on what looks like an approval prompt,
+/// while the process runs, is reported as needing input.
+const APPROVAL_THRESHOLD_SECS: i64 = 60;
+
+/// How an agent lays out a session's log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// One JSON event per line, appended as the session goes.
+    JsonLines,
+    /// A single JSON document rewritten on every change, with the events in
+    /// the array at this pointer.
+    JsonDocument(&'static str),
+}
+
+/// What an event means for the session.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    /// Only carries fields (cwd, model, usage); the status is unchanged.
+    Meta,
+    /// The user sent a prompt; a turn starts.
+    Prompt,
+    /// The agent is working: text, reasoning, a turn starting.
+    Activity,
+    /// A tool call started.
+    ToolStart,
+    /// A tool call finished.
+    ToolEnd,
+    /// The agent asked the user for something (approval, an answer).
+    InputRequest,
+    /// The agent finished its turn.
+    TurnEnd,
+}
+
+/// Maps an event kind to its effect. The first matching rule applies.
+#[derive(Debug, Clone, Copy)]
+pub struct EventRule {
+    pub kind: &'static str,
+    /// Only match when this pointer holds a non-empty value.
+    pub requires: Option<&'static str>,
+    pub effect: Effect,
+}
+
+const fn on(kind: &'static str, effect: Effect) -> EventRule {
+    EventRule { kind, requires: None, effect }
+}
+
+/// How a waiting approval prompt shows up in the log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApprovalSignal {
+    /// Tool calls are logged before approval: a tool call stays pending.
+    PendingTool,
+    /// Tool calls are logged after they ran: an open turn goes quiet.
+    SilentTurn,
+}
+
+/// JSON pointers to the fields of an event. Each list is tried in order.
+#[derive(Debug, Clone, Copy)]
+pub struct Fields {
+    pub timestamp: &'static [&'static str],
+    pub cwd: &'static [&'static str],
+    pub branch: &'static [&'static str],
+    pub repository: &'static [&'static str],
+    pub model: &'static [&'static str],
+    pub prompt: &'static [&'static str],
+    pub tool_name: &'static [&'static str],
+    pub call_id: &'static [&'static str],
+    pub input_tokens: &'static [&'static str],
+    /// Cached input, subtracted from `input_tokens` when it includes it.
+    pub cached_tokens: &'static [&'static str],
+    pub output_tokens: &'static [&'static str],
+    /// Token counts are running totals rather than per-event amounts.
+    pub cumulative_usage: bool,
+    /// Id of the message the usage belongs to. Usage logged again for the
+    /// same message replaces the earlier count rather than adding to it.
+    pub message_id: &'static [&'static str],
+    /// Hash of the project root, for agents that log no cwd.
+    pub project_hash: &'static [&'static str],
+    /// A file the agent modified.
+    pub edited_file: &'static [&'static str],
+}
+
+/// Everything needed to track one agent's sessions.
+#[derive(Debug, Clone, Copy)]
+pub struct AgentRules {
+    pub item_type: &'static str,
+    pub label: &'static str,
+    /// Process name, matched like `copilot` in the process table.
+    pub program: &'static str,
+    /// Log directory, relative to the home directory.
+    pub sessions_dir: &'static str,
+    pub file_prefix: &'static str,
+    /// Logs of sub-agents, which are part of their parent's session.
+    pub subagent_prefix: Option<&'static str>,
+    pub file_extension: &'static str,
+    pub format: LogFormat,
+    /// Breaks a log line that carries several events into separate events.
+    /// Without it every line is one event.
+    pub split: Option<fn(&serde_json::Value) -> Vec<serde_json::Value>>,
+    /// Pointers whose string values, joined with `:`, form an event's kind.
+    pub kind: &'static [&'static str],
+    pub rules: &'static [EventRule],
+    pub fields: Fields,
+    pub approval: ApprovalSignal,
+    /// Tools that are expected to take long, e.g. running sub-agents; while
+    /// one is pending the turn is never taken for an approval prompt.
+    pub long_running_tools: &'static [&'static str],
+    /// Agent activity not followed by an explicit end of the turn is taken
+    /// as the end once nothing followed it for this many seconds.
+    pub turn_settle_secs: Option<i64>,
+}
+
+/// Claude Code: `~/.claude/projects/<project>/<session id>.jsonl`. Each line
+/// can carry several events, which `claude_code::split` separates.
+pub const CLAUDE: AgentRules = AgentRules {
+    item_type: "claude_session",
+    label: "Claude",
+    program: "claude",
+    sessions_dir: ".claude/projects",
+    file_prefix: "",
+    subagent_prefix: Some("agent-"),
+    file_extension: "jsonl",
+    format: LogFormat::JsonLines,
+    split: Some(claude_code::split),
+    kind: &["/type"],
+    rules: &[
+        on(claude_code::PROMPT, Effect::Prompt),
+        on(claude_code::ASSISTANT, Effect::Activity),
+        on(claude_code::TOOL_USE, Effect::ToolStart),
+        on(claude_code::TOOL_RESULT, Effect::ToolEnd),
+        on(claude_code::QUESTION, Effect::InputRequest),
+        on(claude_code::TURN_END, Effect::TurnEnd),
+    ],
+    fields: Fields {
+        timestamp: &["/timestamp"],
+        cwd: &["/cwd"],
+        branch: &["/branch"],
+        repository: &[],
+        model: &["/model"],
+        prompt: &["/text"],
+        tool_name: &["/name"],
+        call_id: &["/call_id"],
+        input_tokens: &["/input_tokens"],
+        cached_tokens: &[],
+        output_tokens: &["/output_tokens"],
+        cumulative_usage: false,
+        message_id: &["/message_id"],
+        project_hash: &[],
+        edited_file: &["/file_path"],
+    },
+    approval: ApprovalSignal::PendingTool,
+    long_running_tools: claude_code::LONG_RUNNING_TOOLS,
+    turn_settle_secs: Some(claude_code::TURN_SETTLE_SECS),
+};
+
+/// Codex CLI: `~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl`.
+pub const CODEX: AgentRules = AgentRules {
+    item_type: "codex_session",
+    label: "Codex",
+    program: "codex",
+    sessions_dir: ".codex/sessions",
+    file_prefix: "rollout-",
+    subagent_prefix: None,
+    file_extension: "jsonl",
+    format: LogFormat::JsonLines,
+    split: None,
+    kind: &["/type", "/payload/type", "/payload/role"],
+    rules: &[
+        on("event_msg:user_message", Effect::Prompt),
+        on("event_msg:task_started", Effect::Activity),
+        on("event_msg:agent_message", Effect::Activity),
+        on("response_item:message:assistant", Effect::Activity),
+        on("response_item:reasoning", Effect::Activity),
+        on("response_item:function_call", Effect::ToolStart),
+        on("response_item:custom_tool_call", Effect::ToolStart),
+        on("response_item:local_shell_call", Effect::ToolStart),
+        on("response_item:function_call_output", Effect::ToolEnd),
+        on("response_item:custom_tool_call_output", Effect::ToolEnd),
+        on("event_msg:exec_approval_request", Effect::InputRequest),
+        on("event_msg:apply_patch_approval_request", Effect::InputRequest),
+        on("event_msg:task_complete", Effect::TurnEnd),
+        on("event_msg:turn_aborted", Effect::TurnEnd),
+        on("session_meta", Effect::Meta),
+        on("turn_context", Effect::Meta),
+        on("event_msg:token_count", Effect::Meta),
+    ],
+    fields: Fields {
+        timestamp: &["/timestamp"],
+        cwd: &["/payload/cwd"],
+        branch: &["/payload/git/branch"],
+        repository: &["/payload/git/repository_url"],
+        model: &["/payload/model"],
+        prompt: &["/payload/message"],
+        tool_name: &["/payload/name"],
+        call_id: &["/payload/call_id"],
+        input_tokens: &["/payload/info/total_token_usage/input_tokens"],
+        cached_tokens: &["/payload/info/total_token_usage/cached_input_tokens"],
+        output_tokens: &["/payload/info/total_token_usage/output_tokens"],
+        cumulative_usage: true,
+        message_id: &[],
+        project_hash: &[],
+        edited_file: &[],
+    },
+    approval: ApprovalSignal::PendingTool,
+    long_running_tools: &[],
+    turn_settle_secs: None,
+};
+
+/// Gemini CLI: `~/.gemini/tmp/<project hash>/chats/session-*.json`.
+pub const GEMINI: AgentRules = AgentRules {
+    item_type: "gemini_session",
+    label: "Gemini",
+    program: "gemini",
+    sessions_dir: ".gemini/tmp",
+    file_prefix: "session-",
+    subagent_prefix: None,
+    file_extension: "json",
+    format: LogFormat::JsonDocument("/messages"),
+    split: None,
+    kind: &["/type"],
+    rules: &[
+        on("user", Effect::Prompt),
+        EventRule {
+            kind: "gemini",
+            requires: Some("/toolCalls/0"),
+            effect: Effect::Activity,
+        },
+        on("gemini", Effect::TurnEnd),
+        on("error", Effect::TurnEnd),
+        on("info", Effect::Meta),
+    ],
+    fields: Fields {
+        timestamp: &["/timestamp", "/startTime"],
+        cwd: &[],
+        branch: &[],
+        repository: &[],
+        model: &["/model"],
+        prompt: &["/content"],
+        tool_name: &[],
+        call_id: &[],
+        input_tokens: &["/tokens/input"],
+        cached_tokens: &["/tokens/cached"],
+        output_tokens: &["/tokens/output"],
+        cumulative_usage: false,
+        message_id: &[],
+        project_hash: &["/projectHash"],
+        edited_file: &[],
+    },
+    approval: ApprovalSignal::SilentTurn,
+    long_running_tools: &[],
+    turn_settle_secs: None,
+};
+
+pub const AGENTS: &[AgentRules] = &[CLAUDE, CODEX, GEMINI];
+
+/// The rule set tracking items of `item_type`, if any.
+pub fn rules_for(item_type: &str) -> Option<&'static AgentRules> {
+    AGENTS.iter().find(|a| a.item_type == item_type)
+}
+
+fn lookup<'a>(value: &'a serde_json::Value, pointers: &[&str]) -> Option<&'a serde_json::Value> {
+    pointers
+        .iter()
+        .filter_map(|p| value.pointer(p))
+        .find(|v| !v.is_null())
+}
+
+fn lookup_str(value: &serde_json::Value, pointers: &[&str]) -> Option<String> {
+    lookup(value, pointers)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+fn lookup_u64(value: &serde_json::Value, pointers: &[&str]) -> Option<u64> {
+    lookup(value, pointers).and_then(|v| v.as_u64())
+}
+
+/// `owner/repo` from a git remote URL, or the URL unchanged.
+fn repository_slug(url: &str) -> String {
+    let trimmed = url.trim_end_matches('/').trim_end_matches(".git");
+    let mut parts = trimmed.rsplit(['/', ':']);
+    match (parts.next(), parts.next()) {
+        (Some(repo), Some(owner)) if !repo.is_empty() && !owner.is_empty() && trimmed.contains("github") => {
+            format!("{}/{}", owner, repo)
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// The Gemini CLI names its project directories after the SHA-256 of the
+/// project root.
+pub fn project_hash(path: &str) -> String {
+    Sha256::digest(path.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl AgentRules {
+    pub fn sessions_dir(&self) -> Option<PathBuf> {
+        let home = std::env::var("HOME").ok()?;
+        Some(PathBuf::from(home).join(self.sessions_dir))
+    }
+
+    /// Working directories of this agent's running processes.
+    pub fn active_cwds(&self) -> HashSet<String> {
+        process_table::default_process_table().cwds_of(self.program)
+    }
+
+    /// Find recently written session logs.
+    pub fn discover_sessions(&self) -> Vec<AgentSession> {
+        match self.sessions_dir() {
+            Some(base) => self.discover_sessions_in(&base, SystemTime::now() - DISCOVERY_WINDOW),
+            None => vec![],
+        }
+    }
+
+    fn discover_sessions_in(&self, base: &Path, modified_since: SystemTime) -> Vec<AgentSession> {
+        let mut sessions = Vec::new();
+        let mut dirs = vec![(base.to_path_buf(), 0)];
+        while let Some((dir, depth)) = dirs.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let metadata = match entry.metadata() {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+                if metadata.is_dir() {
+                    if depth < MAX_DISCOVERY_DEPTH {
+                        dirs.push((path, depth + 1));
+                    }
+                    continue;
+                }
+                let recent = metadata.modified().map(|t| t >= modified_since).unwrap_or(false);
+                if recent {
+                    if let Some(session) = self.session_at(&path) {
+                        sessions.push(session);
+                    }
+                }
+            }
+        }
+        sessions
+    }
+
+    /// The session logged at `path`, if it is one of this agent's logs.
+    pub fn session_at(&self, path: &Path) -> Option<AgentSession> {
+        if path.extension().and_then(|e| e.to_str()) != Some(self.file_extension) {
+            return None;
+        }
+        let id = path.file_stem()?.to_str()?;
+        if !id.starts_with(self.file_prefix) || self.subagent_prefix.is_some_and(|p| id.starts_with(p)) {
+            return None;
+        }
+        Some(AgentSession {
+            id: id.to_string(),
+            log_path: path.to_path_buf(),
+            updated_at: fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+        })
+    }
+
+    fn event_kind(&self, event: &serde_json::Value) -> String {
+        self.kind
+            .iter()
+            .filter_map(|p| event.pointer(p).and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    fn effect_of(&self, event: &serde_json::Value) -> Option<Effect> {
+        let kind = self.event_kind(event);
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.kind == kind
+                    && rule
+                        .requires
+                        .map(|p| event.pointer(p).map(|v| !v.is_null()).unwrap_or(false))
+                        .unwrap_or(true)
+            })
+            .map(|rule| rule.effect)
+    }
+}
+
+/// A session log found on disk. Its id is the log's file name.
+#[derive(Debug, Clone)]
+pub struct AgentSession {
+    pub id: String,
+    pub log_path: PathBuf,
+    pub updated_at: Option<String>,
+}
+
+/// Parser state for a session log, persisted in SQLite (with `offset`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Bytes of a JSON Lines log consumed so far, or the size of a JSON
+    /// document when it was last parsed. Stored in its own column.
+    #[serde(skip)]
+    pub offset: u64,
+    pub turn_open: bool,
+    pub input_requested: bool,
+    pub pending_tools: Vec<PendingTool>,
+    pub last_effect: Option<Effect>,
+    pub last_event_at: Option<String>,
+    pub started_at: Option<String>,
+    pub cwd: Option<String>,
+    pub branch: Option<String>,
+    pub repository: Option<String>,
+    pub project_hash: Option<String>,
+    pub first_prompt: Option<String>,
+    pub last_prompt: Option<String>,
+    /// Message whose usage was counted last, and how much was counted.
+    pub usage_message_id: Option<String>,
+    pub usage_counted: (u64, u64),
+    pub stats: SessionStats,
+    pub version: u32,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        SessionState {
+            offset: 0,
+            turn_open: false,
+            input_requested: false,
+            pending_tools: Vec::new(),
+            last_effect: None,
+            last_event_at: None,
+            started_at: None,
+            cwd: None,
+            branch: None,
+            repository: None,
+            project_hash: None,
+            first_prompt: None,
+            last_prompt: None,
+            usage_message_id: None,
+            usage_counted: (0, 0),
+            stats: SessionStats::default(),
+            version: Self::VERSION,
+        }
+    }
+}
+
+impl IncrementalState for SessionState {
+    const VERSION: u32 = 2;
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn set_offset(&mut self, offset: u64) {
+        self.offset = offset;
+    }
+}
+
+impl SessionState {
+    /// Fold in what was written to the log since the last read.
+    /// Returns true if the state changed and should be persisted.
+    pub fn read_new(&mut self, rules: &AgentRules, path: &Path) -> bool {
+        match rules.format {
+            LogFormat::JsonLines => self.read_new_lines(rules, path),
+            LogFormat::JsonDocument(events) => self.read_document(rules, path, events),
+        }
+    }
+
+    fn read_new_lines(&mut self, rules: &AgentRules, path: &Path) -> bool {
+        let mut file = match fs::File::open(path) {
+            Ok(f) => f,
+            Err(_) => return false,
+        };
+        let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let mut changed = false;
+        if file_len < self.offset {
+            *self = SessionState::default();
+            changed = true;
+        }
+        if file.seek(SeekFrom::Start(self.offset)).is_err() {
+            return changed;
+        }
+
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    // A partially written line is picked up on the next read.
+                    if line.last() != Some(&b'\n') {
+                        break;
+                    }
+                    self.offset += n as u64;
+                    changed = true;
+                    if let Ok(event) = serde_json::from_slice::<serde_json::Value>(&line) {
+                        self.apply(rules, &event);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// Documents are rewritten as a whole, so they are re-parsed whenever
+    /// their size changes.
+    fn read_document(&mut self, rules: &AgentRules, path: &Path, events: &str) -> bool {
+        let content = match fs::read(path) {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+        if content.len() as u64 == self.offset {
+            return false;
+        }
+        let document: serde_json::Value = match serde_json::from_slice(&content) {
+            Ok(d) => d,
+            // Caught mid-write; try again on the next read
+            Err(_) => return false,
+        };
+
+        // Keep the cwd found from the project hash across re-parses
+        let cwd = self.cwd.take();
+        *self = SessionState {
+            offset: content.len() as u64,
+            cwd,
+            ..SessionState::default()
+        };
+        self.apply_fields(rules, &document);
+        for event in document.pointer(events).and_then(|v| v.as_array()).into_iter().flatten() {
+            self.apply(rules, event);
+        }
+        true
+    }
+
+    fn apply_fields(&mut self, rules: &AgentRules, event: &serde_json::Value) {
+        let fields = &rules.fields;
+        if let Some(ts) = lookup_str(event, fields.timestamp) {
+            self.started_at.get_or_insert(ts);
+        }
+        if let Some(cwd) = lookup_str(event, fields.cwd) {
+            self.cwd = Some(cwd);
+        }
+        if let Some(branch) = lookup_str(event, fields.branch) {
+            self.branch = Some(branch);
+        }
+        if let Some(url) = lookup_str(event, fields.repository) {
+            self.repository = Some(repository_slug(&url));
+        }
+        if let Some(hash) = lookup_str(event, fields.project_hash) {
+            self.project_hash = Some(hash);
+        }
+        if let Some(model) = lookup_str(event, fields.model) {
+            self.stats.model = Some(model);
+        }
+        if let Some(path) = lookup_str(event, fields.edited_file) {
+            if !self.stats.files_edited.contains(&path) {
+                self.stats.files_edited.push(path);
+            }
+        }
+
+        let input = lookup_u64(event, fields.input_tokens);
+        let output = lookup_u64(event, fields.output_tokens);
+        if input.is_some() || output.is_some() {
+            let cached = lookup_u64(event, fields.cached_tokens).unwrap_or(0);
+            let input = input.unwrap_or(0).saturating_sub(cached);
+            let output = output.unwrap_or(0);
+            if fields.cumulative_usage {
+                self.stats.input_tokens = input;
+                self.stats.output_tokens = output;
+                return;
+            }
+            let message_id = lookup_str(event, fields.message_id);
+            if message_id.is_some() && message_id == self.usage_message_id {
+                self.stats.input_tokens = self.stats.input_tokens.saturating_sub(self.usage_counted.0);
+                self.stats.output_tokens = self.stats.output_tokens.saturating_sub(self.usage_counted.1);
+            }
+            self.stats.input_tokens += input;
+            self.stats.output_tokens += output;
+            self.usage_message_id = message_id;
+            self.usage_counted = (input, output);
+        }
+    }
+
+    /// Fold a log line into the state.
+    pub fn apply(&mut self, rules: &AgentRules, line: &serde_json::Value) {
+        match rules.split {
+            Some(split) => {
+                for event in split(line) {
+                    self.apply_event(rules, &event);
+                }
+            }
+            None => self.apply_event(rules, line),
+        }
+    }
+
+    fn apply_event(&mut self, rules: &AgentRules, event: &serde_json::Value) {
+        self.apply_fields(rules, event);
+        let effect = match rules.effect_of(event) {
+            Some(e) => e,
+            None => return,
+        };
+        let fields = &rules.fields;
+
+        if effect != Effect::Meta && effect != Effect::InputRequest {
+            self.input_requested = false;
+        }
+        match effect {
+            Effect::Meta => {}
+            Effect::Prompt => {
+                self.pending_tools.clear();
+                let prompt = lookup(event, fields.prompt).map(prompt_text).unwrap_or_default();
+                let prompt = prompt.trim();
+                // Injected context (instructions, environment) isn't a prompt
+                if prompt.is_empty() || prompt.starts_with('<') {
+                    return;
+                }
+                self.turn_open = true;
+                self.stats.turn_count += 1;
+                let title = truncate_title(prompt);
+                if self.first_prompt.is_none() {
+                    self.first_prompt = Some(title.clone());
+                }
+                self.last_prompt = Some(title);
+            }
+            Effect::Activity => self.turn_open = true,
+            Effect::ToolStart => {
+                self.turn_open = true;
+                let name = lookup_str(event, fields.tool_name).unwrap_or_default();
+                if !name.is_empty() {
+                    *self.stats.tool_calls.entry(name.clone()).or_insert(0) += 1;
+                }
+                self.pending_tools.push(PendingTool {
+                    call_id: lookup_str(event, fields.call_id),
+                    name,
+                });
+            }
+            Effect::ToolEnd => {
+                let position = match lookup_str(event, fields.call_id) {
+                    Some(id) => self
+                        .pending_tools
+                        .iter()
+                        .position(|t| t.call_id.as_deref() == Some(id.as_str())),
+                    None => self.pending_tools.len().checked_sub(1),
+                };
+                if let Some(i) = position {
+                    self.pending_tools.remove(i);
+                }
+            }
+            Effect::InputRequest => self.input_requested = true,
+            Effect::TurnEnd => {
+                self.turn_open = false;
+                self.pending_tools.clear();
+            }
+        }
+
+        self.last_effect = Some(effect);
+        if let Some(ts) = lookup_str(event, fields.timestamp) {
+            self.last_event_at = Some(ts);
+        }
+    }
+
+    /// Determine the live activity status of the session.
+    ///
+    /// - Input requested → InputNeeded
+    /// - Turn ended (or never started) → Idle
+    /// - Long-running tool pending while the process runs → InProgress
+    /// - Open turn quiet past the approval threshold in the way the agent
+    ///   logs approvals → InputNeeded
+    /// - Agent activity quiet past the settle time → Idle
+    /// - Open turn → InProgress (or Idle if >2 min old)
+    pub fn classify(&self, rules: &AgentRules, process_running: bool) -> SessionActivity {
+        if self.last_effect.is_none() {
+            return if process_running {
+                SessionActivity::InProgress
+            } else {
+                SessionActivity::Idle
+            };
+        }
+
+        let last_event_age_secs = self
+            .last_event_at
+            .as_deref()
+            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| chrono::Utc::now().signed_duration_since(ts).num_seconds())
+            .unwrap_or(i64::MAX);
+        let is_stale = last_event_age_secs > 120; // 2 minutes
+
+        if self.input_requested {
+            return if process_running || !is_stale {
+                SessionActivity::InputNeeded
+            } else {
+                SessionActivity::Idle
+            };
+        }
+
+        if !self.turn_open {
+            return SessionActivity::Idle;
+        }
+
+        let long_running = self
+            .pending_tools
+            .iter()
+            .any(|t| rules.long_running_tools.contains(&t.name.as_str()));
+        if process_running && long_running {
+            return SessionActivity::InProgress;
+        }
+
+        let awaiting_approval = match rules.approval {
+            ApprovalSignal::PendingTool => !self.pending_tools.is_empty(),
+            ApprovalSignal::SilentTurn => self.pending_tools.is_empty(),
+        };
+        if process_running && awaiting_approval && last_event_age_secs > APPROVAL_THRESHOLD_SECS {
+            return SessionActivity::InputNeeded;
+        }
+
+        let settled = rules.turn_settle_secs.is_some_and(|settle| {
+            self.last_effect == Some(Effect::Activity)
+                && self.pending_tools.is_empty()
+                && last_event_age_secs > settle
+        });
+        if settled {
+            return SessionActivity::Idle;
+        }
+
+        if is_stale {
+            SessionActivity::Idle
+        } else {
+            SessionActivity::InProgress
+        }
+    }
+
+    /// Whether the session's process is running, matching its cwd or, when
+    /// only a project hash is known, the hash of a running process's cwd.
+    /// A cwd found through the hash is remembered.
+    pub fn is_process_running(&mut self, active_cwds: &HashSet<String>) -> bool {
+        if let Some(cwd) = &self.cwd {
+            return active_cwds.contains(cwd);
+        }
+        let hash = match &self.project_hash {
+            Some(h) => h,
+            None => return false,
+        };
+        match active_cwds.iter().find(|cwd| project_hash(cwd) == *hash) {
+            Some(cwd) => {
+                self.cwd = Some(cwd.clone());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Prompt text from a string or an array of `{text}` parts.
+fn prompt_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+fn classify_events(rules: &AgentRules, events: &[serde_json::Value], process_running: bool) -> SessionActivity {
+    let mut state = SessionState::default();
+    for event in events {
+        state.apply(rules, event);
+    }
+    state.classify(rules, process_running)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn now_ts() -> String {
+        chrono::Utc::now().to_rfc3339()
+    }
+
+    /// Create a timestamp N seconds in the past.
+    fn past_ts(seconds_ago: i64) -> String {
+        (chrono::Utc::now() - chrono::Duration::seconds(seconds_ago)).to_rfc3339()
+    }
+
+    fn codex(kind: &str, payload: serde_json::Value, ts: &str) -> serde_json::Value {
+        json!({"timestamp": ts, "type": kind, "payload": payload})
+    }
+
+    fn codex_prompt(text: &str, ts: &str) -> serde_json::Value {
+        codex("event_msg", json!({"type": "user_message", "message": text}), ts)
+    }
+
+    fn codex_tool(call_id: &str, ts: &str) -> serde_json::Value {
+        codex(
+            "response_item",
+            json!({"type": "function_call", "name": "shell", "call_id": call_id, "arguments": "{}"}),
+            ts,
+        )
+    }
+
+    fn gemini_message(kind: &str, ts: &str, extra: serde_json::Value) -> serde_json::Value {
+        let mut message = json!({"id": "m", "type": kind, "timestamp": ts, "content": "text"});
+        message.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        message
+    }
+
+    // ---- Codex ----
+
+    #[test]
+    fn codex_prompt_is_in_progress_until_task_complete() {
+        let events = vec![
+            codex("session_meta", json!({"id": "abc", "cwd": "/work/app", "git": {"branch": "main", "repository_url": "git@github.com:me/app.git"}}), &now_ts()),
+            codex("response_item", json!({"type": "message", "role": "user", "content": [{"type": "input_text", "text": "<environment_context>"}]}), &now_ts()),
+            codex_prompt("Fix the flaky test", &now_ts()),
+            codex("event_msg", json!({"type": "task_started"}), &now_ts()),
+        ];
+        assert_eq!(classify_events(&CODEX, &events, true), SessionActivity::InProgress);
+
+        let mut done = events.clone();
+        done.push(codex("event_msg", json!({"type": "task_complete"}), &now_ts()));
+        assert_eq!(classify_events(&CODEX, &done, true), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn codex_pending_tool_is_approval_prompt() {
+        let running = vec![codex_prompt("Run the tests", &now_ts()), codex_tool("c1", &now_ts())];
+        assert_eq!(classify_events(&CODEX, &running, true), SessionActivity::InProgress);
+
+        let waiting = vec![codex_prompt("Run the tests", &past_ts(100)), codex_tool("c1", &past_ts(90))];
+        assert_eq!(classify_events(&CODEX, &waiting, true), SessionActivity::InputNeeded);
+
+        let mut finished = waiting.clone();
+        finished.push(codex("response_item", json!({"type": "function_call_output", "call_id": "c1", "output": "ok"}), &past_ts(80)));
+        assert_eq!(classify_events(&CODEX, &finished, true), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn codex_stale_turn_is_idle() {
+        let events = vec![codex_prompt("Refactor", &past_ts(300))];
+        assert_eq!(classify_events(&CODEX, &events, false), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn codex_state_tracks_workspace_and_usage() {
+        let mut state = SessionState::default();
+        for event in [
+            codex("session_meta", json!({"id": "abc", "cwd": "/work/app", "git": {"branch": "main", "repository_url": "https://github.com/me/app.git"}}), &past_ts(10)),
+            codex("turn_context", json!({"cwd": "/work/app", "model": "gpt-5-codex"}), &now_ts()),
+            codex_prompt("Fix the flaky test", &now_ts()),
+            codex_tool("c1", &now_ts()),
+            codex("event_msg", json!({"type": "token_count", "info": {"total_token_usage": {"input_tokens": 1000, "cached_input_tokens": 400, "output_tokens": 100}}}), &now_ts()),
+            codex("event_msg", json!({"type": "token_count", "info": {"total_token_usage": {"input_tokens": 3000, "cached_input_tokens": 1000, "output_tokens": 300}}}), &now_ts()),
+        ] {
+            state.apply(&CODEX, &event);
+        }
+
+        assert_eq!(state.cwd.as_deref(), Some("/work/app"));
+        assert_eq!(state.branch.as_deref(), Some("main"));
+        assert_eq!(state.repository.as_deref(), Some("me/app"));
+        assert_eq!(state.first_prompt.as_deref(), Some("Fix the flaky test"));
+        assert_eq!(state.stats.model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(state.stats.input_tokens, 2000);
+        assert_eq!(state.stats.output_tokens, 300);
+        assert_eq!(state.stats.tool_calls.get("shell"), Some(&1));
+    }
+
+    #[test]
+    fn codex_logs_are_read_incrementally() {
+        let dir = std::env::temp_dir().join(format!("itl-codex-{}", uuid::Uuid::new_v4()));
+        let day = dir.join("2026").join("10").join("18");
+        fs::create_dir_all(&day).unwrap();
+        let path = day.join("rollout-2026-10-18T09-00-00-abc.jsonl");
+        fs::write(&path, format!("{}\n", codex_prompt("Fix the flaky test", &now_ts()))).unwrap();
+        fs::write(day.join("notes.txt"), "not a log").unwrap();
+
+        let sessions = CODEX.discover_sessions_in(&dir, SystemTime::now() - DISCOVERY_WINDOW);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, "rollout-2026-10-18T09-00-00-abc");
+
+        let mut state = SessionState::default();
+        assert!(state.read_new(&CODEX, &path));
+        assert!(!state.read_new(&CODEX, &path));
+        use std::io::Write;
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}\n{{\"partial", codex("event_msg", json!({"type": "task_complete"}), &now_ts())).unwrap();
+        assert!(state.read_new(&CODEX, &path));
+        assert_eq!(state.last_effect, Some(Effect::TurnEnd));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // ---- Claude ----
+
+    fn claude_prompt(text: &str, ts: &str) -> serde_json::Value {
+        json!({"type": "user", "cwd": "/work/app", "timestamp": ts, "message": {"role": "user", "content": text}})
+    }
+
+    #[test]
+    fn claude_transcripts_skip_subagents_and_empty_logs() {
+        let dir = std::env::temp_dir().join(format!("itl-claude-{}", uuid::Uuid::new_v4()));
+        let project = dir.join("-work-app");
+        fs::create_dir_all(&project).unwrap();
+        let path = project.join("3f2a.jsonl");
+        let lines = [
+            json!({"type": "summary", "summary": "Old summary"}),
+            claude_prompt("Caveat: The messages below were generated by the user", &now_ts()),
+            claude_prompt("Add a health endpoint", &now_ts()),
+        ];
+        fs::write(&path, lines.iter().map(|l| format!("{}\n", l)).collect::<String>()).unwrap();
+        fs::write(project.join("agent-1.jsonl"), format!("{}\n", claude_prompt("Sub-agent task", &now_ts()))).unwrap();
+        fs::write(project.join("empty.jsonl"), format!("{}\n", json!({"type": "summary", "summary": "x"}))).unwrap();
+
+        let mut sessions = CLAUDE.discover_sessions_in(&dir, SystemTime::now() - DISCOVERY_WINDOW);
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+        let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["3f2a", "empty"]);
+
+        let mut state = SessionState::default();
+        assert!(state.read_new(&CLAUDE, &path));
+        assert!(!state.read_new(&CLAUDE, &path));
+        assert_eq!(state.first_prompt.as_deref(), Some("Add a health endpoint"));
+        assert!(state.is_process_running(&HashSet::from(["/work/app".to_string()])));
+
+        // A transcript holding only a summary has no session to track yet
+        let mut empty = SessionState::default();
+        empty.read_new(&CLAUDE, &project.join("empty.jsonl"));
+        assert!(empty.started_at.is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // ---- Gemini ----
+
+    #[test]
+    fn gemini_turn_ends_with_final_answer() {
+        let working = vec![
+            gemini_message("user", &now_ts(), json!({"content": "Explain the build"})),
+            gemini_message("gemini", &now_ts(), json!({"toolCalls": [{"id": "t1", "name": "read_file", "status": "success"}]})),
+        ];
+        assert_eq!(classify_events(&GEMINI, &working, true), SessionActivity::InProgress);
+
+        let mut done = working.clone();
+        done.push(gemini_message("gemini", &now_ts(), json!({"tokens": {"input": 10, "output": 5}})));
+        assert_eq!(classify_events(&GEMINI, &done, true), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn gemini_quiet_turn_is_approval_prompt() {
+        let events = vec![
+            gemini_message("user", &past_ts(100), json!({"content": "Delete the build dir"})),
+            gemini_message("gemini", &past_ts(90), json!({"toolCalls": [{"id": "t1", "name": "read_file", "status": "success"}]})),
+        ];
+        assert_eq!(classify_events(&GEMINI, &events, true), SessionActivity::InputNeeded);
+        assert_eq!(classify_events(&GEMINI, &events, false), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn gemini_document_is_reparsed_when_rewritten() {
+        let dir = std::env::temp_dir().join(format!("itl-gemini-{}", uuid::Uuid::new_v4()));
+        let chats = dir.join(project_hash("/work/app")).join("chats");
+        fs::create_dir_all(&chats).unwrap();
+        let path = chats.join("session-2026-10-18T09-00-abc.json");
+        let mut document = json!({
+            "sessionId": "abc",
+            "projectHash": project_hash("/work/app"),
+            "startTime": now_ts(),
+            "messages": [gemini_message("user", &now_ts(), json!({"content": "Explain the build"}))]
+        });
+        fs::write(&path, document.to_string()).unwrap();
+
+        let mut state = SessionState::default();
+        assert!(state.read_new(&GEMINI, &path));
+        assert!(!state.read_new(&GEMINI, &path));
+        assert_eq!(state.first_prompt.as_deref(), Some("Explain the build"));
+
+        document["messages"].as_array_mut().unwrap().push(gemini_message(
+            "gemini",
+            &now_ts(),
+            json!({"model": "gemini-2.5-pro", "tokens": {"input": 120, "cached": 20, "output": 30}}),
+        ));
+        fs::write(&path, document.to_string()).unwrap();
+        assert!(state.read_new(&GEMINI, &path));
+        assert_eq!(state.stats.turn_count, 1);
+        assert_eq!(state.stats.input_tokens, 100);
+        assert_eq!(state.stats.model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(state.classify(&GEMINI, true), SessionActivity::Idle);
+
+        let sessions = GEMINI.discover_sessions_in(&dir, SystemTime::now() - DISCOVERY_WINDOW);
+        assert_eq!(sessions.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gemini_cwd_is_found_from_project_hash() {
+        let mut state = SessionState {
+            project_hash: Some(project_hash("/work/app")),
+            ..SessionState::default()
+        };
+        let other = HashSet::from(["/work/other".to_string()]);
+        assert!(!state.is_process_running(&other));
+        assert_eq!(state.cwd, None);
+
+        let active = HashSet::from(["/work/other".to_string(), "/work/app".to_string()]);
+        assert!(state.is_process_running(&active));
+        assert_eq!(state.cwd.as_deref(), Some("/work/app"));
+        assert!(!state.is_process_running(&other));
+    }
+
+    #[test]
+    fn repository_urls_become_slugs() {
+        assert_eq!(repository_slug("git@github.com:me/app.git"), "me/app");
+        assert_eq!(repository_slug("https://github.com/me/app"), "me/app");
+        assert_eq!(repository_slug("https://gitlab.example.com/x/y.git"), "https://gitlab.example.com/x/y.git");
+        assert_eq!(rules_for("codex_session").map(|r| r.label), Some("Codex"));
+        assert!(rules_for("copilot_agent").is_none());
+    }
+}
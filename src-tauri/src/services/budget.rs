@@ -0,0 +1,219 @@
+use crate::db::{DailySpend, Item, Settings};
+
+/// Metadata keys owned by the budget check.
+pub const METADATA_KEYS: [&str; 2] = ["over_budget", "budget_reason"];
+
+/// Item types whose metadata reports token and cost usage.
+pub fn is_agent_session(item_type: &str) -> bool {
+    matches!(item_type, "opencode_session" | "copilot_agent" | "cli_session")
+        || super::agent_logs::rules_for(item_type).is_some()
+}
+
+/// The local day usage is attributed to, as `YYYY-MM-DD`.
+pub fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Token and cost thresholds for agent sessions; `None` means unlimited.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Budgets {
+    pub session_tokens: Option<u64>,
+    pub session_cost: Option<f64>,
+    pub daily_tokens: Option<u64>,
+    pub daily_cost: Option<f64>,
+}
+
+impl Budgets {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            session_tokens: u64::try_from(settings.budget_session_tokens).ok().filter(|t| *t > 0),
+            session_cost: Some(settings.budget_session_cost).filter(|c| *c > 0.0),
+            daily_tokens: u64::try_from(settings.budget_daily_tokens).ok().filter(|t| *t > 0),
+            daily_cost: Some(settings.budget_daily_cost).filter(|c| *c > 0.0),
+        }
+    }
+
+    /// Why a session is over its budget, if it is. Sub-agent usage counts
+    /// towards the parent session.
+    pub fn session_exceeded(&self, usage: &SessionUsage) -> Option<String> {
+        exceeded(
+            self.session_tokens,
+            self.session_cost,
+            usage.tokens + usage.children_tokens,
+            usage.cost + usage.children_cost,
+        )
+    }
+
+    /// Why a day's spend is over the daily budget, if it is.
+    pub fn daily_exceeded(&self, spend: &[DailySpend]) -> Option<String> {
+        let tokens = spend.iter().map(|s| s.tokens.max(0) as u64).sum();
+        let cost = spend.iter().map(|s| s.cost).sum();
+        exceeded(self.daily_tokens, self.daily_cost, tokens, cost)
+    }
+}
+
+fn exceeded(max_tokens: Option<u64>, max_cost: Option<f64>, tokens: u64, cost: f64) -> Option<String> {
+    if let Some(max) = max_cost.filter(|max| cost >= *max) {
+        return Some(format!("${:.2} spent, budget is ${:.2}", cost, max));
+    }
+    if let Some(max) = max_tokens.filter(|max| tokens >= *max) {
+        return Some(format!("{} tokens used, budget is {}", tokens, max));
+    }
+    None
+}
+
+/// Usage reported in an agent session item's metadata.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionUsage {
+    /// Repository when known, otherwise the working directory.
+    pub directory: String,
+    pub tokens: u64,
+    pub cost: f64,
+    pub children_tokens: u64,
+    pub children_cost: f64,
+}
+
+/// Read an agent session's usage. `None` for other items and for sessions
+/// that haven't reported any usage yet.
+pub fn session_usage(item: &Item) -> Option<SessionUsage> {
+    if !is_agent_session(&item.item_type) {
+        return None;
+    }
+    let metadata: serde_json::Value = serde_json::from_str(&item.metadata).ok()?;
+    let tokens = metadata["total_tokens"].as_u64();
+    let cost = metadata["total_cost"].as_f64();
+    if tokens.is_none() && cost.is_none() {
+        return None;
+    }
+
+    let directory = ["repository", "directory", "cwd"]
+        .iter()
+        .find_map(|key| metadata[*key].as_str().filter(|s| !s.is_empty()))
+        .unwrap_or("unknown")
+        .to_string();
+
+    Some(SessionUsage {
+        directory,
+        tokens: tokens.unwrap_or(0),
+        cost: cost.unwrap_or(0.0),
+        children_tokens: metadata["children_total_tokens"].as_u64().unwrap_or(0),
+        children_cost: metadata["children_total_cost"].as_f64().unwrap_or(0.0),
+    })
+}
+
+/// Set or clear the over-budget flag. Returns whether the metadata changed.
+pub fn set_flag(map: &mut serde_json::Map<String, serde_json::Value>, reason: Option<&str>) -> bool {
+    let flagged = map.get("over_budget").and_then(|v| v.as_bool()).unwrap_or(false);
+    match reason {
+        Some(reason) => {
+            let changed = !flagged || map.get("budget_reason").and_then(|v| v.as_str()) != Some(reason);
+            map.insert("over_budget".to_string(), serde_json::json!(true));
+            map.insert("budget_reason".to_string(), serde_json::json!(reason));
+            changed
+        }
+        None => {
+            let had_keys = METADATA_KEYS.iter().any(|key| map.contains_key(*key));
+            for key in METADATA_KEYS {
+                map.remove(key);
+            }
+            had_keys
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item_type: &str, metadata: serde_json::Value) -> Item {
+        Item {
+            id: "item-1".to_string(),
+            item_type: item_type.to_string(),
+            title: "Session".to_string(),
+            url: None,
+            status: "in_progress".to_string(),
+            previous_status: None,
+            metadata: metadata.to_string(),
+            last_checked_at: None,
+            last_updated_at: None,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        }
+    }
+
+    #[test]
+    fn sub_agent_usage_counts_towards_session_budget() {
+        let budgets = Budgets {
+            session_cost: Some(1.0),
+            ..Default::default()
+        };
+        let usage = SessionUsage {
+            tokens: 1_000_000,
+            cost: 0.4,
+            children_cost: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(budgets.session_exceeded(&usage), None);
+
+        let usage = SessionUsage { cost: 0.6, ..usage };
+        assert_eq!(
+            budgets.session_exceeded(&usage).as_deref(),
+            Some("$1.10 spent, budget is $1.00")
+        );
+    }
+
+    #[test]
+    fn daily_spend_is_summed_across_directories() {
+        let budgets = Budgets {
+            daily_tokens: Some(10_000),
+            ..Default::default()
+        };
+        let spend = |directory: &str, tokens| DailySpend {
+            directory: directory.to_string(),
+            sessions: 1,
+            tokens,
+            cost: 0.0,
+        };
+        assert_eq!(budgets.daily_exceeded(&[spend("/a", 6_000)]), None);
+        assert_eq!(
+            budgets.daily_exceeded(&[spend("/a", 6_000), spend("owner/repo", 4_000)]).as_deref(),
+            Some("10000 tokens used, budget is 10000")
+        );
+    }
+
+    #[test]
+    fn usage_is_read_from_session_metadata() {
+        let copilot = item(
+            "copilot_agent",
+            serde_json::json!({"cwd": "/home/me/repo", "repository": "me/repo", "total_tokens": 2400}),
+        );
+        let usage = session_usage(&copilot).unwrap();
+        assert_eq!(usage.directory, "me/repo");
+        assert_eq!(usage.tokens, 2400);
+
+        let opencode = item(
+            "opencode_session",
+            serde_json::json!({"directory": "/home/me/repo", "total_tokens": 100, "total_cost": 0.25, "children_total_tokens": 50}),
+        );
+        let usage = session_usage(&opencode).unwrap();
+        assert_eq!(usage.directory, "/home/me/repo");
+        assert_eq!(usage.children_tokens, 50);
+
+        assert!(session_usage(&item("opencode_session", serde_json::json!({"directory": "/x"}))).is_none());
+        assert!(session_usage(&item("github_pr", serde_json::json!({"total_tokens": 1}))).is_none());
+    }
+
+    #[test]
+    fn flag_changes_are_reported() {
+        let mut map = serde_json::Map::new();
+        assert!(!set_flag(&mut map, None));
+        assert!(set_flag(&mut map, Some("over")));
+        assert!(!set_flag(&mut map, Some("over")));
+        assert_eq!(map["over_budget"], true);
+        assert!(set_flag(&mut map, None));
+        assert!(map.is_empty());
+    }
+}
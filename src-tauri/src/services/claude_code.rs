@@ -0,0 +1,358 @@
+//! Claude Code transcripts, tracked by the `agent_logs::CLAUDE` rules. A
+//! transcript line is a whole message: a prompt, the results of several tool
+//! calls, or text followed by several tool calls. `split` turns each line
+//! into the events it carries, named by the kinds below.
+
+use serde_json::{json, Value};
+
+pub const PROMPT: &str = "prompt";
+pub const ASSISTANT: &str = "assistant";
+pub const TOOL_USE: &str = "tool_use";
+pub const TOOL_RESULT: &str = "tool_result";
+/// A tool call that asks the user something and blocks until answered.
+pub const QUESTION: &str = "question";
+pub const TURN_END: &str = "turn_end";
+
+/// Assistant text without a stop reason is only taken as the end of the
+/// turn once nothing followed it for this long: streamed messages are
+/// written one content block per line.
+pub const TURN_SETTLE_SECS: i64 = 10;
+
+/// Tools that run sub-agents and are expected to take a long time.
+pub const LONG_RUNNING_TOOLS: &[&str] = &["Task", "Agent"];
+
+/// Tools that ask the user something and block until answered.
+const USER_INPUT_TOOLS: &[&str] = &["AskUserQuestion", "ExitPlanMode"];
+
+/// Tools whose `file_path` argument is a file the agent modified.
+const FILE_EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// The events a transcript line carries, each with the line's timestamp,
+/// cwd and branch.
+pub fn split(line: &Value) -> Vec<Value> {
+    // Sub-agent lines are interleaved in older transcripts
+    if line["isSidechain"].as_bool() == Some(true) {
+        return vec![];
+    }
+    let event = |kind: &str, fields: Value| {
+        let mut event = json!({
+            "type": kind,
+            "timestamp": line["timestamp"],
+            "cwd": line["cwd"],
+            "branch": line["gitBranch"],
+        });
+        if let (Some(event), Value::Object(fields)) = (event.as_object_mut(), fields) {
+            event.extend(fields);
+        }
+        event
+    };
+
+    match line["type"].as_str() {
+        Some("user") => split_user(line, event),
+        Some("assistant") => split_assistant(&line["message"], event),
+        _ => vec![],
+    }
+}
+
+fn split_user(line: &Value, event: impl Fn(&str, Value) -> Value) -> Vec<Value> {
+    let content = &line["message"]["content"];
+    let results: Vec<Value> = content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|p| p["type"] == "tool_result")
+        .filter_map(|p| p["tool_use_id"].as_str())
+        .map(|id| event(TOOL_RESULT, json!({ "call_id": id })))
+        .collect();
+    if !results.is_empty() {
+        return results;
+    }
+
+    if parts_text(content).starts_with("[Request interrupted by user") {
+        return vec![event(TURN_END, json!({}))];
+    }
+    match prompt_text(line) {
+        Some(text) => vec![event(PROMPT, json!({ "text": text }))],
+        None => vec![],
+    }
+}
+
+fn split_assistant(message: &Value, event: impl Fn(&str, Value) -> Value) -> Vec<Value> {
+    let mut assistant = json!({ "message_id": message["id"] });
+    if let Some(model) = message["model"].as_str().filter(|m| !m.starts_with('<')) {
+        assistant["model"] = json!(model);
+    }
+    // Cache reads are context re-sent on every request; counting them would
+    // make long sessions look far more expensive than they are.
+    let usage = &message["usage"];
+    if usage.is_object() {
+        let tokens = |key: &str| usage[key].as_u64().unwrap_or(0);
+        assistant["input_tokens"] = json!(tokens("input_tokens") + tokens("cache_creation_input_tokens"));
+        assistant["output_tokens"] = json!(tokens("output_tokens"));
+    }
+
+    let mut events = vec![event(ASSISTANT, assistant)];
+    for part in message["content"].as_array().into_iter().flatten() {
+        if part["type"] != "tool_use" {
+            continue;
+        }
+        let name = part["name"].as_str().unwrap_or("");
+        let mut tool = json!({ "call_id": part["id"], "name": name });
+        if FILE_EDIT_TOOLS.contains(&name) {
+            tool["file_path"] = part["input"]["file_path"].clone();
+        }
+        events.push(event(TOOL_USE, tool));
+        if USER_INPUT_TOOLS.contains(&name) {
+            events.push(event(QUESTION, json!({})));
+        }
+    }
+
+    match message["stop_reason"].as_str() {
+        None | Some("tool_use") => {}
+        Some(_) => events.push(event(TURN_END, json!({}))),
+    }
+    events
+}
+
+/// The text of a prompt typed by the user. Tool results, injected
+/// meta messages and slash-command wrappers are not prompts.
+fn prompt_text(line: &Value) -> Option<String> {
+    if line["isMeta"].as_bool() == Some(true) {
+        return None;
+    }
+    let content = &line["message"]["content"];
+    if content.as_array().is_some_and(|parts| parts.iter().any(|p| p["type"] == "tool_result")) {
+        return None;
+    }
+    let text = parts_text(content);
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('<') || text.starts_with("Caveat:") {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+fn parts_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::agent_logs::{SessionState, CLAUDE};
+    use crate::services::copilot_cli::SessionActivity;
+
+    fn now_ts() -> String {
+        chrono::Utc::now().to_rfc3339()
+    }
+
+    /// Create a timestamp N seconds in the past.
+    fn past_ts(seconds_ago: i64) -> String {
+        (chrono::Utc::now() - chrono::Duration::seconds(seconds_ago)).to_rfc3339()
+    }
+
+    fn classify_events(events: &[Value], process_running: bool) -> SessionActivity {
+        let mut state = SessionState::default();
+        for event in events {
+            state.apply(&CLAUDE, event);
+        }
+        state.classify(&CLAUDE, process_running)
+    }
+
+    fn prompt(text: &str, ts: &str) -> Value {
+        json!({
+            "type": "user",
+            "cwd": "/work/app",
+            "gitBranch": "main",
+            "timestamp": ts,
+            "message": {"role": "user", "content": text}
+        })
+    }
+
+    fn assistant_text(text: &str, stop_reason: Option<&str>, ts: &str) -> Value {
+        json!({
+            "type": "assistant",
+            "timestamp": ts,
+            "message": {
+                "id": "msg_text",
+                "model": "claude-sonnet-4-5",
+                "content": [{"type": "text", "text": text}],
+                "stop_reason": stop_reason,
+                "usage": {"input_tokens": 10, "output_tokens": 5}
+            }
+        })
+    }
+
+    fn tool_use(id: &str, name: &str, ts: &str) -> Value {
+        json!({
+            "type": "assistant",
+            "timestamp": ts,
+            "message": {
+                "id": format!("msg_{}", id),
+                "content": [{"type": "tool_use", "id": id, "name": name, "input": {"file_path": "src/lib.rs"}}],
+                "stop_reason": "tool_use"
+            }
+        })
+    }
+
+    fn tool_result(id: &str, ts: &str) -> Value {
+        json!({
+            "type": "user",
+            "timestamp": ts,
+            "message": {"role": "user", "content": [{"type": "tool_result", "tool_use_id": id, "content": "ok"}]}
+        })
+    }
+
+    #[test]
+    fn fresh_prompt_is_in_progress() {
+        let events = vec![prompt("Fix the build", &now_ts())];
+        assert_eq!(classify_events(&events, true), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn stale_prompt_is_idle() {
+        let events = vec![prompt("Fix the build", &past_ts(300))];
+        assert_eq!(classify_events(&events, false), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn end_turn_is_idle() {
+        let events = vec![
+            prompt("Fix the build", &now_ts()),
+            assistant_text("Done.", Some("end_turn"), &now_ts()),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn streamed_text_settles_into_idle() {
+        let fresh = vec![
+            prompt("Fix the build", &now_ts()),
+            assistant_text("Looking at it", None, &now_ts()),
+        ];
+        assert_eq!(classify_events(&fresh, true), SessionActivity::InProgress);
+
+        let settled = vec![
+            prompt("Fix the build", &past_ts(40)),
+            assistant_text("All fixed", None, &past_ts(30)),
+        ];
+        assert_eq!(classify_events(&settled, true), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn running_tool_is_in_progress() {
+        let events = vec![
+            prompt("Fix the build", &now_ts()),
+            tool_use("t1", "Bash", &now_ts()),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn long_pending_tool_is_permission_prompt() {
+        let events = vec![
+            prompt("Fix the build", &past_ts(100)),
+            tool_use("t1", "Bash", &past_ts(90)),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::InputNeeded);
+    }
+
+    #[test]
+    fn long_running_subagent_is_in_progress() {
+        let events = vec![
+            prompt("Review the repo", &past_ts(400)),
+            tool_use("t1", "Task", &past_ts(300)),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn ask_user_question_is_input_needed() {
+        let events = vec![
+            prompt("Plan the refactor", &now_ts()),
+            tool_use("t1", "AskUserQuestion", &now_ts()),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::InputNeeded);
+
+        let mut answered = events.clone();
+        answered.push(tool_result("t1", &now_ts()));
+        assert_eq!(classify_events(&answered, true), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn tool_result_resumes_work() {
+        let events = vec![
+            prompt("Fix the build", &now_ts()),
+            tool_use("t1", "Edit", &now_ts()),
+            tool_result("t1", &now_ts()),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::InProgress);
+    }
+
+    #[test]
+    fn interrupt_is_idle() {
+        let events = vec![
+            prompt("Fix the build", &now_ts()),
+            tool_use("t1", "Bash", &now_ts()),
+            json!({
+                "type": "user",
+                "timestamp": now_ts(),
+                "message": {"role": "user", "content": [{"type": "text", "text": "[Request interrupted by user for tool use]"}]}
+            }),
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn sidechain_lines_are_ignored() {
+        let mut sidechain = tool_use("s1", "Bash", &now_ts());
+        sidechain["isSidechain"] = json!(true);
+        let events = vec![
+            prompt("Fix the build", &now_ts()),
+            assistant_text("Done.", Some("end_turn"), &now_ts()),
+            sidechain,
+        ];
+        assert_eq!(classify_events(&events, true), SessionActivity::Idle);
+    }
+
+    #[test]
+    fn stats_are_accumulated_from_transcript() {
+        let mut state = SessionState::default();
+        let mut streamed = assistant_text("Working", None, &now_ts());
+        for event in [
+            prompt("<command-name>/clear</command-name>", &now_ts()),
+            prompt("Fix the build", &now_ts()),
+            streamed.clone(),
+            {
+                // The same message again with its final usage
+                streamed["message"]["usage"] = json!({"input_tokens": 10, "cache_creation_input_tokens": 90, "cache_read_input_tokens": 5000, "output_tokens": 50});
+                streamed
+            },
+            tool_use("t1", "Edit", &now_ts()),
+            tool_use("t2", "Edit", &now_ts()),
+            prompt("And the tests", &now_ts()),
+        ] {
+            state.apply(&CLAUDE, &event);
+        }
+
+        assert_eq!(state.cwd.as_deref(), Some("/work/app"));
+        assert_eq!(state.branch.as_deref(), Some("main"));
+        assert_eq!(state.first_prompt.as_deref(), Some("Fix the build"));
+        assert_eq!(state.last_prompt.as_deref(), Some("And the tests"));
+        let stats = &state.stats;
+        assert_eq!(stats.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(stats.input_tokens, 100);
+        assert_eq!(stats.output_tokens, 50);
+        assert_eq!(stats.tool_calls.get("Edit"), Some(&2));
+        assert_eq!(stats.files_edited, vec!["src/lib.rs".to_string()]);
+        assert_eq!(stats.turn_count, 2);
+    }
+}
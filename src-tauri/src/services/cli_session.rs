@@ -0,0 +1,188 @@
+/// Seconds without a heartbeat after which a wrapped command is considered lost.
+/// The `loop-track` wrapper sends a heartbeat every 15 seconds.
+pub const HEARTBEAT_TIMEOUT_SECS: i64 = 90;
+
+/// Default minimum runtime before a command reported by the shell hooks
+/// becomes a tracked `cli_session` item.
+pub const DEFAULT_SHELL_TRACK_THRESHOLD_SECS: i64 = 30;
+
+/// Default programs never auto-tracked by the shell hooks: editors, pagers,
+/// interactive clients and terminal multiplexers.
+pub const DEFAULT_SHELL_TRACK_IGNORE: &str = "vim,nvim,vi,nano,emacs,hx,less,more,man,top,htop,btop,watch,ssh,mosh,tmux,screen,ipython,irb,pry,psql,mysql,sqlite3,redis-cli,fg,loop-track";
+
+/// Interpreters that start a REPL when run without arguments.
+const REPL_PROGRAMS: &[&str] = &["python", "python3", "node", "ruby", "bash", "zsh", "fish", "sh"];
+
+/// Prefixes that wrap the actual program being run.
+const COMMAND_WRAPPERS: &[&str] = &["sudo", "time", "command", "exec", "nohup", "env", "noglob"];
+
+/// Why a CLI session was marked as lost.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LostReason {
+    /// The wrapper process no longer exists.
+    ProcessGone,
+    /// The wrapper stopped sending heartbeats.
+    HeartbeatTimeout,
+}
+
+impl LostReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LostReason::ProcessGone => "process_gone",
+            LostReason::HeartbeatTimeout => "heartbeat_timeout",
+        }
+    }
+}
+
+/// Check whether a process with the given PID exists on this host. A
+/// process of another user can't be signalled but still exists.
+pub fn is_process_alive(pid: u32) -> bool {
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(p) if p > 0 => p,
+        _ => return false,
+    };
+    // SAFETY: signal 0 sends nothing, it only checks the pid
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Decide whether an in-progress CLI session has been orphaned.
+///
+/// Sessions created by older wrappers carry neither a `pid` nor heartbeats;
+/// those are never marked lost.
+pub fn detect_lost_session(
+    metadata: &serde_json::Value,
+    now: chrono::DateTime<chrono::Utc>,
+    process_alive: impl Fn(u32) -> bool,
+) -> Option<LostReason> {
+    if let Some(pid) = metadata["pid"].as_u64() {
+        if !process_alive(pid as u32) {
+            return Some(LostReason::ProcessGone);
+        }
+    }
+
+    let last_heartbeat = metadata["last_heartbeat_at"]
+        .as_str()
+        .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())?;
+
+    if now.signed_duration_since(last_heartbeat).num_seconds() > HEARTBEAT_TIMEOUT_SECS {
+        Some(LostReason::HeartbeatTimeout)
+    } else {
+        None
+    }
+}
+
+/// Extract the program name from a shell command line, skipping
+/// environment assignments and wrappers like `sudo` or `time`.
+/// Returns the program and whether it was given any arguments.
+fn command_program(command: &str) -> Option<(String, bool)> {
+    let mut words = command.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        let is_assignment = word
+            .split_once('=')
+            .map(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(false);
+        if is_assignment || COMMAND_WRAPPERS.contains(&word) {
+            continue;
+        }
+        let program = word.rsplit('/').next().unwrap_or(word).to_string();
+        let has_args = words.peek().is_some();
+        return Some((program, has_args));
+    }
+    None
+}
+
+/// Whether a command reported by the shell hooks should be skipped.
+/// `ignore_list` is a comma-separated list of program names.
+pub fn is_ignored_command(command: &str, ignore_list: &str) -> bool {
+    let (program, has_args) = match command_program(command) {
+        Some(p) => p,
+        None => return true,
+    };
+
+    if !has_args && REPL_PROGRAMS.contains(&program.as_str()) {
+        return true;
+    }
+
+    ignore_list
+        .split(',')
+        .map(|entry| entry.trim())
+        .any(|entry| !entry.is_empty() && entry == program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ts(seconds_ago: i64) -> String {
+        (chrono::Utc::now() - chrono::Duration::seconds(seconds_ago)).to_rfc3339()
+    }
+
+    #[test]
+    fn legacy_session_is_never_lost() {
+        let meta = json!({"command": "npm test"});
+        assert_eq!(detect_lost_session(&meta, chrono::Utc::now(), |_| false), None);
+    }
+
+    #[test]
+    fn dead_pid_is_lost() {
+        let meta = json!({"pid": 4242, "last_heartbeat_at": ts(5)});
+        assert_eq!(
+            detect_lost_session(&meta, chrono::Utc::now(), |_| false),
+            Some(LostReason::ProcessGone)
+        );
+    }
+
+    #[test]
+    fn stale_heartbeat_is_lost_even_if_pid_exists() {
+        let meta = json!({"pid": 4242, "last_heartbeat_at": ts(120)});
+        assert_eq!(
+            detect_lost_session(&meta, chrono::Utc::now(), |_| true),
+            Some(LostReason::HeartbeatTimeout)
+        );
+    }
+
+    #[test]
+    fn fresh_heartbeat_and_live_pid_is_alive() {
+        let meta = json!({"pid": 4242, "last_heartbeat_at": ts(10)});
+        assert_eq!(detect_lost_session(&meta, chrono::Utc::now(), |_| true), None);
+    }
+
+    #[test]
+    fn editors_and_ssh_are_ignored() {
+        assert!(is_ignored_command("vim src/main.rs", DEFAULT_SHELL_TRACK_IGNORE));
+        assert!(is_ignored_command("ssh build-box", DEFAULT_SHELL_TRACK_IGNORE));
+        assert!(is_ignored_command("/usr/bin/less log.txt", DEFAULT_SHELL_TRACK_IGNORE));
+    }
+
+    #[test]
+    fn wrappers_and_env_assignments_are_skipped() {
+        assert!(is_ignored_command("sudo vim /etc/hosts", DEFAULT_SHELL_TRACK_IGNORE));
+        assert!(is_ignored_command("EDITOR=nvim RUST_LOG=debug nvim", DEFAULT_SHELL_TRACK_IGNORE));
+        assert!(!is_ignored_command("RUST_LOG=debug cargo test", DEFAULT_SHELL_TRACK_IGNORE));
+    }
+
+    #[test]
+    fn bare_interpreter_is_a_repl_but_scripts_are_tracked() {
+        assert!(is_ignored_command("python3", DEFAULT_SHELL_TRACK_IGNORE));
+        assert!(!is_ignored_command("python3 manage.py test", DEFAULT_SHELL_TRACK_IGNORE));
+    }
+
+    #[test]
+    fn custom_ignore_list_is_respected() {
+        assert!(!is_ignored_command("npm test", DEFAULT_SHELL_TRACK_IGNORE));
+        assert!(is_ignored_command("npm test", "make, npm"));
+        assert!(is_ignored_command("   ", ""));
+    }
+
+    #[test]
+    fn current_process_is_alive() {
+        assert!(is_process_alive(std::process::id()));
+        // kill(0, 0) would check our own process group
+        assert!(!is_process_alive(0));
+        assert!(!is_process_alive(u32::MAX));
+    }
+}
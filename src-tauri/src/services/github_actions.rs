@@ -43,6 +43,19 @@ pub async fn check_github_action(
     Ok(result)
 }
 
+/// Item status for a workflow run's `status` and `conclusion`.
+pub fn run_status(status: &str, conclusion: Option<&str>) -> &'static str {
+    match status {
+        "queued" | "waiting" => "waiting",
+        "in_progress" => "in_progress",
+        "completed" => match conclusion {
+            Some("failure") | Some("cancelled") => "failed",
+            _ => "completed",
+        },
+        _ => "waiting",
+    }
+}
+
 async fn fetch_workflow_run_via_http(
     token: &str,
     owner: &str,
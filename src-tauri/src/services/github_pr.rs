@@ -12,6 +12,34 @@ struct PullRequest {
     merged: bool,
     draft: bool,
     updated_at: String,
+    #[serde(default)]
+    head: Option<Head>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Head {
+    #[serde(rename = "ref")]
+    branch: String,
+    sha: String,
+}
+
+/// An open pull request found for a branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchPr {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRuns {
+    check_runs: Vec<CheckRun>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRun {
+    status: String,
+    conclusion: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,9 +83,124 @@ pub async fn check_github_pr(
     result.insert("has_approval".to_string(), serde_json::json!(has_approval));
     result.insert("has_changes_requested".to_string(), serde_json::json!(has_changes_requested));
 
+    if let Some(head) = &pr.head {
+        result.insert("head_branch".to_string(), serde_json::json!(head.branch));
+        // CI status is best effort; the PR is still tracked without it
+        if let Ok(runs) = fetch_json::<CheckRuns>(token, &format!("repos/{}/{}/commits/{}/check-runs", owner, repo, head.sha)).await {
+            if let Some(ci_status) = summarize_check_runs(&runs.check_runs) {
+                result.insert("ci_status".to_string(), serde_json::json!(ci_status));
+            }
+        }
+    }
+
     Ok(result)
 }
 
+/// Item status for a PR's state and reviews. `new_reviews` is whether
+/// reviews arrived since the last check.
+pub fn pr_status(
+    state: &str,
+    merged: bool,
+    has_approval: bool,
+    has_changes_requested: bool,
+    new_reviews: bool,
+) -> &'static str {
+    if merged {
+        "merged"
+    } else if state == "closed" {
+        "completed"
+    } else if has_approval {
+        "approved"
+    } else if new_reviews || has_changes_requested {
+        "updated"
+    } else {
+        "in_progress"
+    }
+}
+
+/// The open pull request whose head is `branch` in `owner/repo`, if any.
+pub async fn find_open_pr(token: &str, owner: &str, repo: &str, branch: &str) -> Result<Option<BranchPr>> {
+    let endpoint = format!(
+        "repos/{}/{}/pulls?state=open&head={}",
+        owner,
+        repo,
+        urlencoding::encode(&format!("{}:{}", owner, branch))
+    );
+    let prs: Vec<BranchPr> = fetch_json(token, &endpoint).await?;
+    Ok(prs.into_iter().next())
+}
+
+/// CI state of a single check suite, as `summarize_check_runs` names it.
+pub fn check_suite_status(status: &str, conclusion: Option<&str>) -> &'static str {
+    let suite = CheckRun {
+        status: status.to_string(),
+        conclusion: conclusion.map(str::to_string),
+    };
+    summarize_check_runs(&[suite]).unwrap_or("running")
+}
+
+/// Whether a PR's CI turned failed between the `ci_status` it had and the
+/// one it has now.
+pub fn ci_newly_failed(old: Option<&serde_json::Value>, new: Option<&serde_json::Value>) -> bool {
+    old != new && new.and_then(|s| s.as_str()) == Some("failed")
+}
+
+/// Overall CI state of a commit's check runs: `running` while any run is
+/// unfinished, then `failed` if any run failed, otherwise `passed`.
+fn summarize_check_runs(runs: &[CheckRun]) -> Option<&'static str> {
+    if runs.is_empty() {
+        return None;
+    }
+    if runs.iter().any(|r| r.status != "completed") {
+        return Some("running");
+    }
+    let failed = runs.iter().any(|r| {
+        matches!(
+            r.conclusion.as_deref(),
+            Some("failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure")
+        )
+    });
+    Some(if failed { "failed" } else { "passed" })
+}
+
+/// GET a GitHub API endpoint (relative to `https://api.github.com/`),
+/// falling back to the gh CLI like PR polling does.
+async fn fetch_json<T: serde::de::DeserializeOwned>(token: &str, endpoint: &str) -> Result<T> {
+    let http_err = if token.trim().is_empty() {
+        anyhow::anyhow!("GitHub token not configured")
+    } else {
+        let response = reqwest::Client::new()
+            .get(format!("https://api.github.com/{}", endpoint))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("User-Agent", "in-the-loop-app")
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .send()
+            .await;
+        match response {
+            Ok(r) if r.status().is_success() => return Ok(r.json().await?),
+            Ok(r) => anyhow::anyhow!("GitHub API error: {}", r.status()),
+            Err(e) => anyhow::anyhow!(e),
+        }
+    };
+
+    let endpoint_owned = endpoint.to_string();
+    let token_owned = token.to_string();
+    let body = task::spawn_blocking(move || {
+        let with_token = run_gh_api(&endpoint_owned, (!token_owned.trim().is_empty()).then_some(token_owned.as_str()))?;
+        match with_token {
+            Ok(body) => Ok(Ok(body)),
+            Err(_) => run_gh_api(&endpoint_owned, None),
+        }
+    })
+    .await??;
+
+    match body {
+        Ok(body) => Ok(serde_json::from_str(&body)?),
+        Err(gh_err) => Err(anyhow::anyhow!("http: {} | gh: {}", http_err, gh_err)),
+    }
+}
+
 async fn fetch_pr_via_http(
     token: &str,
     owner: &str,
@@ -217,3 +360,52 @@ fn run_gh_api(endpoint: &str, token: Option<&str>) -> Result<std::result::Result
         Ok(Err(String::from_utf8_lossy(&output.stderr).trim().to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(status: &str, conclusion: Option<&str>) -> CheckRun {
+        CheckRun {
+            status: status.to_string(),
+            conclusion: conclusion.map(|c| c.to_string()),
+        }
+    }
+
+    #[test]
+    fn check_runs_are_summarized() {
+        assert_eq!(summarize_check_runs(&[]), None);
+        assert_eq!(
+            summarize_check_runs(&[run("completed", Some("failure")), run("in_progress", None)]),
+            Some("running")
+        );
+        assert_eq!(
+            summarize_check_runs(&[run("completed", Some("success")), run("completed", Some("failure"))]),
+            Some("failed")
+        );
+        assert_eq!(
+            summarize_check_runs(&[run("completed", Some("success")), run("completed", Some("skipped"))]),
+            Some("passed")
+        );
+    }
+
+    #[test]
+    fn only_a_change_to_failed_is_a_ci_failure() {
+        let failed = serde_json::json!("failed");
+        let running = serde_json::json!("running");
+        assert!(ci_newly_failed(Some(&running), Some(&failed)));
+        assert!(ci_newly_failed(None, Some(&failed)));
+        assert!(!ci_newly_failed(Some(&failed), Some(&failed)));
+        assert!(!ci_newly_failed(Some(&failed), Some(&running)));
+    }
+
+    #[test]
+    fn pull_request_head_is_optional() {
+        let pr: PullRequest = serde_json::from_str(
+            r#"{"number": 1, "title": "t", "state": "open", "merged": false, "draft": false, "updated_at": "x",
+                "head": {"ref": "fix-login", "sha": "abc"}}"#,
+        )
+        .unwrap();
+        assert_eq!(pr.head.unwrap().branch, "fix-login");
+    }
+}
@@ -0,0 +1,360 @@
+//! GitHub webhook deliveries, turned into updates of the `github_pr` and
+//! `github_action` items they concern. Polling stays the fallback for events
+//! that never arrive.
+
+use crate::db::Item;
+use crate::services::{github_actions, github_pr, url_parser};
+use serde_json::Value;
+
+/// Header naming the event type.
+pub const EVENT_HEADER: &str = "X-GitHub-Event";
+
+/// Header carrying the `sha256=<hex>` HMAC of the body.
+pub const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+
+/// The parts of a webhook payload items are updated from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GithubEvent {
+    PullRequest {
+        repo: Repo,
+        number: u64,
+        title: String,
+        state: String,
+        merged: bool,
+        draft: bool,
+        updated_at: Option<String>,
+        head_branch: Option<String>,
+    },
+    Review {
+        repo: Repo,
+        number: u64,
+        /// `approved`, `changes_requested`, `commented` or `dismissed`.
+        state: String,
+    },
+    WorkflowRun {
+        repo: Repo,
+        run_id: u64,
+        name: String,
+        status: String,
+        conclusion: Option<String>,
+        updated_at: Option<String>,
+    },
+    CheckSuite {
+        repo: Repo,
+        head_branch: Option<String>,
+        pull_requests: Vec<u64>,
+        ci_status: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repo {
+    pub owner: String,
+    pub name: String,
+}
+
+impl Repo {
+    fn is(&self, owner: &str, name: &str) -> bool {
+        self.owner.eq_ignore_ascii_case(owner) && self.name.eq_ignore_ascii_case(name)
+    }
+}
+
+/// What an event changes on an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemUpdate {
+    pub status: String,
+    pub metadata: Value,
+    pub title: Option<String>,
+}
+
+/// Read the event named by the `X-GitHub-Event` header. `None` for events
+/// that don't concern tracked items, like `ping`.
+pub fn parse_event(event: &str, payload: &Value) -> Option<GithubEvent> {
+    let repo = Repo {
+        owner: payload["repository"]["owner"]["login"].as_str()?.to_string(),
+        name: payload["repository"]["name"].as_str()?.to_string(),
+    };
+    let string = |value: &Value| value.as_str().map(str::to_string);
+
+    match event {
+        "pull_request" => {
+            let pr = &payload["pull_request"];
+            Some(GithubEvent::PullRequest {
+                repo,
+                number: pr["number"].as_u64()?,
+                title: string(&pr["title"]).unwrap_or_default(),
+                state: string(&pr["state"])?,
+                merged: pr["merged"].as_bool().unwrap_or(false),
+                draft: pr["draft"].as_bool().unwrap_or(false),
+                updated_at: string(&pr["updated_at"]),
+                head_branch: string(&pr["head"]["ref"]),
+            })
+        }
+        "pull_request_review" if payload["action"] == "submitted" => Some(GithubEvent::Review {
+            repo,
+            number: payload["pull_request"]["number"].as_u64()?,
+            state: payload["review"]["state"].as_str()?.to_lowercase(),
+        }),
+        "workflow_run" => {
+            let run = &payload["workflow_run"];
+            Some(GithubEvent::WorkflowRun {
+                repo,
+                run_id: run["id"].as_u64()?,
+                name: string(&run["name"]).unwrap_or_default(),
+                status: string(&run["status"])?,
+                conclusion: string(&run["conclusion"]),
+                updated_at: string(&run["updated_at"]),
+            })
+        }
+        "check_suite" => {
+            let suite = &payload["check_suite"];
+            Some(GithubEvent::CheckSuite {
+                repo,
+                head_branch: string(&suite["head_branch"]),
+                pull_requests: suite["pull_requests"]
+                    .as_array()
+                    .map(|prs| prs.iter().filter_map(|pr| pr["number"].as_u64()).collect())
+                    .unwrap_or_default(),
+                ci_status: github_pr::check_suite_status(suite["status"].as_str()?, suite["conclusion"].as_str()),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// How `event` changes `item`, or `None` when it is about something else.
+pub fn apply(event: &GithubEvent, item: &Item) -> Option<ItemUpdate> {
+    let mut metadata: Value = serde_json::from_str(&item.metadata).unwrap_or_else(|_| serde_json::json!({}));
+    if !metadata.is_object() {
+        metadata = serde_json::json!({});
+    }
+    let field = |key: &str| item_field(item, &metadata, key);
+    let (owner, repo) = (field("owner")?, field("repo")?);
+
+    match (event, item.item_type.as_str()) {
+        (
+            GithubEvent::PullRequest { repo: r, number, title, state, merged, draft, updated_at, head_branch },
+            "github_pr",
+        ) if r.is(&owner, &repo) && field("pr_number")? == number.to_string() => {
+            let status = github_pr::pr_status(
+                state,
+                *merged,
+                metadata["has_approval"].as_bool().unwrap_or(false),
+                metadata["has_changes_requested"].as_bool().unwrap_or(false),
+                false,
+            )
+            .to_string();
+            set_identity(&mut metadata, &owner, &repo, "pr_number", &number.to_string());
+            let map = metadata.as_object_mut()?;
+            map.insert("state".to_string(), serde_json::json!(state));
+            map.insert("merged".to_string(), serde_json::json!(merged));
+            map.insert("draft".to_string(), serde_json::json!(draft));
+            if let Some(updated_at) = updated_at {
+                map.insert("updated_at".to_string(), serde_json::json!(updated_at));
+            }
+            if let Some(branch) = head_branch {
+                map.insert("head_branch".to_string(), serde_json::json!(branch));
+            }
+            let title = Some(title.clone()).filter(|t| !t.is_empty() && *t != item.title);
+            Some(ItemUpdate { status, metadata, title })
+        }
+        (GithubEvent::Review { repo: r, number, state }, "github_pr")
+            if r.is(&owner, &repo) && field("pr_number")? == number.to_string() =>
+        {
+            let has_approval = metadata["has_approval"].as_bool().unwrap_or(false) || state == "approved";
+            let has_changes_requested =
+                metadata["has_changes_requested"].as_bool().unwrap_or(false) || state == "changes_requested";
+            let review_count = metadata["review_count"].as_i64().unwrap_or(0) + 1;
+            let status = github_pr::pr_status(
+                metadata["state"].as_str().unwrap_or("open"),
+                metadata["merged"].as_bool().unwrap_or(false),
+                has_approval,
+                has_changes_requested,
+                true,
+            )
+            .to_string();
+            set_identity(&mut metadata, &owner, &repo, "pr_number", &number.to_string());
+            let map = metadata.as_object_mut()?;
+            map.insert("review_count".to_string(), serde_json::json!(review_count));
+            map.insert("has_approval".to_string(), serde_json::json!(has_approval));
+            map.insert("has_changes_requested".to_string(), serde_json::json!(has_changes_requested));
+            Some(ItemUpdate { status, metadata, title: None })
+        }
+        (GithubEvent::WorkflowRun { repo: r, run_id, name, status, conclusion, updated_at }, "github_action")
+            if r.is(&owner, &repo) && field("run_id")? == run_id.to_string() =>
+        {
+            let new_status = github_actions::run_status(status, conclusion.as_deref()).to_string();
+            set_identity(&mut metadata, &owner, &repo, "run_id", &run_id.to_string());
+            let map = metadata.as_object_mut()?;
+            map.insert("status".to_string(), serde_json::json!(status));
+            map.insert("conclusion".to_string(), serde_json::json!(conclusion));
+            map.insert("name".to_string(), serde_json::json!(name));
+            if let Some(updated_at) = updated_at {
+                map.insert("updated_at".to_string(), serde_json::json!(updated_at));
+            }
+            Some(ItemUpdate { status: new_status, metadata, title: None })
+        }
+        (GithubEvent::CheckSuite { repo: r, head_branch, pull_requests, ci_status }, "github_pr")
+            if r.is(&owner, &repo) =>
+        {
+            let number = field("pr_number")?;
+            let for_pr = pull_requests.iter().any(|n| n.to_string() == number)
+                || (head_branch.is_some() && metadata["head_branch"].as_str() == head_branch.as_deref());
+            if !for_pr {
+                return None;
+            }
+            let status = item.status.clone();
+            metadata.as_object_mut()?.insert("ci_status".to_string(), serde_json::json!(ci_status));
+            Some(ItemUpdate { status, metadata, title: None })
+        }
+        _ => None,
+    }
+}
+
+/// An identifier from the item's metadata, falling back to its URL.
+fn item_field(item: &Item, metadata: &Value, key: &str) -> Option<String> {
+    if let Some(value) = metadata[key].as_str() {
+        return Some(value.to_string());
+    }
+    let parsed = url_parser::parse_url(item.url.as_deref()?).ok()?;
+    parsed.metadata.get(key).cloned()
+}
+
+fn set_identity(metadata: &mut Value, owner: &str, repo: &str, id_key: &str, id: &str) {
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert("owner".to_string(), serde_json::json!(owner));
+        map.insert("repo".to_string(), serde_json::json!(repo));
+        map.insert(id_key.to_string(), serde_json::json!(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item_type: &str, url: &str, status: &str, metadata: Value) -> Item {
+        Item {
+            id: "item-1".to_string(),
+            item_type: item_type.to_string(),
+            title: "Fix login".to_string(),
+            url: Some(url.to_string()),
+            status: status.to_string(),
+            previous_status: None,
+            metadata: metadata.to_string(),
+            last_checked_at: None,
+            last_updated_at: None,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        }
+    }
+
+    fn repository() -> Value {
+        serde_json::json!({"name": "app", "owner": {"login": "Me"}})
+    }
+
+    fn pr_item() -> Item {
+        item(
+            "github_pr",
+            "https://github.com/me/app/pull/7",
+            "in_progress",
+            serde_json::json!({"state": "open", "review_count": 0, "head_branch": "fix-login"}),
+        )
+    }
+
+    #[test]
+    fn pull_request_events_update_state_and_title() {
+        let payload = serde_json::json!({
+            "action": "closed",
+            "repository": repository(),
+            "pull_request": {
+                "number": 7, "title": "Fix login redirect", "state": "closed", "merged": true,
+                "draft": false, "updated_at": "2026-01-02T00:00:00Z", "head": {"ref": "fix-login"}
+            }
+        });
+        let event = parse_event("pull_request", &payload).unwrap();
+
+        let update = apply(&event, &pr_item()).unwrap();
+        assert_eq!(update.status, "merged");
+        assert_eq!(update.title.as_deref(), Some("Fix login redirect"));
+        assert_eq!(update.metadata["merged"], true);
+        assert_eq!(update.metadata["pr_number"], "7");
+
+        let other = item("github_pr", "https://github.com/me/app/pull/8", "in_progress", serde_json::json!({}));
+        assert_eq!(apply(&event, &other), None);
+        let action = item("github_action", "https://github.com/me/app/actions/runs/7", "waiting", serde_json::json!({}));
+        assert_eq!(apply(&event, &action), None);
+    }
+
+    #[test]
+    fn reviews_approve_or_update_prs() {
+        let review = |state: &str| {
+            let payload = serde_json::json!({
+                "action": "submitted",
+                "repository": repository(),
+                "review": {"state": state},
+                "pull_request": {"number": 7}
+            });
+            parse_event("pull_request_review", &payload).unwrap()
+        };
+
+        let approved = apply(&review("APPROVED"), &pr_item()).unwrap();
+        assert_eq!(approved.status, "approved");
+        assert_eq!(approved.metadata["review_count"], 1);
+
+        assert_eq!(apply(&review("commented"), &pr_item()).unwrap().status, "updated");
+
+        let edited = serde_json::json!({"action": "edited", "repository": repository()});
+        assert_eq!(parse_event("pull_request_review", &edited), None);
+    }
+
+    #[test]
+    fn workflow_runs_update_actions() {
+        let payload = serde_json::json!({
+            "action": "completed",
+            "repository": repository(),
+            "workflow_run": {
+                "id": 42, "name": "CI", "status": "completed", "conclusion": "failure",
+                "updated_at": "2026-01-02T00:00:00Z"
+            }
+        });
+        let event = parse_event("workflow_run", &payload).unwrap();
+        let run = item("github_action", "https://github.com/me/app/actions/runs/42", "in_progress", serde_json::json!({}));
+
+        let update = apply(&event, &run).unwrap();
+        assert_eq!(update.status, "failed");
+        assert_eq!(update.metadata["conclusion"], "failure");
+        assert_eq!(update.metadata["run_id"], "42");
+    }
+
+    #[test]
+    fn check_suites_set_ci_status_by_pr_or_branch() {
+        let suite = |pull_requests: Value, branch: &str| {
+            let payload = serde_json::json!({
+                "action": "completed",
+                "repository": repository(),
+                "check_suite": {
+                    "status": "completed", "conclusion": "failure",
+                    "head_branch": branch, "pull_requests": pull_requests
+                }
+            });
+            parse_event("check_suite", &payload).unwrap()
+        };
+
+        let update = apply(&suite(serde_json::json!([{"number": 7}]), "other"), &pr_item()).unwrap();
+        assert_eq!(update.status, "in_progress");
+        assert_eq!(update.metadata["ci_status"], "failed");
+
+        assert!(apply(&suite(serde_json::json!([]), "fix-login"), &pr_item()).is_some());
+        assert_eq!(apply(&suite(serde_json::json!([{"number": 8}]), "other"), &pr_item()), None);
+    }
+
+    #[test]
+    fn unrelated_events_are_ignored() {
+        let payload = serde_json::json!({"zen": "Keep it simple.", "repository": repository()});
+        assert_eq!(parse_event("ping", &payload), None);
+        assert_eq!(parse_event("pull_request", &serde_json::json!({})), None);
+    }
+}
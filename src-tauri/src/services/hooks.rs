@@ -0,0 +1,240 @@
+//! User shell hooks: executables in the hooks directory that run when an
+//! item changes status.
+//!
+//! `on-status-change` runs on every transition, `on-<status>` (e.g.
+//! `on-input-needed`) only when an item enters that status. A hook gets the
+//! item as JSON on stdin and `LOOP_ITEM_ID`, `LOOP_ITEM_TYPE`,
+//! `LOOP_ITEM_TITLE`, `LOOP_ITEM_URL`, `LOOP_OLD_STATUS` and
+//! `LOOP_NEW_STATUS` in its environment.
+
+use crate::db::{HookRun, Item};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+
+/// Hook run on every status change.
+pub const STATUS_CHANGE_HOOK: &str = "on-status-change";
+
+/// How long a hook may run before it is killed.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Captured stderr beyond this many bytes is cut off.
+const STDERR_LIMIT: usize = 4000;
+
+/// `hooks` next to the app's database.
+pub fn hooks_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("com.intheloop.app").join("hooks"))
+}
+
+/// Name of the hook run when an item enters `status`.
+pub fn status_hook_name(status: &str) -> String {
+    format!("on-{}", status.replace('_', "-"))
+}
+
+/// The executable hooks in `dir` for a change to `new_status`: the
+/// status-specific hook first, then `on-status-change`.
+pub fn hooks_for(dir: &Path, new_status: &str) -> Vec<PathBuf> {
+    [status_hook_name(new_status), STATUS_CHANGE_HOOK.to_string()]
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| is_executable(path))
+        .collect()
+}
+
+/// Names of the executable hooks in `dir`, sorted.
+pub fn list_hooks(dir: &Path) -> Vec<String> {
+    let mut hooks: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| is_executable(&e.path()))
+                .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                .filter(|name| name.starts_with("on-"))
+                .collect()
+        })
+        .unwrap_or_default();
+    hooks.sort();
+    hooks
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Run one hook for a status change of `item` and report how it went.
+pub async fn run_hook(path: &Path, item: &Item, old_status: &str, new_status: &str, timeout: Duration) -> HookRun {
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let start = Instant::now();
+    let mut run = HookRun {
+        id: 0,
+        hook: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        item_id: item.id.clone(),
+        old_status: old_status.to_string(),
+        new_status: new_status.to_string(),
+        started_at,
+        duration_ms: 0,
+        exit_code: None,
+        timed_out: false,
+        stderr: String::new(),
+    };
+
+    let child = tokio::process::Command::new(path)
+        .env("LOOP_ITEM_ID", &item.id)
+        .env("LOOP_ITEM_TYPE", &item.item_type)
+        .env("LOOP_ITEM_TITLE", &item.title)
+        .env("LOOP_ITEM_URL", item.url.as_deref().unwrap_or(""))
+        .env("LOOP_OLD_STATUS", old_status)
+        .env("LOOP_NEW_STATUS", new_status)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            run.stderr = format!("Failed to start hook: {}", e);
+            return run;
+        }
+    };
+
+    let input = serde_json::to_vec(item).unwrap_or_default();
+    let stdin = child.stdin.take();
+    let finished = tokio::time::timeout(timeout, async move {
+        if let Some(mut stdin) = stdin {
+            // A hook that doesn't read stdin closes the pipe early; that's fine.
+            let _ = stdin.write_all(&input).await;
+        }
+        child.wait_with_output().await
+    })
+    .await;
+
+    match finished {
+        Ok(Ok(output)) => {
+            run.exit_code = output.status.code();
+            run.stderr = truncate(&String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(Err(e)) => run.stderr = format!("Failed to wait for hook: {}", e),
+        Err(_) => {
+            run.timed_out = true;
+            run.stderr = format!("Killed after {}s", timeout.as_secs());
+        }
+    }
+    run.duration_ms = start.elapsed().as_millis() as i64;
+    run
+}
+
+fn truncate(stderr: &str) -> String {
+    let stderr = stderr.trim_end();
+    if stderr.len() <= STDERR_LIMIT {
+        return stderr.to_string();
+    }
+    let mut end = STDERR_LIMIT;
+    while !stderr.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &stderr[..end])
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn hook_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("itl-hooks-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_hook(dir: &Path, name: &str, script: &str, executable: bool) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        let mode = if executable { 0o755 } else { 0o644 };
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    fn item() -> Item {
+        Item {
+            id: "item-1".to_string(),
+            item_type: "claude_session".to_string(),
+            title: "Fix login".to_string(),
+            url: None,
+            status: "in_progress".to_string(),
+            previous_status: None,
+            metadata: "{}".to_string(),
+            last_checked_at: None,
+            last_updated_at: None,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        }
+    }
+
+    #[test]
+    fn hooks_are_found_by_status() {
+        let dir = hook_dir();
+        write_hook(&dir, STATUS_CHANGE_HOOK, "exit 0", true);
+        write_hook(&dir, "on-input-needed", "exit 0", true);
+        write_hook(&dir, "on-completed", "exit 0", false);
+
+        let names = |status| -> Vec<String> {
+            hooks_for(&dir, status)
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        };
+        assert_eq!(names("input_needed"), vec!["on-input-needed", "on-status-change"]);
+        assert_eq!(names("completed"), vec!["on-status-change"]);
+        assert_eq!(list_hooks(&dir), vec!["on-input-needed", "on-status-change"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn hooks_get_the_item_and_stderr_is_captured() {
+        let dir = hook_dir();
+        let hook = write_hook(
+            &dir,
+            STATUS_CHANGE_HOOK,
+            r#"title=$(cat | sed 's/.*"title":"\([^"]*\)".*/\1/')
+echo "$LOOP_ITEM_ID $LOOP_OLD_STATUS->$LOOP_NEW_STATUS $title" >&2
+exit 3"#,
+            true,
+        );
+
+        let run = run_hook(&hook, &item(), "in_progress", "input_needed", TIMEOUT).await;
+        assert_eq!(run.hook, STATUS_CHANGE_HOOK);
+        assert_eq!(run.exit_code, Some(3));
+        assert!(!run.timed_out);
+        assert_eq!(run.stderr, "item-1 in_progress->input_needed Fix login");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn slow_hooks_are_killed() {
+        let dir = hook_dir();
+        let hook = write_hook(&dir, "on-failed", "sleep 5", true);
+
+        let run = run_hook(&hook, &item(), "in_progress", "failed", Duration::from_millis(200)).await;
+        assert!(run.timed_out);
+        assert_eq!(run.exit_code, None);
+        assert!(run.duration_ms < 5000);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+//! Typed links between items and the status rollups computed over them.
+
+use crate::db::ItemLink;
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+
+/// How the source item of a link relates to its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    /// The target can't go ahead until the source is done.
+    Blocks,
+    /// The source came out of the target (a PR opened by an agent session).
+    ProducedBy,
+    RelatedTo,
+}
+
+impl Relation {
+    pub const ALL: [Relation; 3] = [Relation::Blocks, Relation::ProducedBy, Relation::RelatedTo];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relation::Blocks => "blocks",
+            Relation::ProducedBy => "produced_by",
+            Relation::RelatedTo => "related_to",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown relation '{}', expected blocks, produced_by or related_to", s))
+    }
+
+    /// How the link reads from the source item (`outgoing`) or the target.
+    pub fn label(&self, outgoing: bool) -> &'static str {
+        match (self, outgoing) {
+            (Relation::Blocks, true) => "Blocks",
+            (Relation::Blocks, false) => "Blocked by",
+            (Relation::ProducedBy, true) => "Produced by",
+            (Relation::ProducedBy, false) => "Produced",
+            (Relation::RelatedTo, _) => "Related to",
+        }
+    }
+}
+
+/// Validate a new link against the existing ones. Blocking links may not
+/// form a cycle, or the items involved could never be unblocked.
+pub fn check_new_link(links: &[ItemLink], source: &str, target: &str, relation: &str) -> Result<Relation> {
+    let relation = Relation::parse(relation)?;
+    if source == target {
+        return Err(anyhow!("An item can't be linked to itself"));
+    }
+    if relation == Relation::Blocks && blocks_transitively(links, target, source) {
+        return Err(anyhow!("Link would create a blocking cycle"));
+    }
+    Ok(relation)
+}
+
+/// Whether `from` blocks `to` through a chain of blocking links.
+fn blocks_transitively(links: &[ItemLink], from: &str, to: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut pending = vec![from];
+    while let Some(current) = pending.pop() {
+        if current == to {
+            return true;
+        }
+        if !seen.insert(current) {
+            continue;
+        }
+        pending.extend(
+            links
+                .iter()
+                .filter(|l| l.relation == Relation::Blocks.as_str() && l.source_item_id == current)
+                .map(|l| l.target_item_id.as_str()),
+        );
+    }
+    false
+}
+
+/// Statuses that mean an item no longer blocks anything.
+fn is_resolved(status: &str) -> bool {
+    matches!(status, "merged" | "completed" | "closed")
+}
+
+/// State of the items blocking an item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rollup {
+    Blocked { open: usize, total: usize },
+    Resolved { total: usize },
+    Failed { failed: usize, total: usize },
+}
+
+impl Rollup {
+    /// Roll up the statuses of an item's blockers; a failure anywhere wins.
+    /// `None` when nothing blocks the item.
+    pub fn of(blocker_statuses: &[&str]) -> Option<Self> {
+        let total = blocker_statuses.len();
+        if total == 0 {
+            return None;
+        }
+        let failed = blocker_statuses.iter().filter(|s| **s == "failed").count();
+        let open = blocker_statuses.iter().filter(|s| !is_resolved(s)).count();
+        Some(if failed > 0 {
+            Rollup::Failed { failed, total }
+        } else if open == 0 {
+            Rollup::Resolved { total }
+        } else {
+            Rollup::Blocked { open, total }
+        })
+    }
+
+    /// Timeline event kind recording this state.
+    pub fn event_kind(&self) -> &'static str {
+        match self {
+            Rollup::Blocked { .. } => "rollup_blocked",
+            Rollup::Resolved { .. } => "rollup_resolved",
+            Rollup::Failed { .. } => "rollup_failed",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Rollup::Blocked { open, total } => format!("{} of {} blocking items still open", open, total),
+            Rollup::Resolved { total } if *total == 1 => "The blocking item is done".to_string(),
+            Rollup::Resolved { total } => format!("All {} blocking items are done", total),
+            Rollup::Failed { failed, total } => format!("{} of {} blocking items failed", failed, total),
+        }
+    }
+
+    /// Whether reaching this state is worth a notification.
+    pub fn notifies(&self) -> bool {
+        !matches!(self, Rollup::Blocked { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(source: &str, target: &str, relation: &str) -> ItemLink {
+        ItemLink {
+            source_item_id: source.to_string(),
+            target_item_id: target.to_string(),
+            relation: relation.to_string(),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn relations_round_trip() {
+        for relation in Relation::ALL {
+            assert_eq!(Relation::parse(relation.as_str()).unwrap(), relation);
+        }
+        assert!(Relation::parse("depends_on").is_err());
+        assert_eq!(Relation::Blocks.label(false), "Blocked by");
+    }
+
+    #[test]
+    fn blocking_cycles_are_rejected() {
+        let links = vec![link("a", "b", "blocks"), link("b", "c", "blocks"), link("c", "a", "related_to")];
+        assert!(check_new_link(&links, "c", "a", "blocks").is_err());
+        assert!(check_new_link(&links, "c", "a", "related_to").is_ok());
+        assert!(check_new_link(&links, "a", "c", "blocks").is_ok());
+        assert!(check_new_link(&links, "a", "a", "related_to").is_err());
+        assert!(check_new_link(&links, "a", "b", "needs").is_err());
+    }
+
+    #[test]
+    fn blockers_roll_up() {
+        assert_eq!(Rollup::of(&[]), None);
+        assert_eq!(
+            Rollup::of(&["merged", "in_progress", "approved"]),
+            Some(Rollup::Blocked { open: 2, total: 3 })
+        );
+        assert_eq!(Rollup::of(&["merged", "completed"]), Some(Rollup::Resolved { total: 2 }));
+        assert_eq!(
+            Rollup::of(&["merged", "failed", "in_progress"]),
+            Some(Rollup::Failed { failed: 1, total: 3 })
+        );
+        assert_eq!(Rollup::Resolved { total: 2 }.describe(), "All 2 blocking items are done");
+        assert!(!Rollup::Blocked { open: 1, total: 1 }.notifies());
+    }
+}
@@ -4,3 +4,20 @@ pub mod github_actions;
 pub mod github_pr;
 pub mod opencode;
 pub mod copilot_cli;
+pub mod cli_session;
+pub mod process_table;
+pub mod transcript;
+pub mod opencode_control;
+pub mod opencode_events;
+pub mod budget;
+pub mod claude_code;
+pub mod agent_logs;
+pub mod pr_link;
+pub mod item_links;
+pub mod rules;
+pub mod hooks;
+pub mod webhooks;
+pub mod github_webhook;
+pub mod notifications;
+pub mod reminders;
+pub mod session_state;
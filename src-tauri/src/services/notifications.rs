@@ -0,0 +1,167 @@
+//! Which notification a status change of an item is worth, per item type,
+//! and what each kind of notification defaults to.
+
+use crate::services::agent_logs;
+
+/// A kind of notification, switched on or off per item type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    SessionStarted,
+    SessionEnded,
+    InputNeeded,
+    PrApproved,
+    PrReview,
+    PrMerged,
+    CiFailed,
+    CiPassed,
+    SlackReply,
+    Blockers,
+}
+
+impl Category {
+    pub const ALL: [Category; 10] = [
+        Category::SessionStarted,
+        Category::SessionEnded,
+        Category::InputNeeded,
+        Category::PrApproved,
+        Category::PrReview,
+        Category::PrMerged,
+        Category::CiFailed,
+        Category::CiPassed,
+        Category::SlackReply,
+        Category::Blockers,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Category::SessionStarted => "session_started",
+            Category::SessionEnded => "session_ended",
+            Category::InputNeeded => "input_needed",
+            Category::PrApproved => "pr_approved",
+            Category::PrReview => "pr_review",
+            Category::PrMerged => "pr_merged",
+            Category::CiFailed => "ci_failed",
+            Category::CiPassed => "ci_passed",
+            Category::SlackReply => "slack_reply",
+            Category::Blockers => "blockers",
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::SessionStarted => "Started",
+            Category::SessionEnded => "Ended",
+            Category::InputNeeded => "Input needed",
+            Category::PrApproved => "Approved",
+            Category::PrReview => "New review",
+            Category::PrMerged => "Merged or closed",
+            Category::CiFailed => "CI failed",
+            Category::CiPassed => "CI passed",
+            Category::SlackReply => "New reply",
+            Category::Blockers => "Blockers done or failed",
+        }
+    }
+
+    /// The setting that switched this category on or off for every item
+    /// type before the per-type matrix; it is still the default.
+    pub fn legacy_setting(&self) -> Option<&'static str> {
+        match self {
+            Category::SessionStarted => Some("notify_session_started"),
+            Category::SessionEnded => Some("notify_session_ended"),
+            Category::InputNeeded => Some("notify_input_needed"),
+            _ => None,
+        }
+    }
+}
+
+/// Item types that aren't agent sessions, with their own categories.
+const NON_SESSION_TYPES: &[&str] = &["github_pr", "github_action", "slack_thread"];
+
+/// Every item type, in the order the matrix shows them.
+pub fn item_types() -> Vec<&'static str> {
+    let mut types = NON_SESSION_TYPES.to_vec();
+    types.extend(["copilot_agent", "opencode_session", "cli_session"]);
+    types.extend(agent_logs::AGENTS.iter().map(|agent| agent.item_type));
+    types
+}
+
+/// The categories that can apply to an item type. Any item can be
+/// blocked by linked items.
+pub fn categories_for(item_type: &str) -> &'static [Category] {
+    match item_type {
+        "github_pr" => &[
+            Category::PrApproved,
+            Category::PrReview,
+            Category::PrMerged,
+            Category::CiFailed,
+            Category::Blockers,
+        ],
+        "github_action" => &[Category::CiFailed, Category::CiPassed, Category::Blockers],
+        "slack_thread" => &[Category::SlackReply, Category::Blockers],
+        _ => &[Category::SessionStarted, Category::SessionEnded, Category::InputNeeded, Category::Blockers],
+    }
+}
+
+/// The notification a status change is worth: its default body and
+/// category. New Slack replies and PR CI results aren't status changes and
+/// are notified where they are noticed.
+pub fn classify(item_type: &str, old_status: &str, new_status: &str) -> Option<(&'static str, Category)> {
+    match item_type {
+        "github_pr" => match new_status {
+            "approved" => Some(("PR approved", Category::PrApproved)),
+            "updated" => Some(("New review activity", Category::PrReview)),
+            "merged" => Some(("PR merged", Category::PrMerged)),
+            "completed" => Some(("PR closed", Category::PrMerged)),
+            _ => None,
+        },
+        "github_action" => match new_status {
+            "failed" => Some(("Workflow run failed", Category::CiFailed)),
+            "completed" => Some(("Workflow run succeeded", Category::CiPassed)),
+            _ => None,
+        },
+        "slack_thread" => None,
+        _ => match (old_status, new_status) {
+            ("in_progress", "input_needed") => Some(("Waiting for your input", Category::InputNeeded)),
+            ("in_progress", "completed") => Some(("Agent finished working", Category::SessionEnded)),
+            ("in_progress", "closed") => Some(("Session closed", Category::SessionEnded)),
+            ("in_progress", "lost") => Some(("Lost contact with the terminal", Category::SessionEnded)),
+            (_, "archived") => Some(("Session has been archived", Category::SessionEnded)),
+            ("input_needed" | "completed" | "closed", "in_progress") => {
+                Some(("Agent started working", Category::SessionStarted))
+            }
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_are_classified_per_type() {
+        assert_eq!(
+            classify("claude_session", "in_progress", "input_needed"),
+            Some(("Waiting for your input", Category::InputNeeded))
+        );
+        assert_eq!(classify("github_pr", "in_progress", "approved").map(|c| c.1), Some(Category::PrApproved));
+        assert_eq!(classify("github_pr", "in_progress", "completed").map(|c| c.1), Some(Category::PrMerged));
+        assert_eq!(classify("github_action", "in_progress", "failed").map(|c| c.1), Some(Category::CiFailed));
+        assert_eq!(classify("github_action", "waiting", "in_progress"), None);
+        assert_eq!(classify("slack_thread", "waiting", "updated"), None);
+    }
+
+    #[test]
+    fn every_category_belongs_to_a_type() {
+        for category in Category::ALL {
+            assert_eq!(Category::parse(category.key()), Some(category));
+            assert!(item_types().iter().any(|t| categories_for(t).contains(&category)));
+        }
+        assert!(item_types().contains(&"codex_session"));
+        assert_eq!(categories_for("codex_session")[2], Category::InputNeeded);
+    }
+}
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use url::Url;
 
-#[derive(Debug, Serialize, Deserialize)]
+use super::transcript::{self, TranscriptStep, TranscriptTurn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenCodeSession {
     pub id: String,
     pub title: String,
@@ -14,7 +16,7 @@ pub struct OpenCodeSession {
     pub time: SessionTime,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionTime {
     pub created: f64,
     pub updated: f64,
@@ -36,6 +38,37 @@ pub enum SessionStatus {
     },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionOption {
+    pub label: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionPrompt {
+    pub question: String,
+    #[serde(default)]
+    pub header: String,
+    #[serde(default)]
+    pub options: Vec<QuestionOption>,
+    #[serde(default)]
+    pub multiple: bool,
+}
+
+/// A question an agent asked through OpenCode's question tool, awaiting a reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingQuestion {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(default)]
+    pub questions: Vec<QuestionPrompt>,
+    /// Directory the question was listed under; replies are routed through it.
+    #[serde(default)]
+    pub directory: Option<String>,
+}
+
 pub struct SessionMessageSummary {
     pub message_count: usize,
     pub total_tokens: u64,
@@ -50,14 +83,54 @@ pub struct OpenCodeConfig {
     pub directory: Option<String>,
 }
 
+/// A named OpenCode server resolved for API calls.
+#[derive(Debug, Clone)]
+pub struct ServerEndpoint {
+    pub name: String,
+    pub base_url: String,
+    pub password: String,
+    /// Directory encoded in the configured URL, if any.
+    pub directory: Option<String>,
+}
+
+impl ServerEndpoint {
+    pub fn new(name: &str, raw_url: &str, password: &str) -> Result<Self> {
+        let config = parse_opencode_url(raw_url)?;
+        Ok(Self {
+            name: name.to_string(),
+            base_url: config.base_url,
+            password: password.to_string(),
+            directory: config.directory,
+        })
+    }
+
+    /// Directories to query on this server: those OpenCode has used on this
+    /// machine, plus the one from the server URL (e.g. a container's workspace).
+    pub fn directories(&self) -> Vec<String> {
+        let mut directories = enumerate_opencode_directories();
+        if let Some(ref dir) = self.directory {
+            if !directories.contains(dir) {
+                directories.push(dir.clone());
+            }
+        }
+        directories
+    }
+}
+
 fn build_client() -> reqwest::Client {
     reqwest::Client::new()
 }
 
 fn build_request(client: &reqwest::Client, url: &str, password: &str) -> reqwest::RequestBuilder {
-    let builder = client
-        .get(url)
-        .header("Accept", "application/json");
+    with_auth(client.get(url), password)
+}
+
+fn build_post_request(client: &reqwest::Client, url: &str, password: &str) -> reqwest::RequestBuilder {
+    with_auth(client.post(url), password)
+}
+
+fn with_auth(builder: reqwest::RequestBuilder, password: &str) -> reqwest::RequestBuilder {
+    let builder = builder.header("Accept", "application/json");
     if password.is_empty() {
         builder
     } else {
@@ -144,11 +217,12 @@ pub async fn get_session_statuses(
     Ok(response.json().await?)
 }
 
-pub async fn get_session_message_summary(
+/// Fetch all messages of a session, each with `info` and `parts`.
+pub async fn get_session_messages(
     base_url: &str,
     password: &str,
     session_id: &str,
-) -> Result<SessionMessageSummary> {
+) -> Result<Vec<serde_json::Value>> {
     let client = build_client();
     let url = format!("{}/session/{}/message", base_url, session_id);
 
@@ -160,13 +234,21 @@ pub async fn get_session_message_summary(
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
         return Err(anyhow::anyhow!(
-            "OpenCode API error (get_session_message_summary): {} | {}",
+            "OpenCode API error (get_session_messages): {} | {}",
             status,
             body
         ));
     }
 
-    let messages: Vec<serde_json::Value> = response.json().await?;
+    Ok(response.json().await?)
+}
+
+pub async fn get_session_message_summary(
+    base_url: &str,
+    password: &str,
+    session_id: &str,
+) -> Result<SessionMessageSummary> {
+    let messages = get_session_messages(base_url, password, session_id).await?;
 
     let mut message_count: usize = 0;
     let mut total_tokens: u64 = 0;
@@ -222,6 +304,66 @@ pub async fn get_session_message_summary(
     })
 }
 
+/// Convert session messages into transcript turns. Each user message starts
+/// a turn; assistant text and tool parts become its steps.
+pub fn transcript_from_messages(messages: &[serde_json::Value]) -> Vec<TranscriptTurn> {
+    let mut turns: Vec<TranscriptTurn> = Vec::new();
+
+    for entry in messages {
+        let info = &entry["info"];
+        let timestamp = info["time"]["created"]
+            .as_i64()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|t| t.to_rfc3339());
+        let parts = entry["parts"].as_array().cloned().unwrap_or_default();
+
+        if info["role"].as_str() == Some("user") {
+            let text: Vec<&str> = parts
+                .iter()
+                .filter(|p| p["type"].as_str() == Some("text") && !p["synthetic"].as_bool().unwrap_or(false))
+                .filter_map(|p| p["text"].as_str())
+                .collect();
+            turns.push(TranscriptTurn {
+                user_message: Some(text.join("\n")).filter(|t| !t.is_empty()),
+                timestamp,
+                steps: Vec::new(),
+            });
+            continue;
+        }
+
+        if turns.is_empty() {
+            turns.push(TranscriptTurn::default());
+        }
+        let turn = turns.last_mut().unwrap();
+        for part in &parts {
+            match part["type"].as_str() {
+                Some("text") => {
+                    let text = part["text"].as_str().unwrap_or("");
+                    if !text.is_empty() {
+                        turn.steps.push(TranscriptStep::Text {
+                            text: text.to_string(),
+                            timestamp: timestamp.clone(),
+                        });
+                    }
+                }
+                Some("tool") => {
+                    let state = &part["state"];
+                    turn.steps.push(TranscriptStep::ToolCall {
+                        name: part["tool"].as_str().unwrap_or("unknown").to_string(),
+                        arguments: transcript::tool_value_text(&state["input"]),
+                        result: transcript::tool_value_text(&state["output"])
+                            .or_else(|| transcript::tool_value_text(&state["error"])),
+                        timestamp: timestamp.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    turns
+}
+
 pub fn enumerate_opencode_directories() -> Vec<String> {
     let home = match std::env::var("HOME") {
         Ok(h) => h,
@@ -285,14 +427,14 @@ pub fn build_web_url(base_url: &str, directory: &str) -> String {
     format!("{}/{}", base_url, encoded)
 }
 
-pub async fn get_pending_question_session_ids(
+pub async fn get_pending_questions(
     base_url: &str,
     password: &str,
     directories: &[String],
-) -> Result<Vec<String>> {
+) -> Result<Vec<PendingQuestion>> {
     let client = build_client();
     let url = format!("{}/question", base_url);
-    let mut all_session_ids = Vec::new();
+    let mut all_questions = Vec::new();
 
     for dir in directories {
         let response = build_request(&client, &url, password)
@@ -304,16 +446,171 @@ pub async fn get_pending_question_session_ids(
             continue;
         }
 
-        let questions: Vec<serde_json::Value> = response.json().await.unwrap_or_default();
+        let questions: Vec<PendingQuestion> = response.json().await.unwrap_or_default();
+        all_questions.extend(questions.into_iter().map(|q| PendingQuestion {
+            directory: Some(dir.clone()),
+            ..q
+        }));
+    }
 
-        for q in &questions {
-            if let Some(sid) = q.get("sessionID").and_then(|v| v.as_str()) {
-                all_session_ids.push(sid.to_string());
-            }
-        }
+    Ok(all_questions)
+}
+
+/// Reply to a pending question. `answers` holds the selected option labels
+/// (or free text) for each of the question's prompts, in order.
+pub async fn reply_to_question(
+    base_url: &str,
+    password: &str,
+    question: &PendingQuestion,
+    answers: &[Vec<String>],
+) -> Result<()> {
+    let client = build_client();
+    let url = format!("{}/question/{}/reply", base_url, question.id);
+
+    let mut request = build_post_request(&client, &url, password)
+        .json(&serde_json::json!({ "answers": answers }));
+    if let Some(ref dir) = question.directory {
+        request = request.query(&[("directory", dir.as_str())]);
     }
 
-    Ok(all_session_ids)
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "OpenCode API error (reply_to_question): {} | {}",
+            status,
+            body
+        ));
+    }
+    Ok(())
+}
+
+/// Send a follow-up prompt to a session. Uses `prompt_async`, so this
+/// returns once OpenCode accepted the prompt, not when the reply is done.
+pub async fn send_prompt(
+    base_url: &str,
+    password: &str,
+    session_id: &str,
+    directory: Option<&str>,
+    text: &str,
+) -> Result<()> {
+    let client = build_client();
+    let url = format!("{}/session/{}/prompt_async", base_url, session_id);
+
+    let mut request = build_post_request(&client, &url, password).json(&serde_json::json!({
+        "parts": [{ "type": "text", "text": text }]
+    }));
+    if let Some(dir) = directory {
+        request = request.query(&[("directory", dir)]);
+    }
+
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "OpenCode API error (send_prompt): {} | {}",
+            status,
+            body
+        ));
+    }
+    Ok(())
+}
+
+/// Abort whatever a busy session is currently doing.
+pub async fn abort_session(
+    base_url: &str,
+    password: &str,
+    session_id: &str,
+    directory: Option<&str>,
+) -> Result<()> {
+    let client = build_client();
+    let url = format!("{}/session/{}/abort", base_url, session_id);
+
+    let mut request = build_post_request(&client, &url, password);
+    if let Some(dir) = directory {
+        request = request.query(&[("directory", dir)]);
+    }
+
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "OpenCode API error (abort_session): {} | {}",
+            status,
+            body
+        ));
+    }
+    Ok(())
+}
+
+/// Create a new session in `directory`.
+pub async fn create_session(
+    base_url: &str,
+    password: &str,
+    directory: &str,
+) -> Result<OpenCodeSession> {
+    let client = build_client();
+    let url = format!("{}/session", base_url);
+
+    let response = build_post_request(&client, &url, password)
+        .query(&[("directory", directory)])
+        .json(&serde_json::json!({}))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "OpenCode API error (create_session): {} | {}",
+            status,
+            body
+        ));
+    }
+
+    Ok(response.json().await?)
+}
+
+/// Short human-readable form of a question, for notifications and lists.
+pub fn describe_question(question: &PendingQuestion) -> Option<String> {
+    let prompt = question.questions.first()?;
+    let mut text = prompt.question.clone();
+    if !prompt.options.is_empty() {
+        let labels: Vec<&str> = prompt.options.iter().map(|o| o.label.as_str()).collect();
+        text.push_str(&format!(" [{}]", labels.join(" / ")));
+    }
+    if question.questions.len() > 1 {
+        text.push_str(&format!(" (+{} more)", question.questions.len() - 1));
+    }
+    Some(text)
+}
+
+/// Open the server-wide event stream (SSE), which carries events for all
+/// directories. The caller reads the body in chunks for as long as it lasts.
+pub async fn open_event_stream(base_url: &str, password: &str) -> Result<reqwest::Response> {
+    let client = build_client();
+    let url = format!("{}/global/event", base_url);
+
+    let mut request = client.get(&url).header("Accept", "text/event-stream");
+    if !password.is_empty() {
+        request = request.basic_auth("opencode", Some(password));
+    }
+
+    let response = request.send().await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "OpenCode API error (open_event_stream): {} | {}",
+            status,
+            body
+        ));
+    }
+    Ok(response)
 }
 
 pub async fn check_opencode_health(
@@ -338,7 +635,7 @@ pub async fn poll_opencode_session(
     password: &str,
     session_id: &str,
     statuses: &HashMap<String, SessionStatus>,
-    pending_question_session_ids: &[String],
+    pending_questions: &[PendingQuestion],
 ) -> Result<HashMap<String, serde_json::Value>> {
     let summary = get_session_message_summary(base_url, password, session_id).await?;
 
@@ -349,7 +646,7 @@ pub async fn poll_opencode_session(
         None => "unknown",
     };
 
-    let has_pending_question = pending_question_session_ids.contains(&session_id.to_string());
+    let pending_question = pending_questions.iter().find(|q| q.session_id == session_id);
 
     let mut result = HashMap::new();
     result.insert(
@@ -362,7 +659,11 @@ pub async fn poll_opencode_session(
     );
     result.insert(
         "has_pending_question".to_string(),
-        serde_json::json!(has_pending_question),
+        serde_json::json!(pending_question.is_some()),
+    );
+    result.insert(
+        "pending_question".to_string(),
+        serde_json::json!(pending_question),
     );
     result.insert(
         "model".to_string(),
@@ -387,3 +688,67 @@ pub async fn poll_opencode_session(
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pending_question_is_parsed_and_described() {
+        let question: PendingQuestion = serde_json::from_value(json!({
+            "id": "que_1",
+            "sessionID": "ses_1",
+            "questions": [{
+                "question": "Which database?",
+                "header": "Database",
+                "options": [{"label": "SQLite", "description": "embedded"}, {"label": "Postgres"}]
+            }, {
+                "question": "Add migrations?",
+                "options": []
+            }],
+            "tool": {"messageID": "msg_1", "callID": "call_1"}
+        }))
+        .unwrap();
+
+        assert_eq!(question.session_id, "ses_1");
+        assert_eq!(question.questions[0].options.len(), 2);
+        assert!(!question.questions[0].multiple);
+        assert_eq!(
+            describe_question(&question).as_deref(),
+            Some("Which database? [SQLite / Postgres] (+1 more)")
+        );
+    }
+
+    #[test]
+    fn messages_become_transcript_turns() {
+        let messages = vec![
+            json!({
+                "info": {"role": "user", "time": {"created": 1_700_000_000_000i64}},
+                "parts": [{"type": "text", "text": "add a README"}]
+            }),
+            json!({
+                "info": {"role": "assistant", "time": {"created": 1_700_000_005_000i64}},
+                "parts": [
+                    {"type": "step-start"},
+                    {"type": "text", "text": "Creating it."},
+                    {"type": "tool", "tool": "write", "state": {"status": "completed", "input": {"filePath": "README.md"}, "output": "wrote 12 lines"}}
+                ]
+            }),
+        ];
+
+        let turns = transcript_from_messages(&messages);
+        assert_eq!(turns.len(), 1);
+        assert_eq!(turns[0].user_message.as_deref(), Some("add a README"));
+        assert!(turns[0].timestamp.as_deref().unwrap().starts_with("2023-11-14"));
+        assert_eq!(turns[0].steps.len(), 2);
+        match &turns[0].steps[1] {
+            TranscriptStep::ToolCall { name, arguments, result, .. } => {
+                assert_eq!(name, "write");
+                assert_eq!(arguments.as_deref(), Some("{\"filePath\":\"README.md\"}"));
+                assert_eq!(result.as_deref(), Some("wrote 12 lines"));
+            }
+            other => panic!("expected tool call, got {:?}", other),
+        }
+    }
+}
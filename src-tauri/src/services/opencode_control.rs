@@ -0,0 +1,380 @@
+use crate::db::{Database, Item, OpenCodeServer, DEFAULT_OPENCODE_SERVER};
+use crate::services::opencode::{self, OpenCodeSession, PendingQuestion, ServerEndpoint, SessionStatus};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// All configured OpenCode servers. Servers with an invalid URL are skipped.
+pub fn endpoints(db: &Database) -> Result<Vec<ServerEndpoint>> {
+    Ok(db
+        .get_opencode_servers()?
+        .iter()
+        .filter_map(|s| endpoint(s).ok())
+        .collect())
+}
+
+fn endpoint(server: &OpenCodeServer) -> Result<ServerEndpoint> {
+    ServerEndpoint::new(&server.name, &server.url, &server.password)
+}
+
+/// The configured OpenCode server called `name`.
+pub fn server_endpoint(db: &Database, name: &str) -> Result<ServerEndpoint> {
+    let server = db
+        .get_opencode_server(name)?
+        .ok_or_else(|| anyhow::anyhow!("OpenCode server '{}' is not configured", name))?;
+    endpoint(&server)
+}
+
+/// Name of the server an item's session lives on.
+pub fn server_name(metadata: &serde_json::Value) -> &str {
+    metadata["server"].as_str().unwrap_or(DEFAULT_OPENCODE_SERVER)
+}
+
+fn parse_metadata(item: &Item) -> Result<serde_json::Value> {
+    if item.item_type != "opencode_session" {
+        return Err(anyhow::anyhow!("Item is not an OpenCode session"));
+    }
+    Ok(serde_json::from_str(&item.metadata)?)
+}
+
+fn session_id(metadata: &serde_json::Value) -> Result<&str> {
+    metadata["session_id"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Missing session_id in opencode_session metadata"))
+}
+
+fn session_directory(metadata: &serde_json::Value, session_id: &str) -> Option<String> {
+    metadata["directory"]
+        .as_str()
+        .map(|s| s.to_string())
+        .or_else(|| opencode::find_session_directory(session_id))
+}
+
+fn status_name(status: Option<&SessionStatus>) -> &'static str {
+    match status {
+        Some(SessionStatus::Idle) => "idle",
+        Some(SessionStatus::Busy) => "busy",
+        Some(SessionStatus::Retry { .. }) => "retry",
+        None => "unknown",
+    }
+}
+
+/// Build the item tracking an OpenCode session. Sub-agent sessions pass the
+/// item of their parent session, under which they are nested.
+pub fn session_item(
+    session: &OpenCodeSession,
+    server: &ServerEndpoint,
+    directory: &str,
+    statuses: &HashMap<String, SessionStatus>,
+    parent_item_id: Option<&str>,
+) -> Result<Item> {
+    let status = statuses.get(&session.id);
+    let status_str = if session.time.archived.is_some() {
+        "archived"
+    } else {
+        match status {
+            Some(SessionStatus::Busy) | Some(SessionStatus::Retry { .. }) => "in_progress",
+            Some(SessionStatus::Idle) | None => "waiting",
+        }
+    };
+
+    let title = if session.title.is_empty() {
+        format!("OpenCode Session {}", &session.id[..8.min(session.id.len())])
+    } else {
+        session.title.clone()
+    };
+
+    let metadata = serde_json::json!({
+        "session_id": session.id,
+        "server": server.name,
+        "opencode_url": opencode::build_web_url(&server.base_url, directory),
+        "directory": directory,
+        "session_status": status_name(status),
+        "session_title": session.title,
+        "last_activity": session.time.updated,
+        "parent_session_id": session.parent_id,
+        "parent_item_id": parent_item_id,
+    });
+
+    Ok(Item {
+        id: uuid::Uuid::new_v4().to_string(),
+        item_type: "opencode_session".to_string(),
+        title,
+        url: None,
+        status: status_str.to_string(),
+        previous_status: None,
+        metadata: serde_json::to_string(&metadata)?,
+        last_checked_at: None,
+        last_updated_at: None,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        archived: false,
+        archived_at: None,
+        polling_interval_override: None,
+        checked: status_str == "archived",
+    })
+}
+
+/// Status, tokens and cost of a parent session's sub-agent sessions.
+#[derive(Debug, Default)]
+pub struct ChildRollup {
+    pub children: Vec<serde_json::Value>,
+    pub busy: usize,
+    pub total_tokens: u64,
+    pub total_cost: f64,
+}
+
+impl ChildRollup {
+    /// Summarize `children`, taking live busy/idle state from `statuses`
+    /// and tokens and cost from each child's last poll.
+    pub fn from_children(children: &[Item], statuses: &HashMap<String, SessionStatus>) -> Self {
+        let mut rollup = Self::default();
+        for child in children.iter().filter(|c| !c.archived) {
+            let metadata: serde_json::Value = serde_json::from_str(&child.metadata).unwrap_or_default();
+            let session_status = match metadata["session_id"].as_str() {
+                Some(id) => status_name(statuses.get(id)),
+                None => "unknown",
+            };
+            if session_status == "busy" || session_status == "retry" {
+                rollup.busy += 1;
+            }
+            let tokens = metadata["total_tokens"].as_u64().unwrap_or(0);
+            let cost = metadata["total_cost"].as_f64().unwrap_or(0.0);
+            rollup.total_tokens += tokens;
+            rollup.total_cost += cost;
+            rollup.children.push(serde_json::json!({
+                "item_id": child.id,
+                "session_id": metadata["session_id"],
+                "title": child.title,
+                "status": child.status,
+                "session_status": session_status,
+                "total_tokens": tokens,
+                "total_cost": cost,
+            }));
+        }
+        rollup
+    }
+
+    pub fn insert_into(&self, map: &mut HashMap<String, serde_json::Value>) {
+        map.insert("children".to_string(), serde_json::json!(self.children));
+        map.insert("children_busy".to_string(), serde_json::json!(self.busy));
+        map.insert("children_total_tokens".to_string(), serde_json::json!(self.total_tokens));
+        map.insert("children_total_cost".to_string(), serde_json::json!(self.total_cost));
+    }
+}
+
+fn set_session_status(metadata: &mut serde_json::Value, session_status: &str) {
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert("session_status".to_string(), serde_json::json!(session_status));
+    }
+}
+
+/// Send a follow-up prompt to an idle session. Busy sessions have to be
+/// aborted first, so a prompt never silently queues behind running work.
+pub async fn send_prompt(db: &Database, item: &Item, text: &str) -> Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(anyhow::anyhow!("Prompt is empty"));
+    }
+
+    let mut metadata = parse_metadata(item)?;
+    let session_id = session_id(&metadata)?.to_string();
+    let directory = session_directory(&metadata, &session_id);
+    let server = server_endpoint(db, server_name(&metadata))?;
+
+    let statuses =
+        opencode::get_session_statuses(&server.base_url, &server.password, directory.as_deref()).await?;
+    if matches!(
+        statuses.get(&session_id),
+        Some(SessionStatus::Busy) | Some(SessionStatus::Retry { .. })
+    ) {
+        return Err(anyhow::anyhow!("Session is busy; abort it before sending a new prompt"));
+    }
+
+    opencode::send_prompt(&server.base_url, &server.password, &session_id, directory.as_deref(), text).await?;
+
+    set_session_status(&mut metadata, "busy");
+    db.update_item_status(&item.id, "in_progress", Some(&metadata.to_string()))?;
+    db.add_item_event(&item.id, "prompt_sent", text)?;
+    Ok(())
+}
+
+/// Abort a busy session. The item is marked completed, as it would be once
+/// polling sees the session go idle.
+pub async fn abort(db: &Database, item: &Item) -> Result<()> {
+    let mut metadata = parse_metadata(item)?;
+    let session_id = session_id(&metadata)?.to_string();
+    let directory = session_directory(&metadata, &session_id);
+    let server = server_endpoint(db, server_name(&metadata))?;
+
+    let statuses =
+        opencode::get_session_statuses(&server.base_url, &server.password, directory.as_deref()).await?;
+    if !matches!(
+        statuses.get(&session_id),
+        Some(SessionStatus::Busy) | Some(SessionStatus::Retry { .. })
+    ) {
+        return Err(anyhow::anyhow!("Session is not running"));
+    }
+
+    opencode::abort_session(&server.base_url, &server.password, &session_id, directory.as_deref()).await?;
+
+    set_session_status(&mut metadata, "idle");
+    db.update_item_status(&item.id, "completed", Some(&metadata.to_string()))?;
+    db.add_item_event(&item.id, "aborted", "Session aborted")?;
+    Ok(())
+}
+
+/// Start a new session on `server` in one of the directories it knows
+/// about and track it right away, optionally sending a first prompt.
+pub async fn start_session(db: &Database, server: &str, directory: &str, prompt: Option<&str>) -> Result<Item> {
+    let server = server_endpoint(db, server)?;
+    if !server.directories().iter().any(|d| d == directory) {
+        return Err(anyhow::anyhow!("Unknown OpenCode directory: {}", directory));
+    }
+
+    let session = opencode::create_session(&server.base_url, &server.password, directory).await?;
+    let item = session_item(&session, &server, directory, &HashMap::new(), None)?;
+    db.add_item(&item)?;
+    db.add_item_event(&item.id, "session_started", &format!("Started in {}", directory))?;
+
+    match prompt.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(text) => {
+            send_prompt(db, &item, text).await?;
+            Ok(db.get_item(&item.id)?.unwrap_or(item))
+        }
+        None => Ok(item),
+    }
+}
+
+/// Answer the question an OpenCode session is blocked on. `answers` holds
+/// one entry per prompt in the question, each with the chosen option labels
+/// or free text. The item moves back to in_progress right away.
+pub async fn answer_question(db: &Database, item: &Item, answers: &[Vec<String>]) -> Result<()> {
+    let mut metadata = parse_metadata(item)?;
+    let question: PendingQuestion = serde_json::from_value(metadata["pending_question"].clone())
+        .map_err(|_| anyhow::anyhow!("Session has no pending question"))?;
+
+    if answers.len() != question.questions.len() {
+        return Err(anyhow::anyhow!(
+            "Expected {} answer(s), got {}",
+            question.questions.len(),
+            answers.len()
+        ));
+    }
+
+    let server = server_endpoint(db, server_name(&metadata))?;
+    opencode::reply_to_question(&server.base_url, &server.password, &question, answers).await?;
+
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert("pending_question".to_string(), serde_json::Value::Null);
+        map.insert("has_pending_question".to_string(), serde_json::json!(false));
+    }
+    db.update_item_status(&item.id, "in_progress", Some(&metadata.to_string()))?;
+
+    let summary = answers
+        .iter()
+        .map(|a| a.join(", "))
+        .collect::<Vec<_>>()
+        .join(" | ");
+    db.add_item_event(&item.id, "question_answered", &summary)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::opencode::SessionTime;
+
+    fn session(title: &str, archived: Option<f64>) -> OpenCodeSession {
+        OpenCodeSession {
+            id: "ses_0123456789".to_string(),
+            title: title.to_string(),
+            directory: "/home/me/project".to_string(),
+            parent_id: None,
+            time: SessionTime {
+                created: 1.0,
+                updated: 2.0,
+                archived,
+            },
+        }
+    }
+
+    fn server() -> ServerEndpoint {
+        ServerEndpoint::new("work", "http://localhost:4096", "").unwrap()
+    }
+
+    #[test]
+    fn session_item_reflects_status_and_directory() {
+        let statuses = HashMap::from([("ses_0123456789".to_string(), SessionStatus::Busy)]);
+        let item = session_item(&session("Fix tests", None), &server(), "/home/me/project", &statuses, None).unwrap();
+        assert_eq!(item.item_type, "opencode_session");
+        assert_eq!(item.title, "Fix tests");
+        assert_eq!(item.status, "in_progress");
+        assert!(!item.checked);
+
+        let metadata: serde_json::Value = serde_json::from_str(&item.metadata).unwrap();
+        assert_eq!(metadata["session_id"], "ses_0123456789");
+        assert_eq!(metadata["server"], "work");
+        assert_eq!(server_name(&metadata), "work");
+        assert_eq!(metadata["directory"], "/home/me/project");
+        assert_eq!(metadata["session_status"], "busy");
+    }
+
+    #[test]
+    fn new_and_archived_sessions() {
+        let item = session_item(&session("", None), &server(), "/tmp", &HashMap::new(), None).unwrap();
+        assert_eq!(item.title, "OpenCode Session ses_0123");
+        assert_eq!(item.status, "waiting");
+
+        let item = session_item(&session("Old", Some(3.0)), &server(), "/tmp", &HashMap::new(), None).unwrap();
+        assert_eq!(item.status, "archived");
+        assert!(item.checked);
+    }
+
+    #[test]
+    fn child_session_item_points_to_parent() {
+        let mut child = session("Explore", None);
+        child.parent_id = Some("ses_parent".to_string());
+        let item = session_item(&child, &server(), "/tmp", &HashMap::new(), Some("item-1")).unwrap();
+
+        let metadata: serde_json::Value = serde_json::from_str(&item.metadata).unwrap();
+        assert_eq!(metadata["parent_session_id"], "ses_parent");
+        assert_eq!(metadata["parent_item_id"], "item-1");
+    }
+
+    #[test]
+    fn children_roll_up_busy_tokens_and_cost() {
+        let child = |id: &str, session_id: &str, tokens: u64, archived: bool| {
+            let mut item =
+                session_item(&session("Sub", None), &server(), "/tmp", &HashMap::new(), Some("parent")).unwrap();
+            item.id = id.to_string();
+            item.archived = archived;
+            item.metadata = serde_json::json!({
+                "session_id": session_id,
+                "total_tokens": tokens,
+                "total_cost": 0.5,
+            })
+            .to_string();
+            item
+        };
+        let children = vec![
+            child("a", "ses_a", 1000, false),
+            child("b", "ses_b", 500, false),
+            child("c", "ses_c", 9999, true),
+        ];
+        let statuses = HashMap::from([
+            ("ses_a".to_string(), SessionStatus::Busy),
+            ("ses_b".to_string(), SessionStatus::Idle),
+        ]);
+
+        let rollup = ChildRollup::from_children(&children, &statuses);
+        assert_eq!(rollup.children.len(), 2);
+        assert_eq!(rollup.busy, 1);
+        assert_eq!(rollup.total_tokens, 1500);
+        assert!((rollup.total_cost - 1.0).abs() < f64::EPSILON);
+        assert_eq!(rollup.children[0]["session_status"], "busy");
+    }
+
+    #[test]
+    fn items_without_server_belong_to_default() {
+        assert_eq!(server_name(&serde_json::json!({"session_id": "ses_1"})), DEFAULT_OPENCODE_SERVER);
+    }
+}
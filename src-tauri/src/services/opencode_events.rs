@@ -0,0 +1,214 @@
+use crate::services::opencode::{self, OpenCodeSession, PendingQuestion, ServerEndpoint, SessionStatus};
+use std::collections::HashMap;
+
+/// Splits a server-sent event stream into the `data` payloads of its events.
+/// Bytes are buffered until a full line arrives, so chunks may end anywhere,
+/// including inside a multi-byte character.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the stream; returns the payloads of completed events.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+            // Comments (`:`) and `event:`/`id:`/`retry:` fields are not used by OpenCode
+        }
+
+        events
+    }
+}
+
+/// An OpenCode event relevant to item tracking. Everything else
+/// (message parts, file edits, LSP diagnostics, ...) is ignored.
+#[derive(Debug, Clone)]
+pub enum OpenCodeEvent {
+    Status {
+        session_id: String,
+        status: SessionStatus,
+    },
+    SessionUpdated {
+        session: OpenCodeSession,
+        directory: Option<String>,
+    },
+    QuestionAsked(PendingQuestion),
+    QuestionResolved {
+        session_id: String,
+        question_id: String,
+    },
+}
+
+/// Parse one event payload. Accepts both the global stream's
+/// `{directory, payload: {type, properties}}` envelope and bare events.
+pub fn parse_event(data: &str) -> Option<OpenCodeEvent> {
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    let directory = value["directory"].as_str().map(|s| s.to_string());
+    let event = if value["payload"].is_object() { &value["payload"] } else { &value };
+    let properties = &event["properties"];
+    let session_id = || properties["sessionID"].as_str().map(|s| s.to_string());
+
+    match event["type"].as_str()? {
+        "session.status" => Some(OpenCodeEvent::Status {
+            session_id: session_id()?,
+            status: serde_json::from_value(properties["status"].clone()).ok()?,
+        }),
+        "session.idle" => Some(OpenCodeEvent::Status {
+            session_id: session_id()?,
+            status: SessionStatus::Idle,
+        }),
+        "session.created" | "session.updated" => Some(OpenCodeEvent::SessionUpdated {
+            session: serde_json::from_value(properties["info"].clone()).ok()?,
+            directory,
+        }),
+        "question.asked" => {
+            let question: PendingQuestion = serde_json::from_value(properties.clone()).ok()?;
+            Some(OpenCodeEvent::QuestionAsked(PendingQuestion {
+                directory: question.directory.clone().or(directory),
+                ..question
+            }))
+        }
+        "question.replied" | "question.rejected" => Some(OpenCodeEvent::QuestionResolved {
+            session_id: session_id()?,
+            question_id: properties["requestID"].as_str()?.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// Session statuses and pending questions of an OpenCode server, seeded
+/// from the status endpoints and kept current by the event stream.
+#[derive(Debug, Clone, Default)]
+pub struct LiveState {
+    pub statuses: HashMap<String, SessionStatus>,
+    pub questions: HashMap<String, PendingQuestion>,
+}
+
+impl LiveState {
+    /// Fetch the current state of `server` with one status and question
+    /// request per directory.
+    pub async fn fetch(server: &ServerEndpoint) -> Self {
+        let directories = server.directories();
+        let mut statuses = HashMap::new();
+        for dir in &directories {
+            if let Ok(dir_statuses) =
+                opencode::get_session_statuses(&server.base_url, &server.password, Some(dir)).await
+            {
+                statuses.extend(dir_statuses);
+            }
+        }
+
+        let questions = opencode::get_pending_questions(&server.base_url, &server.password, &directories)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|q| (q.id.clone(), q))
+            .collect();
+
+        Self { statuses, questions }
+    }
+
+    /// Fold in an event. Returns the session whose status or questions changed.
+    pub fn apply(&mut self, event: &OpenCodeEvent) -> Option<String> {
+        match event {
+            OpenCodeEvent::Status { session_id, status } => {
+                self.statuses.insert(session_id.clone(), status.clone());
+                Some(session_id.clone())
+            }
+            OpenCodeEvent::QuestionAsked(question) => {
+                self.questions.insert(question.id.clone(), question.clone());
+                Some(question.session_id.clone())
+            }
+            OpenCodeEvent::QuestionResolved { session_id, question_id } => {
+                self.questions.remove(question_id);
+                Some(session_id.clone())
+            }
+            OpenCodeEvent::SessionUpdated { .. } => None,
+        }
+    }
+
+    pub fn pending_questions(&self) -> Vec<PendingQuestion> {
+        self.questions.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_events_are_split_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b": connected\n\ndata: {\"a\"").is_empty());
+        assert_eq!(parser.push(b":1}\n\ndata: x\r\ndata: y\r\n\r\n"), vec!["{\"a\":1}", "x\ny"]);
+
+        // A multi-byte character split between chunks survives
+        let bytes = "data: żółw\n\n".as_bytes();
+        assert!(parser.push(&bytes[..8]).is_empty());
+        assert_eq!(parser.push(&bytes[8..]), vec!["żółw"]);
+    }
+
+    #[test]
+    fn global_events_are_parsed() {
+        let status = r#"{"directory":"/repo","payload":{"type":"session.status","properties":{"sessionID":"ses_1","status":{"type":"busy"}}}}"#;
+        match parse_event(status) {
+            Some(OpenCodeEvent::Status { session_id, status }) => {
+                assert_eq!(session_id, "ses_1");
+                assert!(matches!(status, SessionStatus::Busy));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let asked = r#"{"directory":"/repo","payload":{"type":"question.asked","properties":{"id":"que_1","sessionID":"ses_1","questions":[{"question":"Proceed?","options":[{"label":"Yes"}]}]}}}"#;
+        match parse_event(asked) {
+            Some(OpenCodeEvent::QuestionAsked(q)) => {
+                assert_eq!(q.id, "que_1");
+                assert_eq!(q.directory.as_deref(), Some("/repo"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let created = r#"{"type":"session.created","properties":{"info":{"id":"ses_2","title":"New","directory":"/repo","time":{"created":1,"updated":1}}}}"#;
+        assert!(matches!(parse_event(created), Some(OpenCodeEvent::SessionUpdated { .. })));
+
+        let part = r#"{"directory":"/repo","payload":{"type":"message.part.updated","properties":{}}}"#;
+        assert!(parse_event(part).is_none());
+        assert!(parse_event("not json").is_none());
+    }
+
+    #[test]
+    fn live_state_tracks_statuses_and_questions() {
+        let mut state = LiveState::default();
+        let asked = parse_event(r#"{"type":"question.asked","properties":{"id":"que_1","sessionID":"ses_1","questions":[]}}"#).unwrap();
+        assert_eq!(state.apply(&asked).as_deref(), Some("ses_1"));
+        assert_eq!(state.pending_questions().len(), 1);
+
+        let replied = parse_event(r#"{"type":"question.replied","properties":{"sessionID":"ses_1","requestID":"que_1","answers":[]}}"#).unwrap();
+        assert_eq!(state.apply(&replied).as_deref(), Some("ses_1"));
+        assert!(state.pending_questions().is_empty());
+
+        let idle = parse_event(r#"{"type":"session.idle","properties":{"sessionID":"ses_1"}}"#).unwrap();
+        state.apply(&idle);
+        assert!(matches!(state.statuses.get("ses_1"), Some(SessionStatus::Idle)));
+    }
+}
@@ -0,0 +1,205 @@
+//! Finding the pull request an agent session is working on: the session's
+//! repository and branch, and when to look the branch up on GitHub again.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// How often a session's branch is looked up again while it has no PR.
+pub const LOOKUP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Branches PRs are merged into rather than opened from.
+const DEFAULT_BRANCHES: &[&str] = &["main", "master", "develop", "trunk", "HEAD"];
+
+/// A GitHub repository and branch an agent session works on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BranchRef {
+    pub owner: String,
+    pub repo: String,
+    pub branch: String,
+}
+
+impl BranchRef {
+    /// The `owner:branch` form GitHub's `head` filter expects.
+    pub fn head(&self) -> String {
+        format!("{}:{}", self.owner, self.branch)
+    }
+}
+
+/// `(owner, repo)` from a GitHub remote URL or an `owner/repo` slug.
+pub fn parse_github_repo(remote: &str) -> Option<(String, String)> {
+    let remote = remote.trim().trim_end_matches('/').trim_end_matches(".git");
+    let path = if let Some(rest) = remote.strip_prefix("git@github.com:") {
+        rest
+    } else if let Some(index) = remote.find("github.com/") {
+        &remote[index + "github.com/".len()..]
+    } else if remote.contains("://") || remote.contains('@') {
+        return None;
+    } else {
+        remote
+    };
+
+    let mut parts = path.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(owner), Some(repo), None) if !owner.is_empty() && !repo.is_empty() => {
+            Some((owner.to_string(), repo.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// The repository and branch recorded in an agent session's metadata,
+/// falling back to the git checkout in its working directory.
+pub async fn branch_ref(metadata: &serde_json::Value) -> Option<BranchRef> {
+    let dir = metadata_str(metadata, "cwd").or_else(|| metadata_str(metadata, "directory"));
+
+    let (owner, repo) = match metadata_str(metadata, "repository").and_then(parse_github_repo) {
+        Some(found) => found,
+        None => parse_github_repo(&git_output(dir?, &["remote", "get-url", "origin"]).await?)?,
+    };
+    let branch = match metadata_str(metadata, "branch") {
+        Some(branch) => branch.to_string(),
+        None => git_output(dir?, &["rev-parse", "--abbrev-ref", "HEAD"]).await?,
+    };
+    feature_branch(owner, repo, branch)
+}
+
+/// The repository and branch when the session's metadata records both, so
+/// no git checkout has to be read.
+pub fn recorded_branch_ref(metadata: &serde_json::Value) -> Option<BranchRef> {
+    let (owner, repo) = metadata_str(metadata, "repository").and_then(parse_github_repo)?;
+    feature_branch(owner, repo, metadata_str(metadata, "branch")?.to_string())
+}
+
+fn metadata_str<'a>(metadata: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    metadata[key].as_str().filter(|s| !s.is_empty())
+}
+
+fn feature_branch(owner: String, repo: String, branch: String) -> Option<BranchRef> {
+    if DEFAULT_BRANCHES.contains(&branch.as_str()) {
+        return None;
+    }
+    Some(BranchRef { owner, repo, branch })
+}
+
+async fn git_output(dir: &str, args: &[&str]) -> Option<String> {
+    if !tokio::fs::metadata(dir).await.ok()?.is_dir() {
+        return None;
+    }
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[derive(Debug)]
+struct Lookup {
+    branch: Option<BranchRef>,
+    at: Instant,
+    ended: bool,
+}
+
+/// When each session's branch was last looked up, so sessions without a PR
+/// don't cost a GitHub request every polling cycle.
+#[derive(Debug, Default)]
+pub struct LookupTimes {
+    last: HashMap<String, Lookup>,
+}
+
+impl LookupTimes {
+    /// Whether the session's branch should be looked up now. `recorded` is
+    /// the branch in its metadata, if any: a session that moved to another
+    /// branch is looked up right away. A session that ended is looked up
+    /// once more, for a PR opened just before it ended.
+    pub fn due(&self, item_id: &str, recorded: Option<&BranchRef>, ended: bool, now: Instant) -> bool {
+        match self.last.get(item_id) {
+            None => true,
+            Some(last) if ended && last.ended => false,
+            Some(last) => {
+                recorded.is_some_and(|b| last.branch.as_ref() != Some(b))
+                    || now.duration_since(last.at) >= LOOKUP_INTERVAL
+            }
+        }
+    }
+
+    /// Record a lookup, `branch` being what was found for the session.
+    pub fn record(&mut self, item_id: &str, branch: Option<BranchRef>, ended: bool, now: Instant) {
+        self.last.insert(item_id.to_string(), Lookup { branch, at: now, ended });
+    }
+
+    /// Forget sessions that are no longer tracked.
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.last.retain(|id, _| keep(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch(name: &str) -> BranchRef {
+        BranchRef {
+            owner: "me".to_string(),
+            repo: "app".to_string(),
+            branch: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn github_remotes_are_parsed() {
+        let expected = Some(("me".to_string(), "app".to_string()));
+        assert_eq!(parse_github_repo("git@github.com:me/app.git"), expected);
+        assert_eq!(parse_github_repo("https://github.com/me/app"), expected);
+        assert_eq!(parse_github_repo("https://token@github.com/me/app.git/"), expected);
+        assert_eq!(parse_github_repo("me/app"), expected);
+        assert_eq!(parse_github_repo("git@gitlab.com:me/app.git"), None);
+        assert_eq!(parse_github_repo("/home/me/app"), None);
+    }
+
+    #[tokio::test]
+    async fn branch_comes_from_session_metadata() {
+        let metadata = serde_json::json!({"repository": "me/app", "branch": "fix-login", "cwd": "/nonexistent"});
+        assert_eq!(branch_ref(&metadata).await, Some(branch("fix-login")));
+        assert_eq!(recorded_branch_ref(&metadata), Some(branch("fix-login")));
+        assert_eq!(branch_ref(&metadata).await.unwrap().head(), "me:fix-login");
+
+        let default_branch = serde_json::json!({"repository": "me/app", "branch": "main"});
+        assert_eq!(branch_ref(&default_branch).await, None);
+
+        let no_checkout = serde_json::json!({"branch": "fix-login", "cwd": "/nonexistent"});
+        assert_eq!(branch_ref(&no_checkout).await, None);
+        assert_eq!(recorded_branch_ref(&no_checkout), None);
+    }
+
+    #[test]
+    fn lookups_are_throttled_per_session_and_branch() {
+        let mut lookups = LookupTimes::default();
+        let start = Instant::now();
+        assert!(lookups.due("s1", Some(&branch("a")), false, start));
+
+        lookups.record("s1", Some(branch("a")), false, start);
+        let later = start + Duration::from_secs(60);
+        assert!(!lookups.due("s1", Some(&branch("a")), false, later));
+        assert!(!lookups.due("s1", None, false, later));
+        assert!(lookups.due("s1", Some(&branch("b")), false, later));
+        assert!(lookups.due("s1", Some(&branch("a")), false, start + LOOKUP_INTERVAL));
+        assert!(lookups.due("s2", Some(&branch("a")), false, start));
+
+        lookups.retain(|id| id != "s1");
+        assert!(lookups.due("s1", Some(&branch("a")), false, start));
+    }
+
+    #[test]
+    fn ended_sessions_are_looked_up_once_more() {
+        let mut lookups = LookupTimes::default();
+        let start = Instant::now();
+        lookups.record("s1", None, false, start);
+        assert!(lookups.due("s1", None, true, start + LOOKUP_INTERVAL));
+
+        lookups.record("s1", None, true, start + LOOKUP_INTERVAL);
+        assert!(!lookups.due("s1", None, true, start + 3 * LOOKUP_INTERVAL));
+        // Resumed
+        assert!(lookups.due("s1", None, false, start + 3 * LOOKUP_INTERVAL));
+    }
+}
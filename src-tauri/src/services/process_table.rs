@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Source of running-process information, abstracted so process detection
+/// can be unit-tested with fake process tables.
+pub trait ProcessTable {
+    /// Working directories of all running processes for `program`.
+    fn cwds_of(&self, program: &str) -> HashSet<String>;
+}
+
+/// Uses `lsof` to inspect the cwd of processes. Used on macOS, which has no `/proc`.
+pub struct LsofProcessTable;
+
+impl ProcessTable for LsofProcessTable {
+    fn cwds_of(&self, program: &str) -> HashSet<String> {
+        let output = Command::new("lsof")
+            .args(["-a", "-d", "cwd", "-c", program, "-Fn"])
+            .output();
+
+        match output {
+            Ok(o) if o.status.success() => parse_lsof_cwds(&String::from_utf8_lossy(&o.stdout)),
+            _ => HashSet::new(),
+        }
+    }
+}
+
+/// Parse `lsof -Fn` output, where each cwd is a line prefixed with `n`.
+fn parse_lsof_cwds(stdout: &str) -> HashSet<String> {
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix('n'))
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Reads `<root>/<pid>/{comm,cmdline,cwd}` on Linux. Fast and needs no external tools.
+pub struct ProcFsProcessTable {
+    root: PathBuf,
+}
+
+impl ProcFsProcessTable {
+    pub fn new() -> Self {
+        Self::with_root("/proc")
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Default for ProcFsProcessTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessTable for ProcFsProcessTable {
+    fn cwds_of(&self, program: &str) -> HashSet<String> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(e) => e,
+            Err(_) => return HashSet::new(),
+        };
+
+        let mut cwds = HashSet::new();
+        for entry in entries.flatten() {
+            let is_pid = entry
+                .file_name()
+                .to_str()
+                .map(|n| n.chars().all(|c| c.is_ascii_digit()))
+                .unwrap_or(false);
+            if !is_pid {
+                continue;
+            }
+
+            let dir = entry.path();
+            let comm = fs::read_to_string(dir.join("comm")).unwrap_or_default();
+            let cmdline: Vec<String> = fs::read(dir.join("cmdline"))
+                .map(|bytes| {
+                    bytes
+                        .split(|b| *b == 0)
+                        .filter(|arg| !arg.is_empty())
+                        .map(|arg| String::from_utf8_lossy(arg).to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            if !matches_program(comm.trim(), &cmdline, program) {
+                continue;
+            }
+
+            // Processes owned by other users can't be inspected; skip them.
+            if let Ok(cwd) = fs::read_link(dir.join("cwd")) {
+                cwds.insert(cwd.to_string_lossy().to_string());
+            }
+        }
+        cwds
+    }
+}
+
+/// Whether a process runs `program`, either directly (its command name
+/// starts with it, matching `lsof -c`) or as a script launched by an
+/// interpreter, e.g. `node /usr/local/bin/copilot`.
+fn matches_program(comm: &str, cmdline: &[String], program: &str) -> bool {
+    if comm.starts_with(program) {
+        return true;
+    }
+    cmdline
+        .iter()
+        .take(2)
+        .any(|arg| Path::new(arg).file_name().and_then(|n| n.to_str()) == Some(program))
+}
+
+/// The process table for the current platform: `/proc` where available,
+/// otherwise `lsof`.
+pub fn default_process_table() -> Box<dyn ProcessTable> {
+    if cfg!(target_os = "linux") && Path::new("/proc").is_dir() {
+        Box::new(ProcFsProcessTable::new())
+    } else {
+        Box::new(LsofProcessTable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_proc_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("itl-proc-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[cfg(unix)]
+    fn add_process(root: &Path, pid: u32, comm: &str, cmdline: &[&str], cwd: &str) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+        fs::write(dir.join("cmdline"), cmdline.join("\0") + "\0").unwrap();
+        std::os::unix::fs::symlink(cwd, dir.join("cwd")).unwrap();
+    }
+
+    #[test]
+    fn lsof_output_is_parsed() {
+        let stdout = "p123\nfcwd\nn/Users/me/project\np456\nfcwd\nn/tmp/other\n";
+        let cwds = parse_lsof_cwds(stdout);
+        assert_eq!(cwds.len(), 2);
+        assert!(cwds.contains("/Users/me/project"));
+        assert!(cwds.contains("/tmp/other"));
+    }
+
+    #[test]
+    fn program_matches_comm_or_interpreter_script() {
+        assert!(matches_program("copilot", &[], "copilot"));
+        assert!(matches_program(
+            "node",
+            &["node".to_string(), "/usr/local/bin/copilot".to_string()],
+            "copilot"
+        ));
+        assert!(!matches_program(
+            "node",
+            &["node".to_string(), "server.js".to_string(), "copilot".to_string()],
+            "copilot"
+        ));
+        assert!(!matches_program("bash", &["bash".to_string()], "copilot"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn procfs_table_finds_matching_cwds() {
+        let root = fake_proc_root();
+        add_process(&root, 100, "copilot", &["copilot"], "/home/me/a");
+        add_process(&root, 101, "node", &["node", "/opt/bin/copilot", "--resume"], "/home/me/b");
+        add_process(&root, 102, "zsh", &["-zsh"], "/home/me/c");
+        fs::create_dir_all(root.join("self")).unwrap();
+
+        let cwds = ProcFsProcessTable::with_root(&root).cwds_of("copilot");
+        assert_eq!(
+            cwds,
+            HashSet::from(["/home/me/a".to_string(), "/home/me/b".to_string()])
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn procfs_table_missing_root_is_empty() {
+        let table = ProcFsProcessTable::with_root("/nonexistent/proc");
+        assert!(table.cwds_of("copilot").is_empty());
+    }
+}
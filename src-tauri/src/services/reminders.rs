@@ -0,0 +1,195 @@
+//! Reminders for items left waiting on the user: how long an item has been
+//! in its status, when the next reminder is due and what it says.
+
+use crate::db::{Item, ItemReminder};
+use crate::services::notifications::Category;
+use crate::services::rules::StatusChange;
+use chrono::{DateTime, Duration, Utc};
+
+/// Statuses that keep reminding while an item stays in them.
+pub const REMINDER_STATUSES: [&str; 3] = ["input_needed", "approved", "updated"];
+
+/// Default minutes between reminders; 0 turns reminders off.
+pub const DEFAULT_INTERVAL_MINUTES: i64 = 15;
+
+/// Sound reminders play when the louder sound is switched on.
+#[cfg(target_os = "macos")]
+pub const LOUD_SOUND: &str = "Sosumi";
+#[cfg(not(target_os = "macos"))]
+pub const LOUD_SOUND: &str = "alarm-clock-elapsed";
+
+/// A reminder that is due: the how-manieth since the item entered its status
+/// and how long it has been waiting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub count: i64,
+    pub waiting: Duration,
+}
+
+/// When the item entered its current status, from its status history;
+/// items without a recorded change count from their creation.
+pub fn status_entered_at(item: &Item, history: &[StatusChange]) -> Option<DateTime<Utc>> {
+    let at = history
+        .iter()
+        .rev()
+        .find(|change| change.to == item.status)
+        .map(|change| change.at.as_str())
+        .unwrap_or(&item.created_at);
+    parse(at)
+}
+
+/// The reminder due at `now` for an item that entered its status at
+/// `entered_at`, if any. Reminders come `interval` after entering the
+/// status or the last reminder, and not while the item is snoozed.
+pub fn due(
+    entered_at: DateTime<Utc>,
+    state: Option<&ItemReminder>,
+    interval: Duration,
+    now: DateTime<Utc>,
+) -> Option<Reminder> {
+    if interval <= Duration::zero() {
+        return None;
+    }
+    let snoozed_until = state.and_then(|s| s.snoozed_until.as_deref()).and_then(parse);
+    if snoozed_until.is_some_and(|until| until > now) {
+        return None;
+    }
+
+    // Reminders sent before the item last entered its status don't count
+    let last = state
+        .and_then(|s| Some((s.reminded_at.as_deref().and_then(parse)?, s.count)))
+        .filter(|(at, _)| *at >= entered_at);
+    let (since, count) = last.unwrap_or((entered_at, 0));
+    (now - since >= interval).then(|| Reminder {
+        count: count + 1,
+        waiting: now - entered_at,
+    })
+}
+
+/// Whether an item was already reminded about since entering its status
+/// and isn't snoozed, which keeps the tray badge pulsing.
+pub fn is_overdue(entered_at: DateTime<Utc>, state: Option<&ItemReminder>, now: DateTime<Utc>) -> bool {
+    let state = match state {
+        Some(state) => state,
+        None => return false,
+    };
+    let snoozed = state.snoozed_until.as_deref().and_then(parse).is_some_and(|until| until > now);
+    let reminded = state.reminded_at.as_deref().and_then(parse).is_some_and(|at| at >= entered_at);
+    reminded && !snoozed
+}
+
+/// The notification category whose settings decide on reminders for an
+/// item in `status`.
+pub fn category(item_type: &str, status: &str) -> Category {
+    match (item_type, status) {
+        (_, "approved") => Category::PrApproved,
+        ("slack_thread", _) => Category::SlackReply,
+        (_, "updated") => Category::PrReview,
+        _ => Category::InputNeeded,
+    }
+}
+
+/// Notification text for a reminder about an item in `status`.
+pub fn body(status: &str, waiting: Duration) -> String {
+    let what = match status {
+        "input_needed" => "Still waiting for your input",
+        "approved" => "Approved and waiting to be merged",
+        _ => "Still has unread activity",
+    };
+    format!("{} ({})", what, format_waiting(waiting))
+}
+
+fn format_waiting(waiting: Duration) -> String {
+    let minutes = waiting.num_minutes().max(1);
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    }
+}
+
+fn parse(at: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(at).ok().map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z").unwrap().with_timezone(&Utc) + Duration::minutes(minutes)
+    }
+
+    fn state(reminded_at: Option<i64>, count: i64, snoozed_until: Option<i64>) -> ItemReminder {
+        ItemReminder {
+            item_id: "item-1".to_string(),
+            reminded_at: reminded_at.map(|m| at(m).to_rfc3339()),
+            count,
+            snoozed_until: snoozed_until.map(|m| at(m).to_rfc3339()),
+        }
+    }
+
+    #[test]
+    fn reminders_repeat_every_interval() {
+        let interval = Duration::minutes(15);
+        assert_eq!(due(at(0), None, interval, at(10)), None);
+        assert_eq!(
+            due(at(0), None, interval, at(15)),
+            Some(Reminder { count: 1, waiting: Duration::minutes(15) })
+        );
+        assert_eq!(due(at(0), Some(&state(Some(15), 1, None)), interval, at(20)), None);
+        assert_eq!(due(at(0), Some(&state(Some(15), 1, None)), interval, at(31)).map(|r| r.count), Some(2));
+        assert_eq!(due(at(0), None, Duration::zero(), at(60)), None);
+    }
+
+    #[test]
+    fn reminders_restart_with_the_status_and_wait_out_snoozes() {
+        let interval = Duration::minutes(15);
+        // Reminded about an earlier stint in the status
+        assert_eq!(due(at(30), Some(&state(Some(20), 3, None)), interval, at(40)), None);
+        assert_eq!(due(at(30), Some(&state(Some(20), 3, None)), interval, at(45)).map(|r| r.count), Some(1));
+
+        assert_eq!(due(at(0), Some(&state(None, 0, Some(60))), interval, at(50)), None);
+        assert_eq!(due(at(0), Some(&state(None, 0, Some(60))), interval, at(60)).map(|r| r.count), Some(1));
+    }
+
+    #[test]
+    fn reminded_items_are_overdue_until_snoozed() {
+        assert!(!is_overdue(at(0), None, at(20)));
+        assert!(is_overdue(at(0), Some(&state(Some(15), 1, None)), at(20)));
+        assert!(!is_overdue(at(30), Some(&state(Some(15), 1, None)), at(40)));
+        assert!(!is_overdue(at(0), Some(&state(Some(15), 1, Some(50))), at(20)));
+        assert!(is_overdue(at(0), Some(&state(Some(15), 1, Some(50))), at(50)));
+    }
+
+    #[test]
+    fn time_in_status_comes_from_the_history() {
+        let item = Item {
+            id: "item-1".to_string(),
+            item_type: "claude_session".to_string(),
+            title: "Fix the build".to_string(),
+            url: None,
+            status: "input_needed".to_string(),
+            previous_status: Some("in_progress".to_string()),
+            metadata: "{}".to_string(),
+            last_checked_at: None,
+            last_updated_at: None,
+            created_at: at(0).to_rfc3339(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        };
+        let change = |to: &str, minutes| StatusChange {
+            item_id: item.id.clone(),
+            from: "in_progress".to_string(),
+            to: to.to_string(),
+            at: at(minutes).to_rfc3339(),
+        };
+
+        assert_eq!(status_entered_at(&item, &[]), Some(at(0)));
+        let history = [change("input_needed", 5), change("in_progress", 8), change("input_needed", 12)];
+        assert_eq!(status_entered_at(&item, &history), Some(at(12)));
+        assert_eq!(body("input_needed", Duration::minutes(75)), "Still waiting for your input (1h 15m)");
+    }
+}
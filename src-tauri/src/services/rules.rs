@@ -0,0 +1,380 @@
+//! User-defined rules run on item status transitions: a trigger, optional
+//! conditions and the actions to take when both match.
+
+use crate::db::{Item, ItemEvent};
+use serde::{Deserialize, Serialize};
+
+/// Which transitions a rule reacts to. Unset fields match anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trigger {
+    #[serde(default)]
+    pub item_type: Option<String>,
+    #[serde(default)]
+    pub from_status: Option<String>,
+    #[serde(default)]
+    pub to_status: Option<String>,
+    /// Case-insensitive text looked for in the title and metadata.
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Extra checks on the item once the trigger matched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// A metadata key (or JSON pointer) is present, or equals `equals`.
+    Metadata {
+        key: String,
+        #[serde(default)]
+        equals: Option<serde_json::Value>,
+    },
+    /// The item is or isn't bound to a todo.
+    HasBoundTodos { value: bool },
+    /// Local time is within `[start_hour, end_hour)`; wraps past midnight
+    /// when `end_hour` is smaller.
+    TimeWindow { start_hour: u32, end_hour: u32 },
+}
+
+/// What a rule does. Text fields may use `{title}`, `{type}`, `{status}`,
+/// `{previous_status}` and `{url}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Archive,
+    CompleteBoundTodos,
+    CreateTodo {
+        title: String,
+        /// Bind the new todo to the item.
+        #[serde(default = "default_true")]
+        bind: bool,
+    },
+    Notify {
+        #[serde(default)]
+        title: Option<String>,
+        body: String,
+    },
+    Webhook { url: String },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Action {
+    /// Short description for timelines and dry runs.
+    pub fn describe(&self, change: &StatusChange, item: &Item) -> String {
+        match self {
+            Action::Archive => "archive".to_string(),
+            Action::CompleteBoundTodos => "complete bound todos".to_string(),
+            Action::CreateTodo { title, .. } => format!("create todo \"{}\"", render(title, item, change)),
+            Action::Notify { body, .. } => format!("notify \"{}\"", render(body, item, change)),
+            Action::Webhook { url } => format!("webhook {}", url),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+/// A status change of an item, as published by `Database::update_item_status`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub item_id: String,
+    pub from: String,
+    pub to: String,
+    pub at: String,
+}
+
+/// Facts about the item the conditions need besides the item itself.
+#[derive(Debug, Clone, Default)]
+pub struct MatchContext {
+    pub bound_todos: usize,
+    /// Local hour of the transition, 0–23.
+    pub hour: u32,
+}
+
+impl Rule {
+    pub fn matches(&self, item: &Item, change: &StatusChange, context: &MatchContext) -> bool {
+        self.trigger_matches(item, change) && self.conditions.iter().all(|c| condition_holds(c, item, context))
+    }
+
+    fn trigger_matches(&self, item: &Item, change: &StatusChange) -> bool {
+        let trigger = &self.trigger;
+        let field_matches = |expected: &Option<String>, actual: &str| {
+            expected.as_deref().filter(|e| !e.is_empty()).map(|e| e == actual).unwrap_or(true)
+        };
+        if !field_matches(&trigger.item_type, &item.item_type)
+            || !field_matches(&trigger.from_status, &change.from)
+            || !field_matches(&trigger.to_status, &change.to)
+        {
+            return false;
+        }
+        match trigger.text.as_deref().filter(|t| !t.is_empty()) {
+            Some(text) => {
+                let text = text.to_lowercase();
+                item.title.to_lowercase().contains(&text) || item.metadata.to_lowercase().contains(&text)
+            }
+            None => true,
+        }
+    }
+}
+
+fn condition_holds(condition: &Condition, item: &Item, context: &MatchContext) -> bool {
+    match condition {
+        Condition::Metadata { key, equals } => {
+            let metadata: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_default();
+            let value = if key.starts_with('/') {
+                metadata.pointer(key)
+            } else {
+                metadata.get(key)
+            };
+            match (value, equals) {
+                (None, _) | (Some(serde_json::Value::Null), _) => false,
+                (Some(_), None) => true,
+                (Some(value), Some(expected)) => value == expected || value.as_str() == Some(&expected.to_string()),
+            }
+        }
+        Condition::HasBoundTodos { value } => (context.bound_todos > 0) == *value,
+        Condition::TimeWindow { start_hour, end_hour } => {
+            if start_hour <= end_hour {
+                context.hour >= *start_hour && context.hour < *end_hour
+            } else {
+                context.hour >= *start_hour || context.hour < *end_hour
+            }
+        }
+    }
+}
+
+/// Fill in `{title}`, `{type}`, `{status}`, `{previous_status}` and `{url}`.
+pub fn render(template: &str, item: &Item, change: &StatusChange) -> String {
+    template
+        .replace("{title}", &item.title)
+        .replace("{type}", &item.item_type)
+        .replace("{status}", &change.to)
+        .replace("{previous_status}", &change.from)
+        .replace("{url}", item.url.as_deref().unwrap_or(""))
+}
+
+/// Timeline event kind recording a status change.
+pub const STATUS_EVENT: &str = "status";
+
+/// Timeline message recording a status change.
+pub fn status_event_message(from: &str, to: &str) -> String {
+    format!("{} → {}", from, to)
+}
+
+/// The status change a timeline message records.
+pub fn parse_status_event(item_id: &str, message: &str, at: &str) -> Option<StatusChange> {
+    let (from, to) = message.split_once(" → ")?;
+    Some(StatusChange {
+        item_id: item_id.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        at: at.to_string(),
+    })
+}
+
+/// Status changes recorded in an item's timeline, oldest first.
+pub fn status_history(item_id: &str, events: &[ItemEvent]) -> Vec<StatusChange> {
+    events
+        .iter()
+        .filter(|e| e.kind == STATUS_EVENT)
+        .filter_map(|e| parse_status_event(item_id, &e.message, &e.created_at))
+        .collect()
+}
+
+/// A rule that fired, or would have fired in a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub item_id: String,
+    pub item_title: String,
+    pub from: String,
+    pub to: String,
+    pub at: String,
+    pub actions: Vec<String>,
+}
+
+impl RuleMatch {
+    pub fn new(rule: &Rule, item: &Item, change: &StatusChange) -> Self {
+        RuleMatch {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            item_id: item.id.clone(),
+            item_title: item.title.clone(),
+            from: change.from.clone(),
+            to: change.to.clone(),
+            at: change.at.clone(),
+            actions: rule.actions.iter().map(|a| a.describe(change, item)).collect(),
+        }
+    }
+}
+
+/// Local hour of an RFC 3339 timestamp, for `TimeWindow` conditions.
+pub fn local_hour(at: &str) -> u32 {
+    use chrono::Timelike;
+    chrono::DateTime::parse_from_rfc3339(at)
+        .map(|t| t.with_timezone(&chrono::Local).hour())
+        .unwrap_or_else(|_| chrono::Local::now().hour())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item_type: &str, title: &str, metadata: serde_json::Value) -> Item {
+        Item {
+            id: "item-1".to_string(),
+            item_type: item_type.to_string(),
+            title: title.to_string(),
+            url: Some("https://github.com/me/app/pull/7".to_string()),
+            status: "merged".to_string(),
+            previous_status: None,
+            metadata: metadata.to_string(),
+            last_checked_at: None,
+            last_updated_at: None,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            archived: false,
+            archived_at: None,
+            polling_interval_override: None,
+            checked: false,
+        }
+    }
+
+    fn change(from: &str, to: &str) -> StatusChange {
+        StatusChange {
+            item_id: "item-1".to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            at: "2026-01-01T10:00:00Z".to_string(),
+        }
+    }
+
+    fn rule(trigger: Trigger, conditions: Vec<Condition>) -> Rule {
+        Rule {
+            id: "r1".to_string(),
+            name: "Archive merged PRs".to_string(),
+            enabled: true,
+            trigger,
+            conditions,
+            actions: vec![Action::Archive],
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn trigger_matches_type_transition_and_text() {
+        let pr = item("github_pr", "Fix login redirect", serde_json::json!({"owner": "me"}));
+        let context = MatchContext::default();
+        let merged = rule(
+            Trigger {
+                item_type: Some("github_pr".to_string()),
+                to_status: Some("merged".to_string()),
+                ..Default::default()
+            },
+            vec![],
+        );
+        assert!(merged.matches(&pr, &change("approved", "merged"), &context));
+        assert!(!merged.matches(&pr, &change("merged", "approved"), &context));
+
+        let from_approved = rule(
+            Trigger {
+                from_status: Some("approved".to_string()),
+                text: Some("LOGIN".to_string()),
+                ..Default::default()
+            },
+            vec![],
+        );
+        assert!(from_approved.matches(&pr, &change("approved", "merged"), &context));
+        let other = item("github_pr", "Bump deps", serde_json::json!({"owner": "me"}));
+        assert!(!from_approved.matches(&other, &change("approved", "merged"), &context));
+        assert!(from_approved.matches(
+            &item("github_pr", "Bump deps", serde_json::json!({"branch": "login-fix"})),
+            &change("approved", "merged"),
+            &context
+        ));
+    }
+
+    #[test]
+    fn conditions_must_all_hold() {
+        let pr = item("github_pr", "Fix", serde_json::json!({"owner": "me", "ci": {"status": "passed"}, "draft": false}));
+        let transition = change("in_progress", "approved");
+        let with = |conditions| rule(Trigger::default(), conditions);
+        let context = MatchContext { bound_todos: 1, hour: 23 };
+
+        assert!(with(vec![Condition::Metadata { key: "owner".to_string(), equals: None }]).matches(&pr, &transition, &context));
+        assert!(with(vec![Condition::Metadata {
+            key: "/ci/status".to_string(),
+            equals: Some(serde_json::json!("passed"))
+        }])
+        .matches(&pr, &transition, &context));
+        assert!(!with(vec![Condition::Metadata {
+            key: "draft".to_string(),
+            equals: Some(serde_json::json!(true))
+        }])
+        .matches(&pr, &transition, &context));
+        assert!(!with(vec![Condition::HasBoundTodos { value: false }]).matches(&pr, &transition, &context));
+        assert!(with(vec![Condition::TimeWindow { start_hour: 22, end_hour: 6 }]).matches(&pr, &transition, &context));
+        assert!(!with(vec![Condition::TimeWindow { start_hour: 9, end_hour: 18 }]).matches(&pr, &transition, &context));
+    }
+
+    #[test]
+    fn rules_deserialize_with_defaults() {
+        let rule: Rule = serde_json::from_value(serde_json::json!({
+            "name": "Todo for failed actions",
+            "trigger": {"item_type": "github_action", "to_status": "failed"},
+            "actions": [
+                {"type": "create_todo", "title": "Fix {title}"},
+                {"type": "notify", "body": "{title} is {status}"},
+                {"type": "webhook", "url": "https://example.com/hook"}
+            ]
+        }))
+        .unwrap();
+        assert!(rule.enabled);
+        assert_eq!(rule.actions[0], Action::CreateTodo { title: "Fix {title}".to_string(), bind: true });
+
+        let action = item("github_action", "CI #12", serde_json::json!({}));
+        let described: Vec<String> = rule
+            .actions
+            .iter()
+            .map(|a| a.describe(&change("in_progress", "failed"), &action))
+            .collect();
+        assert_eq!(
+            described,
+            vec!["create todo \"Fix CI #12\"", "notify \"CI #12 is failed\"", "webhook https://example.com/hook"]
+        );
+    }
+
+    #[test]
+    fn history_is_read_from_status_events() {
+        let event = |id, kind: &str, message: &str| ItemEvent {
+            id,
+            item_id: "item-1".to_string(),
+            kind: kind.to_string(),
+            message: message.to_string(),
+            created_at: "2026-01-01T10:00:00Z".to_string(),
+        };
+        let events = vec![
+            event(1, STATUS_EVENT, &status_event_message("waiting", "in_progress")),
+            event(2, "pr_linked", "Opened PR #7"),
+            event(3, STATUS_EVENT, &status_event_message("in_progress", "merged")),
+        ];
+        let history = status_history("item-1", &events);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1], change("in_progress", "merged"));
+    }
+}
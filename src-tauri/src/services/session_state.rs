@@ -0,0 +1,118 @@
+//! Parser state for session logs that are read incrementally: persisted
+//! between polls together with the offset read up to, and started over when
+//! it was saved by an older version of its parser.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The per-agent state folded from a session log. `Default` is the state
+/// before the first line, with `version` set to `VERSION`.
+pub trait IncrementalState: Default + Serialize + DeserializeOwned {
+    /// Bumped when the state gains fields derived from the log, so states
+    /// persisted by older versions are re-parsed from the start of the log.
+    const VERSION: u32;
+
+    fn version(&self) -> u32;
+
+    /// Bytes of the log consumed so far. Stored in its own column rather
+    /// than in the serialized state.
+    fn offset(&self) -> u64;
+
+    fn set_offset(&mut self, offset: u64);
+}
+
+/// The state persisted as `(offset, json)`, or a fresh one when there is
+/// none or it can't be used.
+pub fn restore<S: IncrementalState>(saved: Option<(u64, String)>) -> S {
+    saved
+        .and_then(|(offset, json)| {
+            let mut state = serde_json::from_str::<S>(&json).ok()?;
+            state.set_offset(offset);
+            Some(state)
+        })
+        .filter(|state| state.version() == S::VERSION)
+        .unwrap_or_default()
+}
+
+/// Restore the persisted state, fold in what `read_new` finds appended to
+/// the log since, and hand the result to `save` if anything was read.
+pub fn refresh<S: IncrementalState>(
+    saved: Option<(u64, String)>,
+    read_new: impl FnOnce(&mut S) -> bool,
+    save: impl FnOnce(u64, &str),
+) -> S {
+    let mut state = restore::<S>(saved);
+    if read_new(&mut state) {
+        if let Ok(json) = serde_json::to_string(&state) {
+            save(state.offset(), &json);
+        }
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State {
+        #[serde(skip)]
+        offset: u64,
+        lines: u32,
+        #[serde(default)]
+        version: u32,
+    }
+
+    impl Default for State {
+        fn default() -> Self {
+            State { offset: 0, lines: 0, version: Self::VERSION }
+        }
+    }
+
+    impl IncrementalState for State {
+        const VERSION: u32 = 2;
+
+        fn version(&self) -> u32 {
+            self.version
+        }
+
+        fn offset(&self) -> u64 {
+            self.offset
+        }
+
+        fn set_offset(&mut self, offset: u64) {
+            self.offset = offset;
+        }
+    }
+
+    #[test]
+    fn states_resume_at_their_offset_unless_outdated() {
+        let current = restore::<State>(Some((120, r#"{"lines":3,"version":2}"#.to_string())));
+        assert_eq!(current, State { offset: 120, lines: 3, version: 2 });
+
+        assert_eq!(restore::<State>(Some((120, r#"{"lines":3}"#.to_string()))), State::default());
+        assert_eq!(restore::<State>(Some((120, "not json".to_string()))), State::default());
+        assert_eq!(restore::<State>(None), State::default());
+    }
+
+    #[test]
+    fn states_are_saved_only_when_something_was_read() {
+        let mut saved = None;
+        let state = refresh::<State>(
+            None,
+            |state| {
+                state.lines += 2;
+                state.offset = 40;
+                true
+            },
+            |offset, json| saved = Some((offset, json.to_string())),
+        );
+        assert_eq!(state.lines, 2);
+        assert_eq!(saved, Some((40, r#"{"lines":2,"version":2}"#.to_string())));
+
+        let mut saved_again = false;
+        refresh::<State>(saved, |_| false, |_, _| saved_again = true);
+        assert!(!saved_again);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::db::{Database, Item};
+use crate::services::{copilot_cli, opencode, opencode_control};
+use anyhow::Result;
+use serde::Serialize;
+
+/// Max characters kept for tool arguments and results.
+const MAX_TOOL_TEXT_LEN: usize = 500;
+
+/// Number of turns returned per page when no limit is given.
+pub const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// One step the assistant took within a turn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptStep {
+    Text {
+        text: String,
+        timestamp: Option<String>,
+    },
+    ToolCall {
+        name: String,
+        arguments: Option<String>,
+        result: Option<String>,
+        timestamp: Option<String>,
+    },
+}
+
+/// A user message and everything the assistant did in response.
+/// Turns before the first user message have no `user_message`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TranscriptTurn {
+    pub user_message: Option<String>,
+    pub timestamp: Option<String>,
+    pub steps: Vec<TranscriptStep>,
+}
+
+/// A page of turns in chronological order.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptPage {
+    pub turns: Vec<TranscriptTurn>,
+    pub total_turns: usize,
+    /// Pass as `before` to fetch the next (older) page; `None` on the first turn.
+    pub next_before: Option<usize>,
+}
+
+/// Truncate tool arguments or results to `MAX_TOOL_TEXT_LEN` chars.
+pub fn truncate_tool_text(s: &str) -> String {
+    if s.chars().count() <= MAX_TOOL_TEXT_LEN {
+        s.to_string()
+    } else {
+        let truncated: String = s.chars().take(MAX_TOOL_TEXT_LEN).collect();
+        format!("{}…", truncated)
+    }
+}
+
+/// Render a JSON value as tool text: strings as-is, everything else as JSON.
+pub fn tool_value_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(truncate_tool_text(s)),
+        other => Some(truncate_tool_text(&other.to_string())),
+    }
+}
+
+/// Select a page of turns counting back from the newest.
+/// `before` is an exclusive turn index; `None` starts from the latest turn.
+pub fn paginate(turns: Vec<TranscriptTurn>, before: Option<usize>, limit: usize) -> TranscriptPage {
+    let total_turns = turns.len();
+    let end = before.unwrap_or(total_turns).min(total_turns);
+    let start = end.saturating_sub(limit.max(1));
+    TranscriptPage {
+        turns: turns.into_iter().skip(start).take(end - start).collect(),
+        total_turns,
+        next_before: if start > 0 { Some(start) } else { None },
+    }
+}
+
+/// Load the transcript of an agent session item (Copilot CLI or OpenCode).
+pub async fn load_transcript(
+    db: &Database,
+    item: &Item,
+    before: Option<usize>,
+    limit: Option<usize>,
+) -> Result<TranscriptPage> {
+    let metadata: serde_json::Value = serde_json::from_str(&item.metadata)?;
+
+    let turns = match item.item_type.as_str() {
+        "copilot_agent" | "cli_session" => {
+            let session_id = metadata["copilot_session_id"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Item is not linked to a Copilot CLI session"))?;
+            copilot_cli::read_transcript(session_id)
+        }
+        "opencode_session" => {
+            let session_id = metadata["session_id"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing session_id in opencode_session metadata"))?;
+            let server = opencode_control::server_endpoint(db, opencode_control::server_name(&metadata))?;
+            let messages = opencode::get_session_messages(&server.base_url, &server.password, session_id).await?;
+            opencode::transcript_from_messages(&messages)
+        }
+        other => return Err(anyhow::anyhow!("Transcripts are not available for {} items", other)),
+    };
+
+    Ok(paginate(turns, before, limit.unwrap_or(DEFAULT_PAGE_SIZE)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turns(n: usize) -> Vec<TranscriptTurn> {
+        (0..n)
+            .map(|i| TranscriptTurn {
+                user_message: Some(format!("message {}", i)),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_page_is_newest_turns() {
+        let page = paginate(turns(25), None, 10);
+        assert_eq!(page.total_turns, 25);
+        assert_eq!(page.turns.len(), 10);
+        assert_eq!(page.turns[0].user_message.as_deref(), Some("message 15"));
+        assert_eq!(page.turns[9].user_message.as_deref(), Some("message 24"));
+        assert_eq!(page.next_before, Some(15));
+    }
+
+    #[test]
+    fn last_page_has_no_next() {
+        let page = paginate(turns(25), Some(5), 10);
+        assert_eq!(page.turns.len(), 5);
+        assert_eq!(page.turns[0].user_message.as_deref(), Some("message 0"));
+        assert_eq!(page.next_before, None);
+
+        let empty = paginate(Vec::new(), None, 10);
+        assert!(empty.turns.is_empty());
+        assert_eq!(empty.next_before, None);
+    }
+
+    #[test]
+    fn long_tool_text_is_truncated() {
+        let long = "x".repeat(MAX_TOOL_TEXT_LEN + 50);
+        let text = truncate_tool_text(&long);
+        assert_eq!(text.chars().count(), MAX_TOOL_TEXT_LEN + 1);
+        assert!(text.ends_with('…'));
+        assert_eq!(tool_value_text(&serde_json::json!({"a": 1})).as_deref(), Some("{\"a\":1}"));
+        assert_eq!(tool_value_text(&serde_json::Value::Null), None);
+    }
+}
@@ -0,0 +1,222 @@
+//! Outgoing webhooks: which status changes a webhook wants, the JSON it
+//! gets, how it is signed and when a failed delivery is tried again.
+
+use crate::db::Item;
+use crate::services::rules::StatusChange;
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+/// Waits before each retry of a failed delivery; after the last one the
+/// delivery is given up.
+pub const RETRY_DELAYS: &[Duration] = &[
+    Duration::from_secs(30),
+    Duration::from_secs(2 * 60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(6 * 60 * 60),
+];
+
+/// How long a receiver may take to answer.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Header carrying the `sha256=<hex>` HMAC of the body, when a secret is set.
+pub const SIGNATURE_HEADER: &str = "X-Loop-Signature-256";
+
+/// Name of a status change as webhook filters see it: `<type>:<status>`.
+pub fn event_name(item_type: &str, status: &str) -> String {
+    format!("{}:{}", item_type, status)
+}
+
+/// Whether a webhook with `filters` wants the event. A filter is a status
+/// (`input_needed`), a type and status (`github_action:failed`), a type with
+/// any status (`github_pr:*`) or `*`. No filters means every event.
+pub fn filter_matches(filters: &[String], item_type: &str, status: &str) -> bool {
+    filters.is_empty()
+        || filters.iter().map(|f| f.trim()).any(|filter| match filter.split_once(':') {
+            Some((t, s)) => (t == "*" || t == item_type) && (s == "*" || s == status),
+            None => filter == "*" || filter == status,
+        })
+}
+
+/// The JSON body sent for a status change.
+pub fn payload(item: &Item, change: &StatusChange) -> serde_json::Value {
+    serde_json::json!({
+        "event": event_name(&item.item_type, &change.to),
+        "item_id": item.id,
+        "item_type": item.item_type,
+        "title": item.title,
+        "url": item.url,
+        "from": change.from,
+        "to": change.to,
+        "at": change.at,
+        "item": item,
+    })
+}
+
+/// `sha256=<hex>` HMAC-SHA256 of `body` under `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", digest)
+}
+
+/// Whether `signature` (`sha256=<hex>`) is the HMAC-SHA256 of `body` under
+/// `secret`, compared in constant time.
+pub fn verify(secret: &str, body: &[u8], signature: &str) -> bool {
+    let digest = match signature.strip_prefix("sha256=").and_then(decode_hex) {
+        Some(d) => d,
+        None => return false,
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.verify_slice(&digest).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let pairs = hex.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// When to try a delivery again after `attempts` failed tries; `None` once
+/// the retries are used up.
+pub fn retry_delay(attempts: i64) -> Option<Duration> {
+    usize::try_from(attempts - 1).ok().and_then(|i| RETRY_DELAYS.get(i)).copied()
+}
+
+/// POST `body` to `url`, signed with `secret` when one is set. Returns the
+/// response status; only transport failures are errors.
+pub async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    secret: Option<&str>,
+    delivery_id: i64,
+    event: &str,
+    body: &str,
+) -> Result<u16> {
+    let mut request = client
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "in-the-loop")
+        .header("X-Loop-Event", event)
+        .header("X-Loop-Delivery", delivery_id.to_string())
+        .body(body.to_string());
+    if let Some(secret) = secret.filter(|s| !s.is_empty()) {
+        request = request.header(SIGNATURE_HEADER, sign(secret, body.as_bytes()));
+    }
+
+    Ok(request.send().await?.status().as_u16())
+}
+
+/// Whether a receiver's answer means the delivery arrived.
+pub fn is_delivered(status: u16) -> bool {
+    (200..300).contains(&status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn filters_match_status_and_type() {
+        let filters = |f: &[&str]| f.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(filter_matches(&[], "github_pr", "approved"));
+        assert!(filter_matches(&filters(&["input_needed"]), "claude_session", "input_needed"));
+        assert!(!filter_matches(&filters(&["input_needed"]), "claude_session", "completed"));
+        assert!(filter_matches(&filters(&["github_action:failed"]), "github_action", "failed"));
+        assert!(!filter_matches(&filters(&["github_action:failed"]), "github_pr", "failed"));
+        assert!(filter_matches(&filters(&["merged", "github_pr:*"]), "github_pr", "closed"));
+        assert!(filter_matches(&filters(&["*:failed"]), "github_pr", "failed"));
+    }
+
+    #[test]
+    fn signatures_are_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signatures_are_verified() {
+        let signature = sign("s3cret", b"{}");
+        assert!(verify("s3cret", b"{}", &signature));
+        assert!(!verify("other", b"{}", &signature));
+        assert!(!verify("s3cret", b"{ }", &signature));
+        assert!(!verify("s3cret", b"{}", signature.trim_start_matches("sha256=")));
+        assert!(!verify("s3cret", b"{}", "sha256=zz"));
+        assert!(!verify("s3cret", b"{}", &signature[..signature.len() - 1]));
+    }
+
+    #[test]
+    fn retries_back_off_then_stop() {
+        assert_eq!(retry_delay(1), Some(Duration::from_secs(30)));
+        assert_eq!(retry_delay(5), Some(Duration::from_secs(6 * 60 * 60)));
+        assert_eq!(retry_delay(6), None);
+        assert_eq!(retry_delay(0), None);
+    }
+
+    /// Headers and body of each request a local receiver got.
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+    async fn receiver(status: StatusCode) -> (String, Received) {
+        let received: Received = Arc::default();
+        let log = received.clone();
+        let app = axum::Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| {
+                let log = log.clone();
+                async move {
+                    log.lock().unwrap().push((headers, body));
+                    status
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed() {
+        let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+        let body = r#"{"event":"github_action:failed"}"#;
+
+        let status = deliver(&reqwest::Client::new(), &url, Some("s3cret"), 7, "github_action:failed", body)
+            .await
+            .unwrap();
+        assert_eq!(status, 204);
+
+        let received = received.lock().unwrap();
+        let (headers, got) = &received[0];
+        assert_eq!(got, body);
+        assert_eq!(headers["x-loop-event"], "github_action:failed");
+        assert_eq!(headers["x-loop-delivery"], "7");
+        assert_eq!(headers[SIGNATURE_HEADER.to_lowercase().as_str()], sign("s3cret", body.as_bytes()).as_str());
+    }
+
+    #[tokio::test]
+    async fn error_responses_are_not_delivered() {
+        let (url, received) = receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        let status = deliver(&reqwest::Client::new(), &url, None, 1, "github_pr:merged", "{}").await.unwrap();
+        assert!(!is_delivered(status));
+        assert!(!received.lock().unwrap()[0].0.contains_key("x-loop-signature-256"));
+
+        let unreachable = deliver(&reqwest::Client::new(), "http://127.0.0.1:1/hook", None, 2, "github_pr:merged", "{}").await;
+        assert!(unreachable.is_err());
+    }
+}
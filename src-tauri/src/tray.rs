@@ -1,5 +1,8 @@
 use crate::db::{Database, Item};
-use std::sync::Arc;
+use crate::services::agent_logs;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{
     image::Image,
     menu::{Menu, MenuItem, PredefinedMenuItem},
@@ -9,6 +12,14 @@ use tauri::{
 
 pub const TRAY_ID: &str = "main-tray";
 
+/// Whether the badge is pulsing for items overdue for the user's attention.
+static PULSING: AtomicBool = AtomicBool::new(false);
+/// Bumped for every pulse loop started; older loops stop when they see it.
+static PULSE_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// The badge text, for the pulse to blink and restore.
+static BADGE: Mutex<String> = Mutex::new(String::new());
+const PULSE_INTERVAL: Duration = Duration::from_millis(800);
+
 fn status_emoji(status: &str) -> &'static str {
     match status {
         "waiting" => "\u{23F3}",
@@ -19,6 +30,7 @@ fn status_emoji(status: &str) -> &'static str {
         "merged" => "\u{1F7E3}",
         "completed" => "\u{2705}",
         "failed" => "\u{274C}",
+        "lost" => "\u{1F47B}",
         "archived" => "\u{1F4E6}",
         _ => "\u{2753}",
     }
@@ -32,7 +44,7 @@ fn type_label(item_type: &str) -> &'static str {
         "copilot_agent" => "Copilot",
         "cli_session" => "CLI",
         "opencode_session" => "OpenCode",
-        _ => "Item",
+        other => agent_logs::rules_for(other).map(|agent| agent.label).unwrap_or("Item"),
     }
 }
 
@@ -148,10 +160,40 @@ pub fn update_tray_badge(app_handle: &AppHandle, actionable_count: usize) {
         } else {
             String::new()
         };
+        *BADGE.lock().unwrap() = title.clone();
         let _ = tray.set_title(Some(title.as_str()));
     }
 }
 
+/// Start or stop blinking a marker next to the badge.
+pub fn set_badge_pulsing(app_handle: &AppHandle, pulsing: bool) {
+    let was_pulsing = PULSING.swap(pulsing, Ordering::SeqCst);
+    if !pulsing || was_pulsing {
+        return;
+    }
+    // A loop from before pulsing was last stopped may still be asleep
+    let generation = PULSE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut lit = true;
+        while PULSING.load(Ordering::SeqCst) {
+            if PULSE_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let badge = BADGE.lock().unwrap().clone();
+            let title = if lit { format!("\u{25CF} {}", badge) } else { badge };
+            if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+                let _ = tray.set_title(Some(title.trim_end()));
+            }
+            lit = !lit;
+            tokio::time::sleep(PULSE_INTERVAL).await;
+        }
+        if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+            let _ = tray.set_title(Some(BADGE.lock().unwrap().as_str()));
+        }
+    });
+}
+
 pub fn rebuild_tray_menu(app_handle: &AppHandle, items: &[Item]) {
     if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
         if let Ok(menu) = build_menu(app_handle, items) {
@@ -174,6 +216,7 @@ pub fn refresh_tray(app_handle: &AppHandle, db: &Arc<Database>) {
                     | "approved"
                     | "merged"
                     | "input_needed"
+                    | "lost"
             )
         })
         .count();
@@ -0,0 +1,100 @@
+use crate::db::{Database, Webhook, WebhookDelivery};
+use crate::services::rules::StatusChange;
+use crate::services::webhooks;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the queue is checked for deliveries due for a retry.
+const TICK: Duration = Duration::from_secs(15);
+
+/// Queue a delivery for every webhook that wants a status change, and send
+/// queued deliveries as they come due. The queue lives in the database, so
+/// pending retries carry over a restart.
+pub fn start(db: Arc<Database>) {
+    let mut changes = db.subscribe_status_changes();
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::new();
+        loop {
+            tokio::select! {
+                change = changes.recv() => match change {
+                    Some(change) => {
+                        if let Err(e) = enqueue(&db, &change) {
+                            eprintln!("Failed to queue webhooks for item {}: {}", change.item_id, e);
+                        }
+                    }
+                    None => return,
+                },
+                _ = tokio::time::sleep(TICK) => {}
+            }
+            if let Err(e) = send_due(&db, &client).await {
+                eprintln!("Failed to send webhooks: {}", e);
+            }
+        }
+    });
+}
+
+fn enqueue(db: &Database, change: &StatusChange) -> Result<()> {
+    let webhooks: Vec<Webhook> = db.get_webhooks()?.into_iter().filter(|w| w.enabled).collect();
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+    let item = match db.get_item(&change.item_id)? {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+
+    let event = webhooks::event_name(&item.item_type, &change.to);
+    let payload = webhooks::payload(&item, change).to_string();
+    for webhook in webhooks
+        .iter()
+        .filter(|w| webhooks::filter_matches(&w.events, &item.item_type, &change.to))
+    {
+        db.add_webhook_delivery(&webhook.id, &item.id, &event, &payload)?;
+    }
+    Ok(())
+}
+
+async fn send_due(db: &Database, client: &reqwest::Client) -> Result<()> {
+    let due = db.get_due_webhook_deliveries(&chrono::Utc::now().to_rfc3339())?;
+    if due.is_empty() {
+        return Ok(());
+    }
+    let webhooks: HashMap<String, Webhook> = db.get_webhooks()?.into_iter().map(|w| (w.id.clone(), w)).collect();
+
+    for delivery in due {
+        match webhooks.get(&delivery.webhook_id) {
+            Some(webhook) => send(db, client, webhook, &delivery).await?,
+            None => db.record_webhook_attempt(delivery.id, None, Some("Webhook was removed"), None)?,
+        }
+    }
+    Ok(())
+}
+
+async fn send(db: &Database, client: &reqwest::Client, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<()> {
+    let result = webhooks::deliver(
+        client,
+        &webhook.url,
+        Some(webhook.secret.as_str()),
+        delivery.id,
+        &delivery.event,
+        &delivery.payload,
+    )
+    .await;
+
+    let (status, error) = match result {
+        Ok(status) if webhooks::is_delivered(status) => (Some(status), None),
+        Ok(status) => (Some(status), Some(format!("HTTP {}", status))),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    match error {
+        None => db.record_webhook_attempt(delivery.id, status, None, None),
+        Some(error) => {
+            let next_attempt_at = webhooks::retry_delay(delivery.attempts + 1)
+                .and_then(|delay| chrono::Duration::from_std(delay).ok())
+                .map(|delay| (chrono::Utc::now() + delay).to_rfc3339());
+            db.record_webhook_attempt(delivery.id, status, Some(&error), next_attempt_at.as_deref())
+        }
+    }
+}
@@ -1,14 +1,18 @@
-import { useState, useEffect } from 'react';
+import { useState, useEffect, Fragment } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { Item } from '../types';
+import { Item, ItemLink, ItemReminder } from '../types';
 import { ItemCard } from './ItemCard';
 import { AddItemForm } from './AddItemForm';
+import { NewOpenCodeSession } from './NewOpenCodeSession';
 import { Settings } from './Settings';
 import { UpdatePrompt } from './UpdatePrompt';
 
 export function Dashboard() {
   const [items, setItems] = useState<Item[]>([]);
+  const [links, setLinks] = useState<ItemLink[]>([]);
+  const [mutedIds, setMutedIds] = useState<Set<string>>(new Set());
+  const [reminders, setReminders] = useState<Map<string, ItemReminder>>(new Map());
   const [showSettings, setShowSettings] = useState(false);
   const [filter, setFilter] = useState<Item['type'] | 'all'>('all');
   const [showArchived, setShowArchived] = useState(false);
@@ -45,6 +49,10 @@ export function Dashboard() {
         metadata: typeof item.metadata === 'string' ? JSON.parse(item.metadata) : item.metadata,
       }));
       setItems(parsedItems);
+      setLinks(await invoke('get_item_links'));
+      setMutedIds(new Set(await invoke<string[]>('get_muted_items')));
+      const loadedReminders = await invoke<ItemReminder[]>('get_item_reminders');
+      setReminders(new Map(loadedReminders.map(r => [r.item_id, r])));
     } catch (error) {
       console.error('Failed to load items:', error);
     }
@@ -68,6 +76,29 @@ export function Dashboard() {
     }
   };
 
+  const handleMute = async (id: string, muted: boolean) => {
+    try {
+      await invoke('set_item_muted', { id, muted });
+      setMutedIds(prev => {
+        const next = new Set(prev);
+        if (muted) next.add(id);
+        else next.delete(id);
+        return next;
+      });
+    } catch (error) {
+      console.error('Failed to mute item:', error);
+    }
+  };
+
+  const handleSnooze = async (id: string, minutes: number) => {
+    try {
+      await invoke('snooze_item', { id, minutes });
+      loadItems();
+    } catch (error) {
+      console.error('Failed to snooze item:', error);
+    }
+  };
+
   const handleArchiveClosed = async () => {
     try {
       const count: number = await invoke('archive_closed_items');
@@ -93,12 +124,44 @@ export function Dashboard() {
     ? items 
     : items.filter(item => item.type === filter);
 
+  // Sub-agent sessions are listed under their parent when it is shown too
+  const shownIds = new Set(filteredItems.map(item => item.id));
+  const childrenOf = (parentId: string) =>
+    filteredItems.filter(item => item.metadata?.parent_item_id === parentId);
+  const topLevelItems = filteredItems.filter(item => !shownIds.has(item.metadata?.parent_item_id));
+
+  const itemsById = new Map(items.map(item => [item.id, item]));
+  const linksOf = (itemId: string) =>
+    links.filter(link => link.source_item_id === itemId || link.target_item_id === itemId);
+
+  const renderItem = (item: Item, nested: boolean): React.ReactNode => (
+    <Fragment key={item.id}>
+      <ItemCard
+        item={item}
+        isArchived={showArchived}
+        nested={nested}
+        links={linksOf(item.id)}
+        itemsById={itemsById}
+        muted={mutedIds.has(item.id)}
+        snoozedUntil={reminders.get(item.id)?.snoozed_until}
+        onArchive={handleArchive}
+        onUnarchive={handleUnarchive}
+        onMute={handleMute}
+        onSnooze={handleSnooze}
+      />
+      {childrenOf(item.id).map(child => renderItem(child, true))}
+    </Fragment>
+  );
+
   const typeCounts = {
     all: items.length,
     slack_thread: items.filter(i => i.type === 'slack_thread').length,
     github_action: items.filter(i => i.type === 'github_action').length,
     github_pr: items.filter(i => i.type === 'github_pr').length,
     copilot_agent: items.filter(i => i.type === 'copilot_agent').length,
+    claude_session: items.filter(i => i.type === 'claude_session').length,
+    codex_session: items.filter(i => i.type === 'codex_session').length,
+    gemini_session: items.filter(i => i.type === 'gemini_session').length,
     cli_session: items.filter(i => i.type === 'cli_session').length,
     opencode_session: items.filter(i => i.type === 'opencode_session').length,
   };
@@ -115,6 +178,7 @@ export function Dashboard() {
 
       <UpdatePrompt />
       {!showArchived && <AddItemForm onItemAdded={loadItems} />}
+      {!showArchived && <NewOpenCodeSession onStarted={loadItems} />}
 
       <div className="filter-row">
         <button 
@@ -155,6 +219,30 @@ export function Dashboard() {
             Copilot ({typeCounts.copilot_agent})
           </button>
         )}
+        {typeCounts.claude_session > 0 && (
+          <button 
+            onClick={() => setFilter('claude_session')}
+            className={`filter-chip ${filter === 'claude_session' ? 'active' : ''}`}
+          >
+            Claude ({typeCounts.claude_session})
+          </button>
+        )}
+        {typeCounts.codex_session > 0 && (
+          <button 
+            onClick={() => setFilter('codex_session')}
+            className={`filter-chip ${filter === 'codex_session' ? 'active' : ''}`}
+          >
+            Codex ({typeCounts.codex_session})
+          </button>
+        )}
+        {typeCounts.gemini_session > 0 && (
+          <button 
+            onClick={() => setFilter('gemini_session')}
+            className={`filter-chip ${filter === 'gemini_session' ? 'active' : ''}`}
+          >
+            Gemini ({typeCounts.gemini_session})
+          </button>
+        )}
         {typeCounts.cli_session > 0 && (
           <button 
             onClick={() => setFilter('cli_session')}
@@ -199,15 +287,7 @@ export function Dashboard() {
         </div>
       ) : (
         <div className="item-list">
-          {filteredItems.map(item => (
-            <ItemCard
-              key={item.id}
-              item={item}
-              isArchived={showArchived}
-              onArchive={handleArchive}
-              onUnarchive={handleUnarchive}
-            />
-          ))}
+          {topLevelItems.map(item => renderItem(item, false))}
         </div>
       )}
 
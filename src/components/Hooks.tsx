@@ -0,0 +1,64 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { HookRun, HooksInfo } from '../types';
+
+function runResult(run: HookRun): string {
+  if (run.timed_out) return 'timed out';
+  if (run.exit_code === null) return 'failed';
+  return run.exit_code === 0 ? 'ok' : `exit ${run.exit_code}`;
+}
+
+export function Hooks() {
+  const [info, setInfo] = useState<HooksInfo | null>(null);
+  const [expanded, setExpanded] = useState<number | null>(null);
+
+  const loadHooks = async () => {
+    try {
+      setInfo(await invoke<HooksInfo>('get_hooks'));
+    } catch (e) {
+      console.error('Failed to load hooks:', e);
+    }
+  };
+
+  useEffect(() => {
+    loadHooks();
+  }, []);
+
+  if (!info) return null;
+
+  return (
+    <div className="settings-field">
+      <label>Shell Hooks</label>
+      <div className="server-row">
+        <span className="server-url" title={info.dir}>{info.dir}</span>
+        <button type="button" className="btn-icon" onClick={() => invoke('open_url', { url: info.dir })} title="Open folder">↗</button>
+        <button type="button" className="btn-icon" onClick={loadHooks} title="Refresh">↻</button>
+      </div>
+      <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+        {info.hooks.length > 0
+          ? `Installed: ${info.hooks.join(', ')}`
+          : 'Add executable on-status-change or on-<status> scripts (e.g. on-input-needed); they get the item as JSON on stdin'}
+      </span>
+      {info.runs.length > 0 && (
+        <div className="spend-summary">
+          <span>Recent runs</span>
+          {info.runs.map(run => (
+            <div key={run.id}>
+              <div
+                className="spend-row hook-run"
+                title={run.stderr || 'No stderr output'}
+                onClick={() => setExpanded(expanded === run.id ? null : run.id)}
+              >
+                <span>{run.hook} · {run.old_status} → {run.new_status}</span>
+                <span className={run.exit_code === 0 ? '' : 'hook-run-failed'}>
+                  {runResult(run)} · {new Date(run.started_at).toLocaleTimeString()}
+                </span>
+              </div>
+              {expanded === run.id && run.stderr && <pre className="hook-stderr">{run.stderr}</pre>}
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
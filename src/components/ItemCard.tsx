@@ -1,15 +1,29 @@
 import { useState } from 'react';
-import { Item } from '../types';
+import { Item, ItemLink, OpenCodeQuestion } from '../types';
 import { StatusBadge } from './StatusBadge';
 import { invoke } from '@tauri-apps/api/core';
 import { ContextMenu } from './ContextMenu';
 import { BindPopover } from './BindPopover';
+import { LinkPopover } from './LinkPopover';
+import { QuestionPanel } from './QuestionPanel';
+import { PromptPanel } from './PromptPanel';
+import { TimelinePanel } from './TimelinePanel';
 
 interface ItemCardProps {
   item: Item;
   isArchived: boolean;
+  nested?: boolean;
+  /** Links from and to this item. */
+  links?: ItemLink[];
+  itemsById?: Map<string, Item>;
+  /** Whether notifications about this item are muted. */
+  muted?: boolean;
+  /** When a snooze of this item's reminders ends, if it was snoozed. */
+  snoozedUntil?: string | null;
   onArchive: (id: string) => void;
   onUnarchive: (id: string) => void;
+  onMute?: (id: string, muted: boolean) => void;
+  onSnooze?: (id: string, minutes: number) => void;
 }
 
 function timeAgo(dateInput?: string | number): string {
@@ -38,23 +52,126 @@ function getOpenCodeSessionUrl(item: Item): string | null {
   return `${baseUrl}/session/${sessionId}`;
 }
 
+function formatTokens(tokens: number): string {
+  if (tokens < 1000) return `${tokens}`;
+  if (tokens < 1_000_000) return `${(tokens / 1000).toFixed(1)}k`;
+  return `${(tokens / 1_000_000).toFixed(1)}M`;
+}
+
+const LOG_AGENT_TYPES: Item['type'][] = ['copilot_agent', 'claude_session', 'codex_session', 'gemini_session'];
+
+function getAgentUsage(item: Item): { label: string; detail: string } | null {
+  if (!LOG_AGENT_TYPES.includes(item.type) && item.type !== 'opencode_session') return null;
+  const tokens = item.metadata?.total_tokens;
+  if (typeof tokens !== 'number' || tokens === 0) return null;
+
+  const parts = [`${formatTokens(tokens)} tok`];
+  const cost = item.metadata?.total_cost;
+  if (typeof cost === 'number' && cost > 0) parts.push(`$${cost.toFixed(2)}`);
+  const toolCalls: Record<string, number> = item.metadata?.tool_calls ?? {};
+  const toolCount = Object.values(toolCalls).reduce((sum, n) => sum + n, 0);
+  if (toolCount > 0) parts.push(`${toolCount} tools`);
+
+  const detail = [
+    item.metadata?.model,
+    ...Object.entries(toolCalls).map(([name, n]) => `${name}: ${n}`),
+    ...(Array.isArray(item.metadata?.files_edited) ? item.metadata.files_edited : []),
+  ].filter(Boolean).join('\n');
+
+  return { label: parts.join(' · '), detail };
+}
+
+function getSubAgents(item: Item): { label: string; detail: string } | null {
+  const children: { title: string; session_status: string; total_tokens: number }[] = item.metadata?.children ?? [];
+  if (children.length === 0) return null;
+
+  const busy = item.metadata?.children_busy ?? 0;
+  const label = `${children.length} sub-agent${children.length === 1 ? '' : 's'}${busy > 0 ? ` · ${busy} busy` : ''}`;
+  const detail = children
+    .map(c => `${c.title} — ${c.session_status}, ${formatTokens(c.total_tokens)} tok`)
+    .join('\n');
+  return { label, detail };
+}
+
+const CI_LABELS: Record<string, string> = {
+  running: 'CI running',
+  passed: 'CI passed',
+  failed: 'CI failed',
+};
+
+function describePr(pr: Item): string {
+  const number = pr.metadata?.pr_number;
+  const state = pr.status === 'merged' ? 'merged' : CI_LABELS[pr.metadata?.ci_status] ?? pr.status.replace('_', ' ');
+  return `opened PR${number ? ` #${number}` : ''}, ${state}`;
+}
+
+const RESOLVED_STATUSES = ['merged', 'completed', 'closed'];
+
+function getBlockers(blockers: Item[]): { label: string; detail: string; failed: boolean } | null {
+  if (blockers.length === 0) return null;
+  const failed = blockers.some(b => b.status === 'failed');
+  const open = blockers.filter(b => !RESOLVED_STATUSES.includes(b.status)).length;
+  const label = failed
+    ? 'blocker failed'
+    : open === 0
+      ? 'unblocked'
+      : `blocked by ${open}/${blockers.length}`;
+  const detail = blockers.map(b => `${b.title} — ${b.status.replace('_', ' ')}`).join('\n');
+  return { label, detail, failed };
+}
+
+const REMINDER_STATUSES = ['input_needed', 'approved', 'updated'];
+
 function getLastActivity(item: Item): string | number | undefined {
-  if (item.type === 'opencode_session' || item.type === 'cli_session' || item.type === 'copilot_agent') {
+  if (item.type === 'opencode_session' || item.type === 'cli_session' || LOG_AGENT_TYPES.includes(item.type)) {
     return item.metadata?.last_activity || undefined;
   }
   return item.last_updated_at || item.last_checked_at;
 }
 
-export function ItemCard({ item, isArchived, onArchive, onUnarchive }: ItemCardProps) {
+function formatDuration(secs: number): string {
+  if (secs < 60) return `${secs}s`;
+  if (secs < 3600) return `${Math.floor(secs / 60)}m ${secs % 60}s`;
+  return `${Math.floor(secs / 3600)}h ${Math.floor((secs % 3600) / 60)}m`;
+}
+
+function getCliResult(item: Item): string | null {
+  if (item.type !== 'cli_session' || item.metadata?.exit_code === undefined) return null;
+  const parts = [`exit ${item.metadata.exit_code}`];
+  if (typeof item.metadata.duration_secs === 'number') {
+    parts.push(formatDuration(item.metadata.duration_secs));
+  }
+  return parts.join(' · ');
+}
+
+export function ItemCard({
+  item,
+  isArchived,
+  nested = false,
+  links = [],
+  itemsById = new Map(),
+  muted = false,
+  snoozedUntil,
+  onArchive,
+  onUnarchive,
+  onMute,
+  onSnooze,
+}: ItemCardProps) {
   const [contextMenu, setContextMenu] = useState<{ x: number; y: number } | null>(null);
   const [bindPopover, setBindPopover] = useState<{ x: number; y: number } | null>(null);
+  const [linkPopover, setLinkPopover] = useState<{ x: number; y: number } | null>(null);
   const [boundTodoIds, setBoundTodoIds] = useState<string[]>([]);
+  const [showPrompt, setShowPrompt] = useState(false);
+  const [showTimeline, setShowTimeline] = useState(false);
 
   const typeName: Record<Item['type'], string> = {
     slack_thread: 'Slack',
     github_action: 'Action',
     github_pr: 'PR',
     copilot_agent: 'Copilot',
+    claude_session: 'Claude',
+    codex_session: 'Codex',
+    gemini_session: 'Gemini',
     cli_session: 'CLI',
     opencode_session: 'OpenCode',
   };
@@ -78,6 +195,15 @@ export function ItemCard({ item, isArchived, onArchive, onUnarchive }: ItemCardP
     setContextMenu({ x: e.clientX, y: e.clientY });
   };
 
+  const handleAbort = async () => {
+    setContextMenu(null);
+    try {
+      await invoke('abort_opencode_session', { itemId: item.id });
+    } catch (e) {
+      console.error('Failed to abort session:', e);
+    }
+  };
+
   const handleBindClick = () => {
     if (contextMenu) {
       setBindPopover({ x: contextMenu.x, y: contextMenu.y });
@@ -85,13 +211,33 @@ export function ItemCard({ item, isArchived, onArchive, onUnarchive }: ItemCardP
     }
   };
 
+  const linked = (relation: ItemLink['relation'], outgoing: boolean): Item[] =>
+    links
+      .filter(l => l.relation === relation && (outgoing ? l.source_item_id : l.target_item_id) === item.id)
+      .map(l => itemsById.get(outgoing ? l.target_item_id : l.source_item_id))
+      .filter((i): i is Item => !!i);
+  const linkedPrs = linked('produced_by', false).filter(i => i.type === 'github_pr');
+  const linkedSessions = linked('produced_by', true);
+  const blockers = getBlockers(linked('blocks', false));
+  const blocking = linked('blocks', true);
+  const related = [...linked('related_to', true), ...linked('related_to', false)];
+
   const lastActivity = getLastActivity(item);
   const lastActivityStr = timeAgo(lastActivity);
+  const snoozed = !!snoozedUntil && new Date(snoozedUntil) > new Date();
   const hasLink = !!(getOpenCodeSessionUrl(item) || item.url);
+  const cliResult = getCliResult(item);
+  const agentUsage = getAgentUsage(item);
+  const subAgents = getSubAgents(item);
+  const pendingQuestion: OpenCodeQuestion | null =
+    item.type === 'opencode_session' && !isArchived ? item.metadata?.pending_question ?? null : null;
+  const isOpenCode = item.type === 'opencode_session' && !isArchived;
+  const sessionBusy = item.metadata?.session_status === 'busy' || item.metadata?.session_status === 'retry';
+  const outputTail: string[] = Array.isArray(item.metadata?.output_tail) ? item.metadata.output_tail : [];
 
   return (
     <>
-      <div className="item-row" onContextMenu={handleContextMenu}>
+      <div className={`item-row${nested ? ' item-nested' : ''}`} onContextMenu={handleContextMenu}>
         <span className="type-badge">{typeName[item.type]}</span>
         <StatusBadge status={item.status} />
         {hasLink ? (
@@ -99,7 +245,62 @@ export function ItemCard({ item, isArchived, onArchive, onUnarchive }: ItemCardP
             {item.title}
           </span>
         ) : (
-          <span className="item-title">{item.title}</span>
+          <span className="item-title" title={outputTail.length ? outputTail.join('\n') : undefined}>{item.title}</span>
+        )}
+        {item.type === 'opencode_session' && item.metadata?.server && item.metadata.server !== 'default' && (
+          <span className="item-time">{item.metadata.server}</span>
+        )}
+        {cliResult && (
+          <span className="item-time">{cliResult}</span>
+        )}
+        {subAgents && (
+          <span className="item-time" title={subAgents.detail}>{subAgents.label}</span>
+        )}
+        {agentUsage && (
+          <span className="item-time" title={agentUsage.detail || undefined}>{agentUsage.label}</span>
+        )}
+        {linkedPrs.map(pr => (
+          <span
+            key={pr.id}
+            className={`item-time item-link${pr.metadata?.ci_status === 'failed' ? ' item-link-failed' : ''}`}
+            role="link"
+            tabIndex={0}
+            title={pr.title}
+            onClick={() => pr.url && invoke('open_url', { url: pr.url })}
+          >
+            {describePr(pr)}
+          </span>
+        ))}
+        {linkedSessions.map(session => (
+          <span key={session.id} className="item-time item-link" title={session.title}>
+            from {typeName[session.type]} session
+          </span>
+        ))}
+        {blockers && (
+          <span className={`item-time${blockers.failed ? ' item-link-failed' : ''}`} title={blockers.detail}>
+            {blockers.label}
+          </span>
+        )}
+        {blocking.length > 0 && (
+          <span className="item-time" title={blocking.map(b => b.title).join('\n')}>
+            blocks {blocking.length}
+          </span>
+        )}
+        {related.length > 0 && (
+          <span className="item-time" title={related.map(r => r.title).join('\n')}>
+            {related.length} related
+          </span>
+        )}
+        {muted && (
+          <span className="item-time" title="Notifications muted">muted</span>
+        )}
+        {snoozed && (
+          <span className="item-time" title="Reminders snoozed">
+            snoozed until {new Date(snoozedUntil!).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' })}
+          </span>
+        )}
+        {item.metadata?.over_budget && (
+          <span className="item-time item-over-budget" title={item.metadata.budget_reason}>over budget</span>
         )}
         {lastActivityStr && (
           <span className="item-time">{lastActivityStr}</span>
@@ -111,17 +312,58 @@ export function ItemCard({ item, isArchived, onArchive, onUnarchive }: ItemCardP
         )}
       </div>
 
+      {pendingQuestion && <QuestionPanel item={item} question={pendingQuestion} />}
+      {showPrompt && <PromptPanel item={item} onClose={() => setShowPrompt(false)} />}
+      {showTimeline && <TimelinePanel item={item} />}
+
       {contextMenu && (
         <ContextMenu
           x={contextMenu.x}
           y={contextMenu.y}
           items={[
             { label: 'Bind to todo...', onClick: handleBindClick },
+            {
+              label: 'Link to item...',
+              onClick: () => { setLinkPopover({ x: contextMenu.x, y: contextMenu.y }); setContextMenu(null); },
+            },
+            ...(isOpenCode && !sessionBusy && !pendingQuestion
+              ? [{ label: 'Send prompt...', onClick: () => { setShowPrompt(true); setContextMenu(null); } }]
+              : []),
+            ...(isOpenCode && sessionBusy ? [{ label: 'Abort session', onClick: handleAbort }] : []),
+            {
+              label: showTimeline ? 'Hide timeline' : 'Show timeline',
+              onClick: () => { setShowTimeline(!showTimeline); setContextMenu(null); },
+            },
+            ...(onSnooze && snoozed
+              ? [{ label: 'End snooze', onClick: () => { onSnooze(item.id, 0); setContextMenu(null); } }]
+              : []),
+            ...(onSnooze && !snoozed && REMINDER_STATUSES.includes(item.status)
+              ? [
+                  { label: 'Snooze 15 minutes', onClick: () => { onSnooze(item.id, 15); setContextMenu(null); } },
+                  { label: 'Snooze 1 hour', onClick: () => { onSnooze(item.id, 60); setContextMenu(null); } },
+                ]
+              : []),
+            ...(onMute
+              ? [{
+                  label: muted ? 'Unmute notifications' : 'Mute notifications',
+                  onClick: () => { onMute(item.id, !muted); setContextMenu(null); },
+                }]
+              : []),
           ]}
           onClose={() => setContextMenu(null)}
         />
       )}
 
+      {linkPopover && (
+        <LinkPopover
+          x={linkPopover.x}
+          y={linkPopover.y}
+          sourceId={item.id}
+          links={links}
+          onClose={() => setLinkPopover(null)}
+        />
+      )}
+
       {bindPopover && (
         <BindPopover
           x={bindPopover.x}
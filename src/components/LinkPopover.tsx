@@ -0,0 +1,104 @@
+import { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Item, ItemLink, LinkRelation } from '../types';
+
+interface LinkPopoverProps {
+  x: number;
+  y: number;
+  /** The item links are created from, read as "source <relation> target". */
+  sourceId: string;
+  links: ItemLink[];
+  onClose: () => void;
+}
+
+const RELATION_LABELS: Record<LinkRelation, string> = {
+  blocks: 'Blocks',
+  produced_by: 'Produced by',
+  related_to: 'Related to',
+};
+
+export function LinkPopover({ x, y, sourceId, links, onClose }: LinkPopoverProps) {
+  const [relation, setRelation] = useState<LinkRelation>('blocks');
+  const [options, setOptions] = useState<Item[]>([]);
+  const [linked, setLinked] = useState<Set<string>>(new Set());
+  const [error, setError] = useState<string | null>(null);
+  const popoverRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    invoke<Item[]>('get_items', { archived: false })
+      .then(items => setOptions(items.filter(i => i.id !== sourceId)))
+      .catch(e => console.error('Failed to load items:', e));
+  }, [sourceId]);
+
+  useEffect(() => {
+    setLinked(new Set(
+      links
+        .filter(l => l.source_item_id === sourceId && l.relation === relation)
+        .map(l => l.target_item_id),
+    ));
+    setError(null);
+  }, [links, sourceId, relation]);
+
+  useEffect(() => {
+    const handleClick = (e: MouseEvent) => {
+      if (popoverRef.current && !popoverRef.current.contains(e.target as Node)) {
+        onClose();
+      }
+    };
+    const handleEsc = (e: KeyboardEvent) => {
+      if (e.key === 'Escape') onClose();
+    };
+    document.addEventListener('mousedown', handleClick);
+    document.addEventListener('keydown', handleEsc);
+    return () => {
+      document.removeEventListener('mousedown', handleClick);
+      document.removeEventListener('keydown', handleEsc);
+    };
+  }, [onClose]);
+
+  const handleToggle = async (targetId: string) => {
+    const isLinked = linked.has(targetId);
+    try {
+      await invoke(isLinked ? 'unlink_items' : 'link_items', { sourceId, targetId, relation });
+      setLinked(prev => {
+        const next = new Set(prev);
+        if (isLinked) next.delete(targetId);
+        else next.add(targetId);
+        return next;
+      });
+      setError(null);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div ref={popoverRef} className="bind-popover" style={{ top: y, left: x }}>
+      <div className="bind-popover-title">
+        This item{' '}
+        <select value={relation} onChange={e => setRelation(e.target.value as LinkRelation)}>
+          {Object.entries(RELATION_LABELS).map(([value, label]) => (
+            <option key={value} value={value}>{label.toLowerCase()}</option>
+          ))}
+        </select>
+      </div>
+      {error && <div className="bind-popover-empty question-error">{error}</div>}
+      {options.length === 0 ? (
+        <div className="bind-popover-empty">No items available</div>
+      ) : (
+        <div className="bind-popover-list">
+          {options.map(opt => (
+            <label key={opt.id} className="bind-popover-option">
+              <input
+                type="checkbox"
+                checked={linked.has(opt.id)}
+                onChange={() => handleToggle(opt.id)}
+              />
+              <span className="bind-popover-label">{opt.title}</span>
+            </label>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
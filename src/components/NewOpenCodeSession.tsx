@@ -0,0 +1,75 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+interface NewOpenCodeSessionProps {
+  onStarted: () => void;
+}
+
+export function NewOpenCodeSession({ onStarted }: NewOpenCodeSessionProps) {
+  const [directories, setDirectories] = useState<Record<string, string[]>>({});
+  // "<server>\n<directory>", so one select can cover all servers
+  const [target, setTarget] = useState('');
+  const [prompt, setPrompt] = useState('');
+  const [error, setError] = useState('');
+  const [loading, setLoading] = useState(false);
+
+  useEffect(() => {
+    invoke<Record<string, string[]>>('get_opencode_directories')
+      .then(dirs => {
+        setDirectories(dirs);
+        const first = Object.entries(dirs).find(([, list]) => list.length > 0);
+        if (first) setTarget(`${first[0]}\n${first[1][0]}`);
+      })
+      .catch(() => setDirectories({}));
+  }, []);
+
+  const servers = Object.entries(directories).filter(([, list]) => list.length > 0);
+  // Nothing to offer until OpenCode has been used in at least one directory
+  if (servers.length === 0) return null;
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError('');
+    setLoading(true);
+
+    try {
+      const [server, directory] = target.split('\n');
+      await invoke('start_opencode_session', { server, directory, prompt: prompt || undefined });
+      setPrompt('');
+      onStarted();
+    } catch (err) {
+      setError(err as string);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <form className="add-form-inline" onSubmit={handleSubmit}>
+      <select className="form-input opencode-directory" value={target} onChange={e => setTarget(e.target.value)}>
+        {servers.length === 1
+          ? servers[0][1].map(dir => (
+              <option key={dir} value={`${servers[0][0]}\n${dir}`}>{dir}</option>
+            ))
+          : servers.map(([server, list]) => (
+              <optgroup key={server} label={server}>
+                {list.map(dir => (
+                  <option key={dir} value={`${server}\n${dir}`}>{dir}</option>
+                ))}
+              </optgroup>
+            ))}
+      </select>
+      <input
+        type="text"
+        className="form-input"
+        placeholder="Start an OpenCode session (optional first prompt)"
+        value={prompt}
+        onChange={e => setPrompt(e.target.value)}
+      />
+      <button type="submit" disabled={loading || !target}>
+        {loading ? '...' : 'Start'}
+      </button>
+      {error && <span className="form-error-inline">{error}</span>}
+    </form>
+  );
+}
@@ -0,0 +1,67 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { NotificationRow } from '../types';
+
+const typeName: Record<string, string> = {
+  slack_thread: 'Slack',
+  github_action: 'Action',
+  github_pr: 'PR',
+  copilot_agent: 'Copilot',
+  claude_session: 'Claude',
+  codex_session: 'Codex',
+  gemini_session: 'Gemini',
+  cli_session: 'CLI',
+  opencode_session: 'OpenCode',
+};
+
+export function NotificationMatrix() {
+  const [rows, setRows] = useState<NotificationRow[]>([]);
+  const [error, setError] = useState('');
+
+  const loadMatrix = async () => {
+    try {
+      setRows(await invoke<NotificationRow[]>('get_notification_matrix'));
+    } catch (e) {
+      console.error('Failed to load notification settings:', e);
+    }
+  };
+
+  useEffect(() => {
+    loadMatrix();
+  }, []);
+
+  const handleToggle = async (itemType: string, category: string, enabled: boolean) => {
+    setError('');
+    try {
+      await invoke('set_notification_pref', { itemType, category, enabled });
+      await loadMatrix();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="settings-field">
+      <label>Notifications by type</label>
+      {rows.map(row => (
+        <div key={row.item_type} className="notification-row">
+          <span className="notification-type">{typeName[row.item_type] ?? row.item_type}</span>
+          {row.toggles.map(toggle => (
+            <label key={toggle.category} className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={toggle.enabled}
+                onChange={e => handleToggle(row.item_type, toggle.category, e.target.checked)}
+              />
+              {toggle.label}
+            </label>
+          ))}
+        </div>
+      ))}
+      <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+        Session types follow the defaults above until changed here; single items can be muted from their menu
+      </span>
+      {error && <span className="form-error-inline">{error}</span>}
+    </div>
+  );
+}
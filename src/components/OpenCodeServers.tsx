@@ -0,0 +1,95 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { OpenCodeServer } from '../types';
+
+function healthLabel(server: OpenCodeServer): string {
+  if (server.healthy === null) return 'Not checked yet';
+  if (server.healthy) return 'Reachable';
+  return server.last_error || 'Unreachable';
+}
+
+export function OpenCodeServers() {
+  const [servers, setServers] = useState<OpenCodeServer[]>([]);
+  const [name, setName] = useState('');
+  const [url, setUrl] = useState('');
+  const [password, setPassword] = useState('');
+  const [error, setError] = useState('');
+
+  const loadServers = async () => {
+    try {
+      setServers(await invoke<OpenCodeServer[]>('get_opencode_servers'));
+    } catch (e) {
+      console.error('Failed to load OpenCode servers:', e);
+    }
+  };
+
+  useEffect(() => {
+    loadServers();
+  }, []);
+
+  const handleAdd = async () => {
+    setError('');
+    try {
+      await invoke('save_opencode_server', { name, url, password: password || undefined });
+      setName('');
+      setUrl('');
+      setPassword('');
+      await loadServers();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleRemove = async (serverName: string) => {
+    try {
+      await invoke('remove_opencode_server', { name: serverName });
+      await loadServers();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="settings-field">
+      <label>OpenCode Servers</label>
+      {servers.map(server => (
+        <div key={server.name} className="server-row" title={healthLabel(server)}>
+          <span className={`server-health ${server.healthy === null ? '' : server.healthy ? 'server-up' : 'server-down'}`} />
+          <span className="server-name">{server.name}</span>
+          <span className="server-url">{server.url}</span>
+          <button type="button" className="btn-icon" onClick={() => handleRemove(server.name)} title="Remove">✕</button>
+        </div>
+      ))}
+      <div className="server-add">
+        <input
+          type="text"
+          className="form-input server-add-name"
+          placeholder="Name"
+          value={name}
+          onChange={e => setName(e.target.value)}
+        />
+        <input
+          type="text"
+          className="form-input"
+          placeholder="Paste any OpenCode URL from your browser"
+          value={url}
+          onChange={e => setUrl(e.target.value)}
+        />
+        <input
+          type="password"
+          className="form-input"
+          placeholder="Password (blank if none)"
+          value={password}
+          onChange={e => setPassword(e.target.value)}
+        />
+        <button type="button" disabled={!name.trim() || !url.trim()} onClick={handleAdd}>
+          Add
+        </button>
+      </div>
+      <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+        Saving an existing name updates it; leave the password blank to keep the current one
+      </span>
+      {error && <span className="form-error-inline">{error}</span>}
+    </div>
+  );
+}
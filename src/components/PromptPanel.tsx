@@ -0,0 +1,50 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Item } from '../types';
+
+interface PromptPanelProps {
+  item: Item;
+  onClose: () => void;
+}
+
+export function PromptPanel({ item, onClose }: PromptPanelProps) {
+  const [text, setText] = useState('');
+  const [sending, setSending] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const send = async () => {
+    if (!text.trim() || sending) return;
+    setSending(true);
+    setError(null);
+    try {
+      await invoke('send_opencode_prompt', { itemId: item.id, text });
+      onClose();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSending(false);
+    }
+  };
+
+  return (
+    <div className="question-panel">
+      <div className="question-options">
+        <input
+          className="question-input"
+          placeholder="Follow-up prompt…"
+          value={text}
+          disabled={sending}
+          autoFocus
+          onChange={e => setText(e.target.value)}
+          onKeyDown={e => {
+            if (e.key === 'Enter') send();
+            if (e.key === 'Escape') onClose();
+          }}
+        />
+        <button className="question-option" disabled={sending || !text.trim()} onClick={send}>Send</button>
+        <button className="question-option" disabled={sending} onClick={onClose}>Cancel</button>
+      </div>
+      {error && <span className="question-error">{error}</span>}
+    </div>
+  );
+}
@@ -0,0 +1,66 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Item, OpenCodeQuestion } from '../types';
+
+interface QuestionPanelProps {
+  item: Item;
+  question: OpenCodeQuestion;
+}
+
+export function QuestionPanel({ item, question }: QuestionPanelProps) {
+  const [custom, setCustom] = useState('');
+  const [sending, setSending] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // Only single-prompt questions can be answered inline; others need OpenCode.
+  if (question.questions.length !== 1) {
+    return (
+      <div className="question-panel">
+        <span className="question-text">Asked {question.questions.length} questions — answer in OpenCode</span>
+      </div>
+    );
+  }
+  const prompt = question.questions[0];
+
+  const send = async (answer: string) => {
+    if (!answer.trim() || sending) return;
+    setSending(true);
+    setError(null);
+    try {
+      await invoke('answer_opencode_question', { itemId: item.id, answers: [[answer.trim()]] });
+      setCustom('');
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSending(false);
+    }
+  };
+
+  return (
+    <div className="question-panel">
+      <span className="question-text">{prompt.question}</span>
+      <div className="question-options">
+        {prompt.options.map(option => (
+          <button
+            key={option.label}
+            className="question-option"
+            title={option.description || undefined}
+            disabled={sending}
+            onClick={() => send(option.label)}
+          >
+            {option.label}
+          </button>
+        ))}
+        <input
+          className="question-input"
+          placeholder="Other answer…"
+          value={custom}
+          disabled={sending}
+          onChange={e => setCustom(e.target.value)}
+          onKeyDown={e => e.key === 'Enter' && send(custom)}
+        />
+      </div>
+      {error && <span className="question-error">{error}</span>}
+    </div>
+  );
+}
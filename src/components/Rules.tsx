@@ -0,0 +1,239 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ItemStatus, ItemType, Rule, RuleAction, RuleCondition, RuleMatch } from '../types';
+
+const ITEM_TYPES: ItemType[] = [
+  'github_pr',
+  'github_action',
+  'slack_thread',
+  'copilot_agent',
+  'claude_session',
+  'codex_session',
+  'gemini_session',
+  'cli_session',
+  'opencode_session',
+];
+
+const STATUSES: ItemStatus[] = [
+  'waiting',
+  'in_progress',
+  'input_needed',
+  'updated',
+  'approved',
+  'merged',
+  'completed',
+  'closed',
+  'failed',
+];
+
+type ActionType = RuleAction['type'];
+
+const ACTION_LABELS: Record<ActionType, string> = {
+  archive: 'Archive the item',
+  complete_bound_todos: 'Complete bound todos',
+  create_todo: 'Create a todo',
+  notify: 'Notify',
+  webhook: 'Call a webhook',
+};
+
+/** Placeholder for the action's text field, if it takes one. */
+const ACTION_ARGUMENT: Partial<Record<ActionType, string>> = {
+  create_todo: 'Todo title, e.g. Follow up on {title}',
+  notify: 'Message, e.g. {title} is {status}',
+  webhook: 'https://…',
+};
+
+function buildAction(type: ActionType, argument: string): RuleAction {
+  switch (type) {
+    case 'create_todo': return { type, title: argument };
+    case 'notify': return { type, body: argument };
+    case 'webhook': return { type, url: argument };
+    default: return { type };
+  }
+}
+
+function describeTrigger(rule: Rule): string {
+  const { item_type, from_status, to_status, text } = rule.trigger;
+  const parts = [
+    item_type || 'any item',
+    `${from_status || 'any'} → ${to_status || 'any'}`,
+  ];
+  if (text) parts.push(`matching "${text}"`);
+  return parts.join(', ');
+}
+
+export function Rules() {
+  const [rules, setRules] = useState<Rule[]>([]);
+  const [name, setName] = useState('');
+  const [itemType, setItemType] = useState('');
+  const [fromStatus, setFromStatus] = useState('');
+  const [toStatus, setToStatus] = useState('');
+  const [text, setText] = useState('');
+  const [metadataKey, setMetadataKey] = useState('');
+  const [actionType, setActionType] = useState<ActionType>('archive');
+  const [argument, setArgument] = useState('');
+  const [matches, setMatches] = useState<RuleMatch[] | null>(null);
+  const [error, setError] = useState('');
+
+  const loadRules = async () => {
+    try {
+      setRules(await invoke<Rule[]>('get_rules'));
+    } catch (e) {
+      console.error('Failed to load rules:', e);
+    }
+  };
+
+  useEffect(() => {
+    loadRules();
+  }, []);
+
+  const draftRule = (): Rule => {
+    const conditions: RuleCondition[] = metadataKey.trim()
+      ? [{ kind: 'metadata', key: metadataKey.trim() }]
+      : [];
+    return {
+      id: '',
+      name: name.trim(),
+      enabled: true,
+      trigger: {
+        item_type: itemType || null,
+        from_status: fromStatus || null,
+        to_status: toStatus || null,
+        text: text.trim() || null,
+      },
+      conditions,
+      actions: [buildAction(actionType, argument.trim())],
+      created_at: '',
+    };
+  };
+
+  const handleAdd = async () => {
+    setError('');
+    try {
+      await invoke('save_rule', { rule: draftRule() });
+      setName('');
+      setText('');
+      setMetadataKey('');
+      setArgument('');
+      setMatches(null);
+      await loadRules();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleToggle = async (rule: Rule) => {
+    try {
+      await invoke('save_rule', { rule: { ...rule, enabled: !rule.enabled } });
+      await loadRules();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleDelete = async (id: string) => {
+    try {
+      await invoke('delete_rule', { id });
+      await loadRules();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleDryRun = async (rule: Rule | null) => {
+    setError('');
+    try {
+      setMatches(await invoke<RuleMatch[]>('dry_run_rules', { rule }));
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const needsArgument = actionType in ACTION_ARGUMENT;
+  const canAdd = name.trim() !== '' && (!needsArgument || argument.trim() !== '');
+
+  return (
+    <div className="settings-field">
+      <label>Rules</label>
+      {rules.map(rule => (
+        <div key={rule.id} className="server-row" title={describeTrigger(rule)}>
+          <input type="checkbox" checked={rule.enabled} onChange={() => handleToggle(rule)} />
+          <span className="server-name">{rule.name}</span>
+          <span className="server-url">{describeTrigger(rule)}</span>
+          <button type="button" className="btn-icon" onClick={() => handleDryRun(rule)} title="Dry run">▶</button>
+          <button type="button" className="btn-icon" onClick={() => handleDelete(rule.id)} title="Remove">✕</button>
+        </div>
+      ))}
+      <div className="server-add">
+        <input
+          type="text"
+          className="form-input server-add-name"
+          placeholder="Name"
+          value={name}
+          onChange={e => setName(e.target.value)}
+        />
+        <select className="form-input" value={itemType} onChange={e => setItemType(e.target.value)}>
+          <option value="">Any item</option>
+          {ITEM_TYPES.map(t => <option key={t} value={t}>{t}</option>)}
+        </select>
+        <select className="form-input" value={fromStatus} onChange={e => setFromStatus(e.target.value)}>
+          <option value="">From any</option>
+          {STATUSES.map(s => <option key={s} value={s}>{s}</option>)}
+        </select>
+        <select className="form-input" value={toStatus} onChange={e => setToStatus(e.target.value)}>
+          <option value="">To any</option>
+          {STATUSES.map(s => <option key={s} value={s}>{s}</option>)}
+        </select>
+        <input
+          type="text"
+          className="form-input"
+          placeholder="Title or metadata contains (optional)"
+          value={text}
+          onChange={e => setText(e.target.value)}
+        />
+        <input
+          type="text"
+          className="form-input"
+          placeholder="Only if metadata has key (optional)"
+          value={metadataKey}
+          onChange={e => setMetadataKey(e.target.value)}
+        />
+        <select className="form-input" value={actionType} onChange={e => setActionType(e.target.value as ActionType)}>
+          {Object.entries(ACTION_LABELS).map(([value, label]) => (
+            <option key={value} value={value}>{label}</option>
+          ))}
+        </select>
+        {needsArgument && (
+          <input
+            type="text"
+            className="form-input"
+            placeholder={ACTION_ARGUMENT[actionType]}
+            value={argument}
+            onChange={e => setArgument(e.target.value)}
+          />
+        )}
+        <button type="button" disabled={!name.trim()} onClick={() => handleDryRun(draftRule())}>
+          Dry run
+        </button>
+        <button type="button" disabled={!canAdd} onClick={handleAdd}>
+          Add
+        </button>
+      </div>
+      <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+        Rules run when an item changes status; texts may use {'{title}'}, {'{status}'}, {'{previous_status}'}, {'{type}'} and {'{url}'}
+      </span>
+      {matches !== null && (
+        <div className="spend-summary">
+          <span>{matches.length === 0 ? 'No past status changes match' : `Would have fired ${matches.length} times`}</span>
+          {matches.slice(0, 20).map(m => (
+            <div key={`${m.rule_id}-${m.item_id}-${m.at}`} className="spend-row" title={m.actions.join(', ')}>
+              <span>{m.item_title}</span>
+              <span>{m.from} → {m.to} · {new Date(m.at).toLocaleDateString()}</span>
+            </div>
+          ))}
+        </div>
+      )}
+      {error && <span className="form-error-inline">{error}</span>}
+    </div>
+  );
+}
@@ -1,16 +1,29 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Credentials, Settings as SettingsType } from '../types';
+import { Credentials, DailySpend, Settings as SettingsType } from '../types';
+import { OpenCodeServers } from './OpenCodeServers';
+import { Rules } from './Rules';
+import { Hooks } from './Hooks';
+import { Webhooks } from './Webhooks';
+import { NotificationMatrix } from './NotificationMatrix';
 
 export function Settings() {
   const [slackToken, setSlackToken] = useState('');
   const [githubToken, setGithubToken] = useState('');
-  const [opencodeUrl, setOpencodeUrl] = useState('');
-  const [opencodePassword, setOpencodePassword] = useState('');
+  const [githubWebhookSecret, setGithubWebhookSecret] = useState('');
   const [pollingInterval, setPollingInterval] = useState(30);
   const [notifySessionStarted, setNotifySessionStarted] = useState(true);
   const [notifySessionEnded, setNotifySessionEnded] = useState(true);
   const [notifyInputNeeded, setNotifyInputNeeded] = useState(true);
+  const [shellTrackThreshold, setShellTrackThreshold] = useState(30);
+  const [shellTrackIgnore, setShellTrackIgnore] = useState('');
+  const [budgetSessionTokens, setBudgetSessionTokens] = useState(0);
+  const [budgetSessionCost, setBudgetSessionCost] = useState(0);
+  const [budgetDailyTokens, setBudgetDailyTokens] = useState(0);
+  const [budgetDailyCost, setBudgetDailyCost] = useState(0);
+  const [reminderInterval, setReminderInterval] = useState(15);
+  const [reminderLoudSound, setReminderLoudSound] = useState(false);
+  const [todaySpend, setTodaySpend] = useState<DailySpend[]>([]);
   const [addItemShortcut, setAddItemShortcut] = useState('Ctrl+Shift+Q');
   const [recordingShortcut, setRecordingShortcut] = useState(false);
   const [loading, setLoading] = useState(false);
@@ -27,6 +40,17 @@ export function Settings() {
       setNotifySessionStarted(settings.notify_session_started);
       setNotifySessionEnded(settings.notify_session_ended);
       setNotifyInputNeeded(settings.notify_input_needed);
+      setShellTrackThreshold(settings.shell_track_threshold_secs);
+      setShellTrackIgnore(settings.shell_track_ignore);
+      setBudgetSessionTokens(settings.budget_session_tokens);
+      setBudgetSessionCost(settings.budget_session_cost);
+      setBudgetDailyTokens(settings.budget_daily_tokens);
+      setBudgetDailyCost(settings.budget_daily_cost);
+      setReminderInterval(settings.reminder_interval_minutes);
+      setReminderLoudSound(settings.reminder_loud_sound);
+
+      const spend: DailySpend[] = await invoke('get_daily_spend', { day: null });
+      setTodaySpend(spend);
 
       const shortcut: string = await invoke('get_add_item_shortcut');
       setAddItemShortcut(shortcut);
@@ -44,8 +68,7 @@ export function Settings() {
       const credentials: Credentials = {};
       if (slackToken) credentials.slack_token = slackToken;
       if (githubToken) credentials.github_token = githubToken;
-      if (opencodeUrl) credentials.opencode_url = opencodeUrl;
-      if (opencodePassword) credentials.opencode_password = opencodePassword;
+      if (githubWebhookSecret) credentials.github_webhook_secret = githubWebhookSecret;
       
       await invoke('save_credentials', { credentials });
       await invoke('save_settings', { 
@@ -54,6 +77,14 @@ export function Settings() {
           notify_session_started: notifySessionStarted,
           notify_session_ended: notifySessionEnded,
           notify_input_needed: notifyInputNeeded,
+          shell_track_threshold_secs: shellTrackThreshold,
+          shell_track_ignore: shellTrackIgnore,
+          budget_session_tokens: budgetSessionTokens,
+          budget_session_cost: budgetSessionCost,
+          budget_daily_tokens: budgetDailyTokens,
+          budget_daily_cost: budgetDailyCost,
+          reminder_interval_minutes: reminderInterval,
+          reminder_loud_sound: reminderLoudSound,
         } 
       });
 
@@ -62,8 +93,7 @@ export function Settings() {
       setMessage('Saved');
       setSlackToken('');
       setGithubToken('');
-      setOpencodeUrl('');
-      setOpencodePassword('');
+      setGithubWebhookSecret('');
     } catch (error) {
       setMessage(`Error: ${error}`);
     } finally {
@@ -86,29 +116,22 @@ export function Settings() {
       </div>
 
       <div className="settings-field">
-        <label htmlFor="opencode-url">OpenCode URL</label>
-        <input
-          id="opencode-url"
-          type="text"
-          className="form-input"
-          placeholder="Paste any OpenCode URL from your browser"
-          value={opencodeUrl}
-          onChange={(e) => setOpencodeUrl(e.target.value)}
-        />
-      </div>
-
-      <div className="settings-field">
-        <label htmlFor="opencode-password">OpenCode Password</label>
+        <label htmlFor="github-webhook-secret">GitHub Webhook Secret</label>
         <input
-          id="opencode-password"
+          id="github-webhook-secret"
           type="password"
           className="form-input"
-          placeholder="Leave blank if none"
-          value={opencodePassword}
-          onChange={(e) => setOpencodePassword(e.target.value)}
+          placeholder="Leave blank to keep the current one"
+          value={githubWebhookSecret}
+          onChange={(e) => setGithubWebhookSecret(e.target.value)}
         />
+        <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+          Relay pull_request, pull_request_review, workflow_run and check_suite events (e.g. with smee) to http://127.0.0.1:19532/api/github/webhook for instant updates
+        </span>
       </div>
 
+      <OpenCodeServers />
+
       <div className="settings-field">
         <label htmlFor="polling-interval">
           Polling: {pollingInterval}s
@@ -174,7 +197,7 @@ export function Settings() {
       </div>
 
       <div className="settings-field">
-        <label>Notifications</label>
+        <label>Notifications for agent sessions</label>
         <label className="checkbox-label">
           <input
             type="checkbox"
@@ -201,6 +224,127 @@ export function Settings() {
         </label>
       </div>
 
+      <NotificationMatrix />
+
+      <div className="settings-field">
+        <label htmlFor="reminder-interval">Reminders (minutes, 0 for none)</label>
+        <input
+          id="reminder-interval"
+          type="number"
+          className="form-input"
+          min="0"
+          value={reminderInterval}
+          onChange={(e) => setReminderInterval(parseInt(e.target.value) || 0)}
+        />
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={reminderLoudSound}
+            onChange={(e) => setReminderLoudSound(e.target.checked)}
+          />
+          Louder sound for reminders
+        </label>
+        <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+          Items left waiting for input, approved or with unread activity notify again at this interval and the tray badge pulses; snooze an item from its menu
+        </span>
+      </div>
+
+      <div className="settings-field">
+        <label htmlFor="shell-track-threshold">
+          Shell hooks: track commands longer than {shellTrackThreshold}s
+        </label>
+        <input
+          id="shell-track-threshold"
+          type="range"
+          min="5"
+          max="300"
+          step="5"
+          value={shellTrackThreshold}
+          onChange={(e) => setShellTrackThreshold(parseInt(e.target.value))}
+          style={{ width: '100%' }}
+        />
+        <label htmlFor="shell-track-ignore">Never track</label>
+        <input
+          id="shell-track-ignore"
+          type="text"
+          className="form-input"
+          placeholder="vim,less,ssh"
+          value={shellTrackIgnore}
+          onChange={(e) => setShellTrackIgnore(e.target.value)}
+        />
+        <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+          Comma-separated program names
+        </span>
+      </div>
+
+      <div className="settings-field">
+        <label>Agent budgets</label>
+        <div className="settings-budget-row">
+          <label htmlFor="budget-session-tokens">Per session: tokens</label>
+          <input
+            id="budget-session-tokens"
+            type="number"
+            min="0"
+            step="10000"
+            className="form-input"
+            value={budgetSessionTokens}
+            onChange={(e) => setBudgetSessionTokens(parseInt(e.target.value) || 0)}
+          />
+          <label htmlFor="budget-session-cost">$</label>
+          <input
+            id="budget-session-cost"
+            type="number"
+            min="0"
+            step="0.5"
+            className="form-input"
+            value={budgetSessionCost}
+            onChange={(e) => setBudgetSessionCost(parseFloat(e.target.value) || 0)}
+          />
+        </div>
+        <div className="settings-budget-row">
+          <label htmlFor="budget-daily-tokens">Per day: tokens</label>
+          <input
+            id="budget-daily-tokens"
+            type="number"
+            min="0"
+            step="10000"
+            className="form-input"
+            value={budgetDailyTokens}
+            onChange={(e) => setBudgetDailyTokens(parseInt(e.target.value) || 0)}
+          />
+          <label htmlFor="budget-daily-cost">$</label>
+          <input
+            id="budget-daily-cost"
+            type="number"
+            min="0"
+            step="0.5"
+            className="form-input"
+            value={budgetDailyCost}
+            onChange={(e) => setBudgetDailyCost(parseFloat(e.target.value) || 0)}
+          />
+        </div>
+        <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+          0 means no limit
+        </span>
+        {todaySpend.length > 0 && (
+          <div className="spend-summary">
+            <span>Today</span>
+            {todaySpend.map(s => (
+              <div key={s.directory} className="spend-row" title={s.directory}>
+                <span>{s.directory}</span>
+                <span>{s.tokens.toLocaleString()} tok · ${s.cost.toFixed(2)}</span>
+              </div>
+            ))}
+          </div>
+        )}
+      </div>
+
+      <Rules />
+
+      <Hooks />
+
+      <Webhooks />
+
       {message && (
         <div className={`settings-msg ${message.includes('Error') ? 'settings-msg-error' : ''}`}>
           {message}
@@ -0,0 +1,35 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Item, ItemEvent } from '../types';
+
+interface TimelinePanelProps {
+  item: Item;
+}
+
+export function TimelinePanel({ item }: TimelinePanelProps) {
+  const [events, setEvents] = useState<ItemEvent[] | null>(null);
+
+  useEffect(() => {
+    invoke<ItemEvent[]>('get_item_timeline', { itemId: item.id })
+      .then(setEvents)
+      .catch(() => setEvents([]));
+  }, [item.id, item.status]);
+
+  if (events === null) return null;
+
+  return (
+    <div className="timeline-panel">
+      {events.length === 0 ? (
+        <span className="timeline-empty">No timeline entries yet</span>
+      ) : (
+        events.map(event => (
+          <div key={event.id} className="timeline-entry">
+            <span className="timeline-time">{new Date(event.created_at).toLocaleString()}</span>
+            <span className="timeline-kind">{event.kind.replace(/_/g, ' ')}</span>
+            <span className="timeline-message">{event.message}</span>
+          </div>
+        ))
+      )}
+    </div>
+  );
+}
@@ -90,6 +90,9 @@ export function TodoCard({ todo, onChanged, isSubtask }: TodoCardProps) {
     github_action: 'Action',
     github_pr: 'PR',
     copilot_agent: 'Copilot',
+    claude_session: 'Claude',
+    codex_session: 'Codex',
+    gemini_session: 'Gemini',
     cli_session: 'CLI',
     opencode_session: 'OpenCode',
   };
@@ -0,0 +1,151 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Webhook, WebhookDelivery } from '../types';
+
+function deliveryResult(delivery: WebhookDelivery): string {
+  if (delivery.state === 'delivered') return `ok${delivery.response_status ? ` (${delivery.response_status})` : ''}`;
+  if (delivery.state === 'failed') return `failed after ${delivery.attempts}`;
+  if (delivery.attempts === 0) return 'queued';
+  const retry = delivery.next_attempt_at ? new Date(delivery.next_attempt_at).toLocaleTimeString() : '';
+  return `retry ${retry}`;
+}
+
+export function Webhooks() {
+  const [webhooks, setWebhooks] = useState<Webhook[]>([]);
+  const [deliveries, setDeliveries] = useState<WebhookDelivery[]>([]);
+  const [url, setUrl] = useState('');
+  const [events, setEvents] = useState('');
+  const [secret, setSecret] = useState('');
+  const [showLog, setShowLog] = useState(false);
+  const [error, setError] = useState('');
+
+  const loadWebhooks = async () => {
+    try {
+      setWebhooks(await invoke<Webhook[]>('get_webhooks'));
+      setDeliveries(await invoke<WebhookDelivery[]>('get_webhook_deliveries'));
+    } catch (e) {
+      console.error('Failed to load webhooks:', e);
+    }
+  };
+
+  useEffect(() => {
+    loadWebhooks();
+  }, []);
+
+  const handleAdd = async () => {
+    setError('');
+    try {
+      const webhook = {
+        id: '',
+        url: url.trim(),
+        events: events.split(',').map(e => e.trim()).filter(Boolean),
+        enabled: true,
+        created_at: '',
+      };
+      await invoke('save_webhook', { webhook, secret: secret || undefined });
+      setUrl('');
+      setEvents('');
+      setSecret('');
+      await loadWebhooks();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleToggle = async (webhook: Webhook) => {
+    try {
+      await invoke('save_webhook', { webhook: { ...webhook, enabled: !webhook.enabled } });
+      await loadWebhooks();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleRemove = async (id: string) => {
+    try {
+      await invoke('delete_webhook', { id });
+      await loadWebhooks();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const handleRetry = async (id: number) => {
+    try {
+      await invoke('retry_webhook_delivery', { id });
+      await loadWebhooks();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const urlOf = (webhookId: string) => webhooks.find(w => w.id === webhookId)?.url ?? 'removed webhook';
+
+  return (
+    <div className="settings-field">
+      <label>Webhooks</label>
+      {webhooks.map(webhook => (
+        <div key={webhook.id} className="server-row" title={webhook.signed ? 'Signed with a secret' : 'Not signed'}>
+          <input type="checkbox" checked={webhook.enabled} onChange={() => handleToggle(webhook)} />
+          <span className="server-url">{webhook.url}</span>
+          <span className="server-name">{webhook.events.length > 0 ? webhook.events.join(', ') : 'all events'}</span>
+          <button type="button" className="btn-icon" onClick={() => handleRemove(webhook.id)} title="Remove">✕</button>
+        </div>
+      ))}
+      <div className="server-add">
+        <input
+          type="text"
+          className="form-input"
+          placeholder="https://…"
+          value={url}
+          onChange={e => setUrl(e.target.value)}
+        />
+        <input
+          type="text"
+          className="form-input"
+          placeholder="Events, e.g. input_needed, github_action:failed (blank for all)"
+          value={events}
+          onChange={e => setEvents(e.target.value)}
+        />
+        <input
+          type="password"
+          className="form-input"
+          placeholder="HMAC secret (optional)"
+          value={secret}
+          onChange={e => setSecret(e.target.value)}
+        />
+        <button type="button" disabled={!url.trim()} onClick={handleAdd}>
+          Add
+        </button>
+      </div>
+      <span style={{ fontSize: '0.8em', opacity: 0.7 }}>
+        Status changes are POSTed as JSON, signed in the X-Loop-Signature-256 header when a secret is set; failed deliveries are retried for about 7 hours
+      </span>
+      {deliveries.length > 0 && (
+        <button type="button" className="btn-icon webhook-log-toggle" onClick={() => { setShowLog(!showLog); loadWebhooks(); }}>
+          {showLog ? 'Hide delivery log' : `Delivery log (${deliveries.length})`}
+        </button>
+      )}
+      {showLog && (
+        <div className="spend-summary">
+          {deliveries.map(delivery => (
+            <div
+              key={delivery.id}
+              className="spend-row"
+              title={`${urlOf(delivery.webhook_id)}${delivery.last_error ? `\n${delivery.last_error}` : ''}`}
+            >
+              <span>{new Date(delivery.created_at).toLocaleTimeString()} · {delivery.event}</span>
+              <span className={delivery.state === 'failed' ? 'hook-run-failed' : ''}>
+                {deliveryResult(delivery)}
+                {delivery.state === 'failed' && (
+                  <button type="button" className="btn-icon" onClick={() => handleRetry(delivery.id)} title="Retry">↻</button>
+                )}
+              </span>
+            </div>
+          ))}
+        </div>
+      )}
+      {error && <span className="form-error-inline">{error}</span>}
+    </div>
+  );
+}
@@ -3,6 +3,9 @@ export type ItemType =
   | 'github_action' 
   | 'github_pr' 
   | 'copilot_agent' 
+  | 'claude_session'
+  | 'codex_session'
+  | 'gemini_session'
   | 'cli_session'
   | 'opencode_session';
 
@@ -16,6 +19,7 @@ export type ItemStatus =
   | 'completed' 
   | 'closed'
   | 'failed'
+  | 'lost'
   | 'archived';
 
 export interface Item {
@@ -35,11 +39,40 @@ export interface Item {
   checked: boolean;
 }
 
+export interface OpenCodeServer {
+  name: string;
+  url: string;
+  healthy: boolean | null;
+  last_checked_at: string | null;
+  last_error: string | null;
+}
+
+export interface ItemEvent {
+  id: number;
+  item_id: string;
+  kind: string;
+  message: string;
+  created_at: string;
+}
+
+export interface OpenCodeQuestion {
+  id: string;
+  sessionID: string;
+  questions: {
+    question: string;
+    header: string;
+    options: { label: string; description: string }[];
+    multiple: boolean;
+  }[];
+  directory?: string;
+}
+
 export interface Credentials {
   slack_token?: string;
   github_token?: string;
   opencode_url?: string;
   opencode_password?: string;
+  github_webhook_secret?: string;
 }
 
 export interface Settings {
@@ -47,6 +80,135 @@ export interface Settings {
   notify_session_started: boolean;
   notify_session_ended: boolean;
   notify_input_needed: boolean;
+  shell_track_threshold_secs: number;
+  shell_track_ignore: string;
+  budget_session_tokens: number;
+  budget_session_cost: number;
+  budget_daily_tokens: number;
+  budget_daily_cost: number;
+  reminder_interval_minutes: number;
+  reminder_loud_sound: boolean;
+}
+
+export interface ItemReminder {
+  item_id: string;
+  reminded_at: string | null;
+  count: number;
+  snoozed_until: string | null;
+}
+
+export interface DailySpend {
+  directory: string;
+  sessions: number;
+  tokens: number;
+  cost: number;
+}
+
+export type LinkRelation = 'blocks' | 'produced_by' | 'related_to';
+
+/** Read as "source <relation> target", e.g. a PR produced_by an agent session. */
+export interface ItemLink {
+  source_item_id: string;
+  target_item_id: string;
+  relation: LinkRelation;
+  created_at: string;
+}
+
+/** Which status transitions a rule reacts to; unset fields match anything. */
+export interface RuleTrigger {
+  item_type?: string | null;
+  from_status?: string | null;
+  to_status?: string | null;
+  text?: string | null;
+}
+
+export type RuleCondition =
+  | { kind: 'metadata'; key: string; equals?: unknown }
+  | { kind: 'has_bound_todos'; value: boolean }
+  | { kind: 'time_window'; start_hour: number; end_hour: number };
+
+export type RuleAction =
+  | { type: 'archive' }
+  | { type: 'complete_bound_todos' }
+  | { type: 'create_todo'; title: string; bind?: boolean }
+  | { type: 'notify'; title?: string | null; body: string }
+  | { type: 'webhook'; url: string };
+
+export interface Rule {
+  id: string;
+  name: string;
+  enabled: boolean;
+  trigger: RuleTrigger;
+  conditions: RuleCondition[];
+  actions: RuleAction[];
+  created_at: string;
+}
+
+/** A rule that would have fired on a past status change. */
+export interface RuleMatch {
+  rule_id: string;
+  rule_name: string;
+  item_id: string;
+  item_title: string;
+  from: string;
+  to: string;
+  at: string;
+  actions: string[];
+}
+
+export interface HookRun {
+  id: number;
+  hook: string;
+  item_id: string;
+  old_status: string;
+  new_status: string;
+  started_at: string;
+  duration_ms: number;
+  exit_code: number | null;
+  timed_out: boolean;
+  stderr: string;
+}
+
+export interface HooksInfo {
+  dir: string;
+  hooks: string[];
+  runs: HookRun[];
+}
+
+export interface NotificationToggle {
+  category: string;
+  label: string;
+  enabled: boolean;
+}
+
+export interface NotificationRow {
+  item_type: ItemType;
+  toggles: NotificationToggle[];
+}
+
+export interface Webhook {
+  id: string;
+  url: string;
+  /** Event filters such as `input_needed` or `github_action:failed`; empty means all. */
+  events: string[];
+  signed: boolean;
+  enabled: boolean;
+  created_at: string;
+}
+
+export interface WebhookDelivery {
+  id: number;
+  webhook_id: string;
+  item_id: string;
+  event: string;
+  payload: string;
+  state: 'pending' | 'delivered' | 'failed';
+  attempts: number;
+  next_attempt_at: string | null;
+  response_status: number | null;
+  last_error: string | null;
+  created_at: string;
+  updated_at: string;
 }
 
 export interface AddItemRequest {